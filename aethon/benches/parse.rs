@@ -0,0 +1,34 @@
+use aethon::{Request, RequestRef};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+/// The request sent by `apollo/test.py`.
+const SMALL: &[u8] = b"1 GET /\nContent-Length: 11\n\nHello World";
+
+fn large() -> Vec<u8> {
+    let mut buffer = b"1 POST /some/longer/path/to/a/resource\n".to_vec();
+    for i in 0..32 {
+        buffer.extend_from_slice(format!("X-Header-{i}: some header value {i}\n").as_bytes());
+    }
+    buffer.extend_from_slice(b"Content-Length: 4096\n\n");
+    buffer.extend_from_slice(&[b'a'; 4096]);
+    buffer
+}
+
+fn parse(c: &mut Criterion) {
+    let large = large();
+    let mut group = c.benchmark_group("parse_request");
+
+    for (name, input) in [("small", SMALL), ("large", large.as_slice())] {
+        group.bench_with_input(BenchmarkId::new("owned", name), input, |b, input| {
+            b.iter(|| Request::try_from(black_box(input)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("borrowed", name), input, |b, input| {
+            b.iter(|| RequestRef::parse(black_box(input)).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
@@ -0,0 +1,112 @@
+use aethon::{Authorization, Host, Method, TypedHeader};
+use clap::Parser;
+use std::{net::SocketAddr, path::PathBuf};
+
+#[derive(Parser)]
+#[command(version, about = "Sends an Aethon request and prints the response's body", long_about = None)]
+#[command(
+    after_help = "Exit codes: 0 for 1xx-3xx responses, 4 for 4xx, 5 for 5xx, 1 if the request failed"
+)]
+pub struct Args {
+    /// Host to send the request to, e.g. example:8081
+    #[arg(value_parser = parse_host)]
+    pub host: Host,
+    /// Path of the request
+    #[arg(default_value = "/")]
+    pub path: String,
+    /// Method of the request
+    #[arg(short = 'X', long, default_value = "GET", value_parser = parse_method)]
+    pub method: Method,
+    /// Header as "Key: Value", can be repeated
+    #[arg(short = 'H', long = "header", value_parser = parse_header)]
+    pub headers: Vec<(String, String)>,
+    /// Body of the request, @FILE reads it from a file and @- from stdin
+    #[arg(short, long)]
+    pub data: Option<String>,
+    /// Saves the body to a file instead of printing it
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    /// Prints the request's and the response's heads to stderr
+    #[arg(short, long)]
+    pub verbose: bool,
+    /// Resolves the host through the Echo server at this address
+    #[arg(long)]
+    pub echo: Option<SocketAddr>,
+    /// How many redirects to follow
+    #[arg(long, default_value_t = 10)]
+    pub max_redirects: usize,
+    /// Limit of the whole exchange in seconds
+    #[arg(long)]
+    pub timeout: Option<u64>,
+    /// Sends the cookies stored in this file and saves the ones the response sets
+    #[arg(short = 'c', long)]
+    pub cookie_jar: Option<PathBuf>,
+    /// Basic credentials as "user:password", sent when the server asks for them
+    #[arg(short, long, value_parser = parse_user, conflicts_with = "bearer")]
+    pub user: Option<Authorization>,
+    /// Bearer token, sent when the server asks for it
+    #[arg(long, value_parser = parse_bearer)]
+    pub bearer: Option<Authorization>,
+}
+
+fn parse_host(s: &str) -> Result<Host, String> {
+    Host::decode(s).map_err(|e| e.to_string())
+}
+
+fn parse_method(s: &str) -> Result<Method, String> {
+    s.to_ascii_uppercase()
+        .parse()
+        .map_err(|e: aethon::Error| e.to_string())
+}
+
+fn parse_header(s: &str) -> Result<(String, String), String> {
+    match s.split_once(':') {
+        Some((key, value)) => Ok((key.trim().to_string(), value.trim().to_string())),
+        None => Err("expected \"Key: Value\"".to_string()),
+    }
+}
+
+fn parse_user(s: &str) -> Result<Authorization, String> {
+    match s.split_once(':') {
+        Some((user, password)) => Ok(Authorization::basic(user, password)),
+        None => Err("expected \"user:password\"".to_string()),
+    }
+}
+
+fn parse_bearer(s: &str) -> Result<Authorization, String> {
+    Ok(Authorization::bearer(s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_args() {
+        let args = Args::parse_from([
+            "aethon",
+            "example:9000",
+            "/upload",
+            "-X",
+            "post",
+            "-H",
+            "Accept: text/html",
+            "-v",
+            "-u",
+            "ali:open:sesame",
+        ]);
+
+        // Tests
+        assert_eq!(Host::new("example", Some(9000)), args.host);
+        assert_eq!("/upload", args.path);
+        assert_eq!(Method::POST, args.method);
+        assert_eq!(
+            vec![("Accept".to_string(), "text/html".to_string())],
+            args.headers
+        );
+        assert!(args.verbose);
+        assert_eq!(Some(Authorization::basic("ali", "open:sesame")), args.user);
+        assert!(Args::try_parse_from(["aethon", "example", "-u", "ali"]).is_err());
+        assert!(Args::try_parse_from(["aethon", "example", "-H", "no colon"]).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use aethon::{Client, CookieJar, Request, Resolver, Response, Status};
+use args::Args;
+use clap::Parser;
+use std::{
+    error::Error,
+    fs,
+    io::{self, Read, Write},
+    process::ExitCode,
+    sync::Arc,
+    time::Duration,
+};
+
+mod args;
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+    match run(args).await {
+        Ok(status) => exit_code(status),
+        Err(e) => {
+            eprintln!("aethon: {e}");
+            ExitCode::from(1)
+        }
+    }
+}
+
+async fn run(args: Args) -> Result<Status, Box<dyn Error>> {
+    let mut client = Client::builder().max_redirects(args.max_redirects);
+    if let Some(echo) = args.echo {
+        client = client.resolver(Resolver::Echo(echo));
+    }
+    if let Some(timeout) = args.timeout {
+        client = client.timeout(Duration::from_secs(timeout));
+    }
+    let jar = match &args.cookie_jar {
+        Some(path) if path.exists() => Arc::new(CookieJar::load(path)?),
+        _ => Arc::new(CookieJar::new()),
+    };
+    client = client.cookie_jar(Arc::clone(&jar));
+    if let Some(credentials) = args.user.or(args.bearer) {
+        client = client.credentials(args.host.host(), credentials);
+    }
+
+    let mut req = Request::builder()
+        .method(args.method)
+        .path(args.path)
+        .typed_header(args.host)
+        .body(read_body(args.data.as_deref())?);
+    for (key, value) in args.headers {
+        req = req.header(key, value);
+    }
+    let req = req.build()?;
+
+    if args.verbose {
+        print_head("> ", &request_head(&req));
+    }
+    let res = client.build().send(req).await?;
+    if args.verbose {
+        print_head("< ", &response_head(&res));
+    }
+    if let Some(path) = &args.cookie_jar {
+        jar.save(path)?;
+    }
+
+    match args.output {
+        Some(path) => fs::write(path, res.body())?,
+        None => io::stdout().write_all(res.body())?,
+    }
+    Ok(res.status())
+}
+
+/// Reads the body given to `--data`.
+fn read_body(data: Option<&str>) -> io::Result<Vec<u8>> {
+    match data {
+        None => Ok(Vec::new()),
+        Some("@-") => {
+            let mut body = Vec::new();
+            io::stdin().read_to_end(&mut body)?;
+            Ok(body)
+        }
+        Some(data) => match data.strip_prefix('@') {
+            Some(path) => fs::read(path),
+            None => Ok(data.as_bytes().to_vec()),
+        },
+    }
+}
+
+/// The start line and the headers of `req`.
+fn request_head(req: &Request) -> String {
+    format!(
+        "{} {} {}\n{}",
+        req.version(),
+        req.method(),
+        req.path(),
+        req.headers()
+    )
+}
+
+/// The status line with its reason and the headers of `res`.
+fn response_head(res: &Response) -> String {
+    let mut head = format!("{} {}", res.version(), res.status());
+    if let Some(reason) = res.status().canonical_reason() {
+        head = format!("{} {}", head, reason);
+    }
+    head.push('\n');
+    head.push_str(&res.headers().to_string());
+    head
+}
+
+fn print_head(prefix: &str, head: &str) {
+    for line in head.lines().filter(|l| !l.is_empty()) {
+        eprintln!("{prefix}{line}");
+    }
+    eprintln!("{}", prefix.trim_end());
+}
+
+fn exit_code(status: Status) -> ExitCode {
+    match status.code() {
+        400..=499 => ExitCode::from(4),
+        500..=599 => ExitCode::from(5),
+        _ => ExitCode::SUCCESS,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_code() {
+        // Tests
+        assert_eq!(ExitCode::SUCCESS, exit_code(Status::OK));
+        assert_eq!(ExitCode::SUCCESS, exit_code(Status::NotModified));
+        assert_eq!(ExitCode::from(4), exit_code(Status::NotFound));
+        assert_eq!(ExitCode::from(5), exit_code(Status::VersionNotSupported));
+    }
+
+    #[test]
+    fn test_response_head() {
+        let res = Response::builder().header("a", "b").build().unwrap();
+
+        // Tests
+        assert_eq!("1 200 OK\na: b\n", response_head(&res));
+        assert_eq!(b"Hi", &read_body(Some("Hi")).unwrap()[..]);
+    }
+}
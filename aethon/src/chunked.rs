@@ -0,0 +1,316 @@
+//! Chunked transfer encoding, for bodies whose length isn't known in advance.
+//!
+//! Every chunk is its length in hex, `\n`, the data and `\n`. A chunk of length `0` ends the body,
+//! it's followed by optional trailers and an empty line:
+//!
+//! ```text
+//! 5\nHello\n6\n World\n0\nChecksum: 1234\n\n
+//! ```
+
+use super::{
+    error::{ParseError, ParseErrorKind},
+    headers::HeadersRef,
+    parser::{self, HeadLimits, ParseOptions},
+    Headers,
+};
+
+/// Value of `Transfer-Encoding` for chunked bodies.
+pub(crate) const CHUNKED: &str = "chunked";
+
+/// Maximal length of a chunk size line, 16 hex digits are enough for any `u64`.
+const MAX_SIZE_LINE: usize = 64;
+
+/// Writes one chunk, empty data is skipped since a chunk of length `0` ends the body.
+pub(crate) fn encode_chunk(data: &[u8], dst: &mut Vec<u8>) {
+    if data.is_empty() {
+        return;
+    }
+
+    dst.extend_from_slice(format!("{:x}\n", data.len()).as_bytes());
+    dst.extend_from_slice(data);
+    dst.push(b'\n');
+}
+
+/// Writes the last chunk followed by `trailers`.
+pub(crate) fn encode_end(trailers: &Headers, dst: &mut Vec<u8>) {
+    dst.extend_from_slice(format!("0\n{}\n", trailers).as_bytes());
+}
+
+/// Encodes a whole body as one chunk.
+pub(crate) fn encode(body: &[u8], trailers: &Headers, dst: &mut Vec<u8>) {
+    encode_chunk(body, dst);
+    encode_end(trailers, dst);
+}
+
+/// A piece of a chunked body returned by [`ChunkedDecoder::decode`].
+#[derive(Debug, PartialEq)]
+pub enum Chunk<'a> {
+    /// Data of the body, a chunk may be returned in several pieces.
+    Data(&'a [u8]),
+    /// The body has ended, with these trailers.
+    End(HeadersRef<'a>),
+}
+
+/// Incremental decoder of a chunked body.
+///
+/// Errors are positioned in the `buf` of the failing call.
+///
+/// ```
+/// use aethon::{Chunk, ChunkedDecoder};
+///
+/// let mut decoder = ChunkedDecoder::new();
+/// let (consumed, chunk) = decoder.decode(b"5\nHello\n0\n\n").unwrap();
+/// assert_eq!((7, Some(Chunk::Data(b"Hello"))), (consumed, chunk));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ChunkedDecoder {
+    state: State,
+    options: ParseOptions,
+    /// Length of the data announced so far, checked against the body limit.
+    announced: u64,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum State {
+    /// Reading the size line of the next chunk.
+    #[default]
+    Size,
+    /// Reading the data of a chunk.
+    Data(u64),
+    /// Reading the `\n` after the data.
+    DataEnd,
+    /// Reading the trailers after the last chunk.
+    Trailers,
+    Done,
+}
+
+impl ChunkedDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A decoder parsing as strictly as `options` say, `\r\n` line endings are only accepted
+    /// when lenient.
+    pub fn with_options(options: ParseOptions) -> Self {
+        Self {
+            state: State::default(),
+            options,
+            announced: 0,
+        }
+    }
+
+    /// Whether the last chunk and the trailers were decoded.
+    pub fn is_done(&self) -> bool {
+        self.state == State::Done
+    }
+
+    /// Decodes the next piece of the body at the start of `buf`.
+    ///
+    /// Returns the number of consumed bytes, which the caller must drop from `buf` before the
+    /// next call, and the decoded piece if there was enough data for one.
+    pub fn decode<'a>(&mut self, buf: &'a [u8]) -> Result<(usize, Option<Chunk<'a>>), ParseError> {
+        let mut consumed = 0;
+        let error = |kind, offset| ParseError::at(kind, buf, offset);
+
+        loop {
+            let rest = &buf[consumed..];
+            match self.state {
+                State::Size => {
+                    let Some(i) = rest.iter().position(|&b| b == b'\n') else {
+                        if rest.len() > MAX_SIZE_LINE {
+                            return Err(error(ParseErrorKind::InvalidChunkSize, consumed));
+                        }
+                        return Ok((consumed, None));
+                    };
+
+                    let invalid = || error(ParseErrorKind::InvalidChunkSize, consumed);
+                    let line = std::str::from_utf8(&rest[..i]).map_err(|_| invalid())?;
+                    let line = self
+                        .options
+                        .strip_cr(line)
+                        .map_err(|e| e.shift(buf, consumed))?;
+                    // Extensions after ; are ignored
+                    let size = line.split(';').next().unwrap_or_default();
+                    let size = match self.options.is_strict() {
+                        true => size,
+                        false => size.trim(),
+                    };
+                    if size.is_empty() || !size.bytes().all(|b| b.is_ascii_hexdigit()) {
+                        return Err(invalid());
+                    }
+                    let size = u64::from_str_radix(size, 16).map_err(|_| invalid())?;
+                    self.announced = self.announced.saturating_add(size);
+                    if self.announced > self.options.max_body_len as u64 {
+                        return Err(error(ParseErrorKind::BodyTooLarge, consumed));
+                    }
+
+                    consumed += i + 1;
+                    self.state = match size {
+                        0 => State::Trailers,
+                        size => State::Data(size),
+                    };
+                }
+                State::Data(remaining) => {
+                    if rest.is_empty() {
+                        return Ok((consumed, None));
+                    }
+
+                    let n = rest
+                        .len()
+                        .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+                    self.state = match remaining - n as u64 {
+                        0 => State::DataEnd,
+                        remaining => State::Data(remaining),
+                    };
+                    return Ok((consumed + n, Some(Chunk::Data(&rest[..n]))));
+                }
+                State::DataEnd => {
+                    let len = match rest {
+                        [b'\n', ..] => 1,
+                        [b'\r', b'\n', ..] if !self.options.is_strict() => 2,
+                        [b'\r'] if !self.options.is_strict() => return Ok((consumed, None)),
+                        [_, ..] => return Err(error(ParseErrorKind::MissingChunkEnd, consumed)),
+                        [] => return Ok((consumed, None)),
+                    };
+                    consumed += len;
+                    self.state = State::Size;
+                }
+                State::Trailers => {
+                    let (block, len) = match rest {
+                        [b'\n', ..] => (&rest[..0], 1),
+                        [b'\r', b'\n', ..] if !self.options.is_strict() => (&rest[..0], 2),
+                        [b'\r'] if !self.options.is_strict() => return Ok((consumed, None)),
+                        [_, ..] => match parser::find_head_end(rest, &self.options) {
+                            Some((i, end)) => (&rest[..i], i + end),
+                            None => {
+                                HeadLimits::default()
+                                    .check(rest, &self.options)
+                                    .map_err(|e| e.shift(buf, consumed))?;
+                                return Ok((consumed, None));
+                            }
+                        },
+                        [] => return Ok((consumed, None)),
+                    };
+
+                    let block = std::str::from_utf8(block).map_err(|e| {
+                        error(ParseErrorKind::InvalidUtf8, consumed + e.valid_up_to())
+                    })?;
+                    let trailers = HeadersRef::parse(block, &self.options)
+                        .map_err(|e| e.shift(buf, consumed))?;
+                    self.state = State::Done;
+                    return Ok((consumed + len, Some(Chunk::End(trailers))));
+                }
+                State::Done => return Err(error(ParseErrorKind::BodyEnded, consumed)),
+            }
+        }
+    }
+}
+
+/// A chunked body decoded at once.
+#[derive(Debug)]
+pub(crate) struct Decoded<'a> {
+    pub body: Vec<u8>,
+    pub trailers: HeadersRef<'a>,
+}
+
+/// Decodes a whole chunked body at the start of `buf`, returns `Ok(None)` if it's incomplete.
+pub(crate) fn decode(
+    buf: &[u8], mut decoder: ChunkedDecoder,
+) -> Result<Option<Decoded<'_>>, ParseError> {
+    let mut body = Vec::new();
+    let mut len = 0;
+
+    loop {
+        match decoder.decode(&buf[len..]).map_err(|e| e.shift(buf, len))? {
+            (n, Some(Chunk::Data(data))) => {
+                body.extend_from_slice(data);
+                len += n;
+            }
+            (_, Some(Chunk::End(trailers))) => return Ok(Some(Decoded { body, trailers })),
+            (_, None) => return Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let mut dst = Vec::new();
+        encode_chunk(b"Hello", &mut dst);
+        encode_chunk(b"", &mut dst);
+        encode_chunk(&[b'a'; 26], &mut dst);
+        encode_end(&[("Checksum", "1234")].into_iter().collect(), &mut dst);
+
+        // Tests
+        let expected = format!("5\nHello\n1a\n{}\n0\nChecksum: 1234\n\n", "a".repeat(26));
+        assert_eq!(expected.as_bytes(), dst);
+    }
+
+    #[test]
+    fn test_decode_whole_body() {
+        let decoded = decode(
+            b"5\nHello\n6;ext=1\n World\n0\nA: 1\nB: 2\n\nnext packet",
+            ChunkedDecoder::new(),
+        )
+        .unwrap()
+        .unwrap();
+
+        // Tests
+        assert_eq!(b"Hello World", &decoded.body[..]);
+        assert_eq!(Some("2"), decoded.trailers.get("b"));
+    }
+
+    #[test]
+    fn test_decode_byte_by_byte() {
+        let encoded = b"5\nHello\n0\n\n";
+        let mut decoder = ChunkedDecoder::new();
+        let mut buffer = Vec::new();
+        let mut body = Vec::new();
+
+        for &b in encoded {
+            buffer.push(b);
+            let (consumed, chunk) = decoder.decode(&buffer).unwrap();
+            if let Some(Chunk::Data(data)) = chunk {
+                body.extend_from_slice(data);
+            }
+            buffer.drain(..consumed);
+        }
+
+        // Tests
+        assert!(decoder.is_done());
+        assert!(buffer.is_empty());
+        assert_eq!(b"Hello", &body[..]);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        // Tests
+        assert!(decode(b"x\nHello\n0\n\n", ChunkedDecoder::new()).is_err());
+        assert!(decode(b"5\nHello!0\n\n", ChunkedDecoder::new()).is_err());
+        assert!(decode(b"ffffffffffffffffff\n", ChunkedDecoder::new()).is_err());
+        assert!(decode(&[b'1'; 100], ChunkedDecoder::new()).is_err());
+        assert!(decode(b"5\nHel", ChunkedDecoder::new()).unwrap().is_none());
+
+        let error = decode(b"5\nHello\n3\nabc\nz\n", ChunkedDecoder::new()).unwrap_err();
+        assert_eq!(ParseErrorKind::InvalidChunkSize, error.kind());
+        assert_eq!((14, 5), (error.offset(), error.line()));
+    }
+
+    #[test]
+    fn test_decode_crlf() {
+        let encoded = b"5\r\nHello\r\n0\r\nA: 1\r\n\r\n";
+        let decoded = decode(encoded, ChunkedDecoder::new()).unwrap().unwrap();
+        let strict = ChunkedDecoder::with_options(ParseOptions::strict());
+
+        // Tests
+        assert_eq!(b"Hello", &decoded.body[..]);
+        assert_eq!(Some("1"), decoded.trailers.get("a"));
+        assert_eq!(
+            ParseErrorKind::CarriageReturn,
+            decode(encoded, strict).unwrap_err().kind()
+        );
+    }
+}
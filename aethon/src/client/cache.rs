@@ -0,0 +1,413 @@
+use crate::{
+    Authorization, CacheControl, Date, ETag, Expires, Headers, IfModifiedSince, IfNoneMatch,
+    LastModified, Method, Request, Response, Status, TypedHeader, Url,
+};
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+/// Headers a request only sends when it's conditional.
+const CONDITIONAL: [&str; 4] = [
+    IfNoneMatch::NAME,
+    IfModifiedSince::NAME,
+    "If-Match",
+    "If-Unmodified-Since",
+];
+
+/// A private cache of responses to `GET` requests, following RFC 9111.
+///
+/// Fresh responses are answered without the network, stale ones are revalidated with their
+/// `ETag` or `Last-Modified` and reused when the server answers `304 Not Modified`. Like
+/// [`CookieJar`](crate::CookieJar), a cache can be shared by several clients, so responses to
+/// requests with `Authorization` are only stored when they're `public`, `s-maxage` or
+/// `must-revalidate`.
+#[derive(Debug)]
+pub struct Cache {
+    inner: Mutex<Inner>,
+    max_entries: usize,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// A URL has an entry per variant selected by `Vary`.
+    entries: HashMap<Url, Vec<Entry>>,
+    /// Incremented on every use, the least recently used entry is evicted first.
+    clock: u64,
+}
+
+/// The request headers named by `Vary` and their values.
+type Vary = Vec<(Box<str>, Option<Box<str>>)>;
+
+#[derive(Debug)]
+struct Entry {
+    response: Response,
+    vary: Vary,
+    /// When the response was received or last revalidated.
+    received: SystemTime,
+    used: u64,
+}
+
+impl Entry {
+    fn matches(&self, req: &Request) -> bool {
+        self.vary
+            .iter()
+            .all(|(name, value)| req.headers().get(name) == value.as_deref())
+    }
+
+    fn is_fresh(&self, req: &Request, now: SystemTime) -> bool {
+        let req_cc = cache_control(req.headers());
+        let res_cc = cache_control(self.response.headers());
+        if req_cc.no_cache || res_cc.no_cache {
+            return false;
+        }
+
+        let age = now.duration_since(self.received).unwrap_or_default();
+        let lifetime = freshness_lifetime(&self.response, self.received).unwrap_or_default();
+        age < lifetime && req_cc.max_age.is_none_or(|max_age| age <= max_age)
+    }
+
+    /// Returns `req` asking the server whether the stored response is still current.
+    fn revalidation(&self, req: &Request) -> Option<Request> {
+        let headers = self.response.headers();
+        let etag = headers.typed_get::<ETag>().ok().flatten();
+        let last_modified = headers.typed_get::<LastModified>().ok().flatten();
+        if etag.is_none() && last_modified.is_none() {
+            return None;
+        }
+
+        let mut req = req.clone();
+        if let Some(etag) = etag {
+            req.headers_mut()
+                .typed_insert(IfNoneMatch::Tags(vec![etag]));
+        }
+        if let Some(LastModified(time)) = last_modified {
+            req.headers_mut().typed_insert(IfModifiedSince(time));
+        }
+        Some(req)
+    }
+}
+
+/// What the cache can do for a request.
+pub(crate) enum Lookup {
+    /// The stored response can be used as is.
+    Fresh(Response),
+    /// The stored response has to be revalidated with this conditional request.
+    Stale(Request),
+    Miss,
+}
+
+impl Cache {
+    /// Creates a cache keeping up to 256 responses.
+    pub fn new() -> Self {
+        Self::with_capacity(256)
+    }
+
+    /// Creates a cache keeping up to `max_entries` responses.
+    pub fn with_capacity(max_entries: usize) -> Self {
+        Self {
+            inner: Mutex::default(),
+            max_entries,
+        }
+    }
+
+    /// Number of stored responses, including stale ones.
+    pub fn len(&self) -> usize {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.entries.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.entries.clear();
+    }
+
+    pub(crate) fn lookup(&self, url: &Url, req: &Request) -> Lookup {
+        // Conditional requests are the caller's own business
+        if *req.method() != Method::GET || CONDITIONAL.iter().any(|h| req.headers().contains(h)) {
+            return Lookup::Miss;
+        }
+
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.clock += 1;
+        let clock = inner.clock;
+        let Some(entry) = inner
+            .entries
+            .get_mut(url)
+            .and_then(|entries| entries.iter_mut().find(|e| e.matches(req)))
+        else {
+            return Lookup::Miss;
+        };
+
+        entry.used = clock;
+        if entry.is_fresh(req, SystemTime::now()) {
+            return Lookup::Fresh(entry.response.clone());
+        }
+        match entry.revalidation(req) {
+            Some(conditional) => Lookup::Stale(conditional),
+            None => Lookup::Miss,
+        }
+    }
+
+    /// Takes the response the server sent to `req`, the request as the caller made it, and
+    /// returns the one to give the caller.
+    pub(crate) fn update(&self, url: &Url, req: &Request, res: Response) -> Response {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+
+        // Changing the content makes the stored copies outdated
+        if !req.method().is_safe() && res.status().code() < 400 {
+            inner.entries.remove(url);
+            return res;
+        }
+        if *req.method() != Method::GET || CONDITIONAL.iter().any(|h| req.headers().contains(h)) {
+            return res;
+        }
+
+        let now = SystemTime::now();
+        inner.clock += 1;
+        let clock = inner.clock;
+        let entries = inner.entries.entry(url.clone()).or_default();
+
+        if res.status() == Status::NotModified {
+            let Some(entry) = entries.iter_mut().find(|e| e.matches(req)) else {
+                return res;
+            };
+            for (name, value) in res.headers().iter() {
+                if !name.eq_ignore_ascii_case(Headers::CONTENT_LENGTH)
+                    && !name.eq_ignore_ascii_case(Headers::TRANSFER_ENCODING)
+                {
+                    entry.response.headers_mut().insert(name, value);
+                }
+            }
+            entry.received = now;
+            entry.used = clock;
+            return entry.response.clone();
+        }
+
+        entries.retain(|e| !e.matches(req));
+        if let Some(vary) = vary(req, &res).filter(|_| is_storable(req, &res, now)) {
+            entries.push(Entry {
+                response: res.clone(),
+                vary,
+                received: now,
+                used: clock,
+            });
+        }
+        if entries.is_empty() {
+            inner.entries.remove(url);
+        }
+        inner.evict(self.max_entries);
+        res
+    }
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Inner {
+    /// Drops the least recently used entries until there are at most `max`.
+    fn evict(&mut self, max: usize) {
+        let mut len: usize = self.entries.values().map(Vec::len).sum();
+        while len > max {
+            let oldest = self
+                .entries
+                .iter()
+                .flat_map(|(url, entries)| entries.iter().map(move |e| (e.used, url)))
+                .min_by_key(|(used, _)| *used)
+                .map(|(used, url)| (used, url.clone()));
+            let Some((used, url)) = oldest else {
+                return;
+            };
+
+            if let Some(entries) = self.entries.get_mut(&url) {
+                entries.retain(|e| e.used != used);
+                if entries.is_empty() {
+                    self.entries.remove(&url);
+                }
+            }
+            len -= 1;
+        }
+    }
+}
+
+fn cache_control(headers: &Headers) -> CacheControl {
+    headers
+        .typed_get::<CacheControl>()
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+/// The request headers `res` varies on, `None` if it can't be stored because of `Vary: *`.
+fn vary(req: &Request, res: &Response) -> Option<Vary> {
+    let mut vary = Vec::new();
+    for name in res.headers().get_all("Vary").flat_map(|v| v.split(',')) {
+        let name = name.trim();
+        match name {
+            "*" => return None,
+            "" => {}
+            _ => vary.push((name.into(), req.headers().get(name).map(Into::into))),
+        }
+    }
+    Some(vary)
+}
+
+/// Whether `res` can be stored and later reused or revalidated.
+fn is_storable(req: &Request, res: &Response, now: SystemTime) -> bool {
+    let res_cc = cache_control(res.headers());
+    if cache_control(req.headers()).no_store || res_cc.no_store {
+        return false;
+    }
+    // The cache may be shared, so responses to credentials are only stored when they say so
+    let shareable = res_cc.public || res_cc.s_max_age.is_some() || res_cc.must_revalidate;
+    if req.headers().contains(Authorization::NAME) && !shareable {
+        return false;
+    }
+    let headers = res.headers();
+    let has_validator = headers.contains(ETag::NAME) || headers.contains(LastModified::NAME);
+    let understood = matches!(
+        res.status().code(),
+        200 | 203 | 204 | 300 | 301 | 308 | 404 | 410
+    );
+    understood && (has_validator || freshness_lifetime(res, now).is_some())
+}
+
+/// How long `res` stays fresh after it was received, `None` without any hint.
+fn freshness_lifetime(res: &Response, received: SystemTime) -> Option<Duration> {
+    let headers = res.headers();
+    if let Some(max_age) = cache_control(headers).max_age {
+        return Some(max_age);
+    }
+
+    let date = match headers.typed_get::<Date>() {
+        Ok(Some(Date(date))) => date,
+        _ => received,
+    };
+    if headers.contains(Expires::NAME) {
+        // An invalid date means the response is already stale
+        return Some(match headers.typed_get::<Expires>() {
+            Ok(Some(Expires(expires))) => expires.duration_since(date).unwrap_or_default(),
+            _ => Duration::ZERO,
+        });
+    }
+
+    // Without explicit freshness, a tenth of the time since the last change
+    match headers.typed_get::<LastModified>() {
+        Ok(Some(LastModified(modified))) => date.duration_since(modified).ok().map(|d| d / 10),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get(path: &str) -> (Url, Request) {
+        let url: Url = format!("aethon://example{}", path).parse().unwrap();
+        let req = Request::builder().url(&url).build().unwrap();
+        (url, req)
+    }
+
+    fn response(cache_control: &str, etag: Option<&str>) -> Response {
+        let mut res = Response::builder().body("body").build().unwrap();
+        if !cache_control.is_empty() {
+            res.headers_mut().insert("Cache-Control", cache_control);
+        }
+        if let Some(etag) = etag {
+            res.headers_mut().typed_insert(ETag::strong(etag));
+        }
+        res
+    }
+
+    #[test]
+    fn test_fresh_and_stale() {
+        let cache = Cache::new();
+        let (url, req) = get("/fresh");
+        let (stale_url, stale_req) = get("/stale");
+        cache.update(&url, &req, response("max-age=60", None));
+        cache.update(&stale_url, &stale_req, response("no-cache", Some("v1")));
+
+        // Tests
+        assert!(matches!(cache.lookup(&url, &req), Lookup::Fresh(res) if res.body() == b"body"));
+        let Lookup::Stale(conditional) = cache.lookup(&stale_url, &stale_req) else {
+            panic!("expected a revalidation");
+        };
+        assert_eq!(Some("\"v1\""), conditional.headers().get("If-None-Match"));
+
+        let not_modified = Response::builder()
+            .status(Status::NotModified)
+            .header("Cache-Control", "max-age=60")
+            .build()
+            .unwrap();
+        let res = cache.update(&stale_url, &stale_req, not_modified);
+        assert_eq!(b"body", res.body());
+        assert_eq!(Some("max-age=60"), res.headers().get("Cache-Control"));
+        assert!(matches!(
+            cache.lookup(&stale_url, &stale_req),
+            Lookup::Fresh(_)
+        ));
+    }
+
+    #[test]
+    fn test_not_stored() {
+        let cache = Cache::new();
+        let (url, req) = get("/");
+
+        cache.update(&url, &req, response("no-store, max-age=60", None));
+        cache.update(&url, &req, response("", None));
+        let mut vary_all = response("max-age=60", None);
+        vary_all.headers_mut().insert("Vary", "*");
+        cache.update(&url, &req, vary_all);
+        let mut authorized = req.clone();
+        authorized
+            .headers_mut()
+            .typed_insert(Authorization::bearer("token"));
+        cache.update(&url, &authorized, response("max-age=60", None));
+
+        // Tests
+        assert!(cache.is_empty());
+        cache.update(&url, &authorized, response("public, max-age=60", None));
+        assert!(matches!(cache.lookup(&url, &req), Lookup::Fresh(_)));
+    }
+
+    #[test]
+    fn test_vary_and_invalidation() {
+        let cache = Cache::with_capacity(2);
+        let (url, req) = get("/");
+        let mut fr = req.clone();
+        fr.headers_mut().insert("Accept-Language", "fr");
+        let mut res = response("max-age=60", None);
+        res.headers_mut().insert("Vary", "Accept-Language");
+
+        cache.update(&url, &fr, res);
+        // Tests
+        assert!(matches!(cache.lookup(&url, &fr), Lookup::Fresh(_)));
+        assert!(matches!(cache.lookup(&url, &req), Lookup::Miss));
+
+        let post = Request::builder()
+            .method(Method::POST)
+            .url(&url)
+            .build()
+            .unwrap();
+        cache.update(&url, &post, response("", None));
+        assert!(cache.is_empty());
+
+        for path in ["/a", "/b", "/c"] {
+            let (url, req) = get(path);
+            cache.update(&url, &req, response("max-age=60", None));
+        }
+        assert_eq!(2, cache.len());
+        assert!(matches!(
+            cache.lookup(&get("/a").0, &get("/a").1),
+            Lookup::Miss
+        ));
+    }
+}
@@ -0,0 +1,949 @@
+//! An async client sending requests over pooled persistent connections.
+
+mod cache;
+mod pool;
+mod resolve;
+mod transport;
+
+pub use cache::Cache;
+pub use resolve::Resolver;
+
+use self::{
+    cache::Lookup,
+    pool::{Key, Pool},
+    transport::Transport,
+};
+#[cfg(feature = "tls")]
+use super::{tls::TlsConnector, TlsError, TlsRoots};
+use super::{
+    AcceptEncoding, Authorization, ClientConnection, CodecError, Coding, CookieJar, Cookies, Error,
+    Headers, Host, Location, Method, Range, Request, Response, Status, TypedHeader, Url,
+    WwwAuthenticate,
+};
+use std::{fmt, io, sync::Arc, time::Duration};
+use tokio::{
+    net::TcpStream,
+    time::{timeout, timeout_at, Instant},
+};
+
+/// Sends requests to the host in their `Host` header, reusing connections to the same address.
+///
+/// Cloning a client is cheap, the clones share their pool.
+///
+/// ```no_run
+/// # async fn get() -> Result<(), aethon::ClientError> {
+/// use aethon::{Client, Host, Request};
+///
+/// let client = Client::new();
+/// let req = Request::builder()
+///     .path("/index.html")
+///     .typed_header(Host::new("example", None))
+///     .build()
+///     .unwrap();
+/// let res = client.send(req).await?;
+/// println!("{}", res.text_lossy());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Client {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    connect_timeout: Duration,
+    read_timeout: Duration,
+    timeout: Option<Duration>,
+    max_redirects: usize,
+    max_decompressed_len: usize,
+    resolver: Resolver,
+    pool: Pool,
+    cookie_jar: Option<Arc<CookieJar>>,
+    cache: Option<Arc<Cache>>,
+    credentials: Vec<(Box<str>, Authorization)>,
+    #[cfg(feature = "tls")]
+    tls: TlsConnector,
+}
+
+impl Client {
+    /// Port used when the `Host` header has none.
+    pub const DEFAULT_PORT: u16 = Url::DEFAULT_PORT;
+
+    pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    /// Sends a `GET` request to `url`, over TLS if it's `aethons://`.
+    pub async fn get(&self, url: &Url) -> Result<Response, ClientError> {
+        let req = Request::builder().url(url).build()?;
+        self.send_with_timeout(req, url.is_secure()).await
+    }
+
+    /// Sends a request and returns its response, following redirects.
+    pub async fn send(&self, req: Request) -> Result<Response, ClientError> {
+        self.send_with_timeout(req, false).await
+    }
+
+    /// Sends a request over TLS, as for an `aethons://` URL.
+    #[cfg(feature = "tls")]
+    pub async fn send_tls(&self, req: Request) -> Result<Response, ClientError> {
+        self.send_with_timeout(req, true).await
+    }
+
+    async fn send_with_timeout(&self, req: Request, secure: bool) -> Result<Response, ClientError> {
+        match self.inner.timeout {
+            Some(total) => timeout(total, self.follow(req, secure))
+                .await
+                .map_err(|_| ClientError::Timeout)?,
+            None => self.follow(req, secure).await,
+        }
+    }
+
+    async fn follow(&self, mut req: Request, mut secure: bool) -> Result<Response, ClientError> {
+        let mut redirects = 0;
+        loop {
+            let res = self
+                .send_cached(self.with_cookies(&req, secure), secure)
+                .await?;
+            if let (Some(jar), Ok(url)) = (&self.inner.cookie_jar, url(&req, secure)) {
+                jar.store_response(&url, &res);
+            }
+            if let Some(authorized) = self.authorize(&req, &res) {
+                req = authorized;
+                continue;
+            }
+            let next = match self.inner.max_redirects {
+                0 => None,
+                _ => redirect(&req, &res, secure)?,
+            };
+            let Some((next, next_secure)) = next else {
+                return Ok(res);
+            };
+
+            if redirects == self.inner.max_redirects {
+                return Err(ClientError::TooManyRedirects);
+            }
+            redirects += 1;
+            req = next;
+            secure = next_secure;
+        }
+    }
+
+    /// Sends a request, asking for the codings this build supports unless `req` names its own
+    /// or has no response body, and decompressing the response.
+    async fn send_once(&self, mut req: Request, secure: bool) -> Result<Response, ClientError> {
+        let negotiate = !Coding::SUPPORTED.is_empty()
+            && req.method().allows_response_body()
+            && !req.headers().contains(AcceptEncoding::NAME)
+            && req.headers().typed_get::<Range>().ok().flatten().is_none();
+        if negotiate {
+            req.headers_mut().typed_insert(AcceptEncoding::supported());
+        }
+        let res = self.send_uncompressed(req, secure).await?;
+        match negotiate && res.status().allows_body() {
+            true => Ok(res.decompress_with_limit(self.inner.max_decompressed_len)?),
+            false => Ok(res),
+        }
+    }
+
+    async fn send_uncompressed(&self, req: Request, secure: bool) -> Result<Response, ClientError> {
+        let host = req
+            .headers()
+            .typed_get::<Host>()?
+            .ok_or(ClientError::NoHost)?;
+        let port = match secure {
+            true => host.port().unwrap_or(Url::DEFAULT_SECURE_PORT),
+            false => host.port().unwrap_or(Self::DEFAULT_PORT),
+        };
+        // Resolving, connecting and the TLS handshake share one deadline
+        let mut deadline = Instant::now() + self.inner.connect_timeout;
+        let addr = timeout_at(deadline, self.inner.resolver.resolve(host.host(), port))
+            .await
+            .map_err(|_| ClientError::ConnectTimeout)??;
+        let key = Key {
+            addr,
+            tls: secure.then(|| host.host().into()),
+        };
+
+        if let Some(mut conn) = self.inner.pool.take(&key) {
+            let started = Instant::now();
+            match self.exchange(&mut conn, req.clone()).await {
+                Ok(res) => {
+                    self.inner.pool.put(key, conn);
+                    return Ok(res);
+                }
+                // The server may have closed the idle connection in the meantime, the attempt
+                // doesn't count against connecting
+                Err(ClientError::Io(_)) if req.method().is_idempotent() => {
+                    deadline += started.elapsed();
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let transport = timeout_at(deadline, self.connect(&key))
+            .await
+            .map_err(|_| ClientError::ConnectTimeout)??;
+        let mut conn = ClientConnection::new(transport);
+        let res = self.exchange(&mut conn, req).await?;
+        self.inner.pool.put(key, conn);
+        Ok(res)
+    }
+
+    /// Answers from the cache when it can, revalidating stale responses.
+    async fn send_cached(&self, req: Request, secure: bool) -> Result<Response, ClientError> {
+        let (Some(cache), Ok(url)) = (&self.inner.cache, url(&req, secure)) else {
+            return self.send_once(req, secure).await;
+        };
+        let res = match cache.lookup(&url, &req) {
+            Lookup::Fresh(res) => return Ok(res),
+            Lookup::Stale(conditional) => self.send_once(conditional, secure).await?,
+            Lookup::Miss => self.send_once(req.clone(), secure).await?,
+        };
+        Ok(cache.update(&url, &req, res))
+    }
+
+    /// Returns `req` with the jar's cookies for it added to its `Cookie` header.
+    fn with_cookies(&self, req: &Request, secure: bool) -> Request {
+        let mut req = req.clone();
+        let (Some(jar), Ok(url)) = (&self.inner.cookie_jar, url(&req, secure)) else {
+            return req;
+        };
+        if let Some(Cookies(stored)) = jar.header(&url) {
+            let mut cookies = match req.headers().typed_get::<Cookies>() {
+                Ok(Some(cookies)) => cookies,
+                _ => Cookies::default(),
+            };
+            cookies.0.extend(stored);
+            req.headers_mut().typed_insert(cookies);
+        }
+        req
+    }
+
+    /// Returns `req` with the credentials for its host if `res` challenges their scheme and `req`
+    /// had none, so a rejected request is only retried once.
+    fn authorize(&self, req: &Request, res: &Response) -> Option<Request> {
+        if res.status() != Status::Unauthorized || req.headers().contains(Authorization::NAME) {
+            return None;
+        }
+        let host = req.headers().typed_get::<Host>().ok()??;
+        let WwwAuthenticate(challenges) = res.headers().typed_get().ok()??;
+
+        let credentials = self
+            .inner
+            .credentials
+            .iter()
+            .filter(|(h, _)| h.eq_ignore_ascii_case(host.host()))
+            .map(|(_, credentials)| credentials)
+            .find(|credentials| challenges.iter().any(|c| c.accepts(credentials)))?;
+        let mut req = req.clone();
+        req.headers_mut().typed_insert(credentials.clone());
+        Some(req)
+    }
+
+    /// Connects to `key`, performing the TLS handshake if it's for TLS.
+    async fn connect(&self, key: &Key) -> Result<Transport, ClientError> {
+        let stream = TcpStream::connect(key.addr).await?;
+        stream.set_nodelay(true)?;
+        match &key.tls {
+            None => Ok(Transport::Tcp(stream)),
+            #[cfg(feature = "tls")]
+            Some(host) => {
+                let stream = self.inner.tls.connect(host, stream).await?;
+                Ok(Transport::Tls(Box::new(stream)))
+            }
+            #[cfg(not(feature = "tls"))]
+            Some(_) => Err(ClientError::NoTls),
+        }
+    }
+
+    async fn exchange(
+        &self, conn: &mut ClientConnection<Transport>, req: Request,
+    ) -> Result<Response, ClientError> {
+        timeout(self.inner.read_timeout, conn.send(req))
+            .await
+            .map_err(|_| ClientError::ReadTimeout)?
+            .map_err(Into::into)
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The URL `req` is sent to.
+fn url(req: &Request, secure: bool) -> Result<Url, Error> {
+    match secure {
+        true => req.url_with_scheme("aethons"),
+        false => req.url(),
+    }
+}
+
+/// Builds the request following a redirect and whether it goes over TLS, `None` if `res` isn't
+/// one.
+fn redirect(
+    req: &Request, res: &Response, secure: bool,
+) -> Result<Option<(Request, bool)>, ClientError> {
+    use Status::*;
+    let status = res.status();
+    if !matches!(
+        status,
+        MovedPermanently | Found | SeeOther | TemporaryRedirect | PermanentRedirect
+    ) {
+        return Ok(None);
+    }
+    let Some(Location(location)) = res.headers().typed_get()? else {
+        return Ok(None);
+    };
+
+    let base = url(req, secure)?;
+    let target = base.join(&location)?;
+    let mut headers = req.headers().clone();
+    // Credentials are only sent to the host they're meant for, and never in the clear. The jar's
+    // cookies for the target are added back on each hop.
+    if (base.scheme(), base.host(), base.port()) != (target.scheme(), target.host(), target.port())
+    {
+        headers.remove(Authorization::NAME);
+        headers.remove(Cookies::NAME);
+    }
+    headers.typed_insert(target.host_header());
+
+    // See Other is followed with a GET without the body
+    let (method, body) = match status {
+        SeeOther => {
+            for name in [
+                Headers::CONTENT_LENGTH,
+                Headers::TRANSFER_ENCODING,
+                "Content-Type",
+            ] {
+                headers.remove(name);
+            }
+            (Method::GET, Vec::new())
+        }
+        _ => (req.method().clone(), req.body().to_vec()),
+    };
+
+    let next = Request::new(
+        req.version(),
+        method,
+        target.path_and_query(),
+        headers,
+        body,
+    );
+    Ok(Some((next, target.is_secure())))
+}
+
+/// Builder of a [`Client`].
+#[derive(Debug)]
+pub struct ClientBuilder {
+    connect_timeout: Duration,
+    read_timeout: Duration,
+    timeout: Option<Duration>,
+    max_redirects: usize,
+    max_decompressed_len: usize,
+    pool_idle_timeout: Duration,
+    max_idle_per_host: usize,
+    resolver: Resolver,
+    cookie_jar: Option<Arc<CookieJar>>,
+    cache: Option<Arc<Cache>>,
+    credentials: Vec<(Box<str>, Authorization)>,
+    #[cfg(feature = "tls")]
+    tls_roots: TlsRoots,
+}
+
+impl ClientBuilder {
+    /// Limit of resolving the host and connecting to it, including the TLS handshake.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Limit of waiting for a response once the request was sent.
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Limit of the whole exchange including redirects, there's none by default.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// How many redirects are followed, `0` returns redirect responses as they are.
+    pub fn max_redirects(mut self, max: usize) -> Self {
+        self.max_redirects = max;
+        self
+    }
+
+    /// Limit of a response body once decompressed, so a small compressed body can't expand
+    /// without bounds.
+    pub fn max_decompressed_len(mut self, len: usize) -> Self {
+        self.max_decompressed_len = len;
+        self
+    }
+
+    /// How long an unused connection is kept in the pool.
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = timeout;
+        self
+    }
+
+    /// How many unused connections are kept per address, `0` disables pooling.
+    pub fn max_idle_per_host(mut self, max: usize) -> Self {
+        self.max_idle_per_host = max;
+        self
+    }
+
+    pub fn resolver(mut self, resolver: Resolver) -> Self {
+        self.resolver = resolver;
+        self
+    }
+
+    /// Stores the cookies responses set in `jar` and sends them back, there's no jar by default.
+    pub fn cookie_jar(mut self, jar: Arc<CookieJar>) -> Self {
+        self.cookie_jar = Some(jar);
+        self
+    }
+
+    /// Keeps responses in `cache` to reuse them, there's no cache by default.
+    pub fn cache(mut self, cache: Arc<Cache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Credentials for `host`, sent when it answers `401 Unauthorized` with a challenge for their
+    /// scheme.
+    ///
+    /// Requests are retried once with them, they're never sent to other hosts.
+    pub fn credentials(mut self, host: impl Into<Box<str>>, credentials: Authorization) -> Self {
+        self.credentials.push((host.into(), credentials));
+        self
+    }
+
+    /// Certificate authorities trusted for `aethons://`.
+    #[cfg(feature = "tls")]
+    pub fn tls_roots(mut self, roots: TlsRoots) -> Self {
+        self.tls_roots = roots;
+        self
+    }
+
+    pub fn build(self) -> Client {
+        Client {
+            inner: Arc::new(Inner {
+                connect_timeout: self.connect_timeout,
+                read_timeout: self.read_timeout,
+                timeout: self.timeout,
+                max_redirects: self.max_redirects,
+                max_decompressed_len: self.max_decompressed_len,
+                resolver: self.resolver,
+                pool: Pool::new(self.pool_idle_timeout, self.max_idle_per_host),
+                cookie_jar: self.cookie_jar,
+                cache: self.cache,
+                credentials: self.credentials,
+                #[cfg(feature = "tls")]
+                tls: TlsConnector::new(self.tls_roots),
+            }),
+        }
+    }
+}
+
+impl Default for ClientBuilder {
+    /// 10 second connect and 30 second read timeouts, up to 10 redirects, decompressed bodies up
+    /// to 64 MiB, the system resolver and no trusted certificate authorities.
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(10),
+            read_timeout: Duration::from_secs(30),
+            timeout: None,
+            max_redirects: 10,
+            max_decompressed_len: 64 * 1024 * 1024,
+            pool_idle_timeout: Duration::from_secs(20),
+            max_idle_per_host: 8,
+            resolver: Resolver::System,
+            cookie_jar: None,
+            cache: None,
+            credentials: Vec::new(),
+            #[cfg(feature = "tls")]
+            tls_roots: TlsRoots::new(),
+        }
+    }
+}
+
+/// Error returned by [`Client`].
+#[derive(Debug)]
+pub enum ClientError {
+    /// The request has no `Host` header.
+    NoHost,
+    /// The host couldn't be resolved.
+    Resolve(Box<str>),
+    /// Resolving the host or connecting to it took too long.
+    ConnectTimeout,
+    /// The response took too long to arrive.
+    ReadTimeout,
+    /// The whole exchange took too long.
+    Timeout,
+    /// The redirect limit was reached.
+    TooManyRedirects,
+    /// Setting up TLS failed.
+    #[cfg(feature = "tls")]
+    Tls(TlsError),
+    /// The URL is `aethons://` but the `tls` feature is disabled.
+    #[cfg(not(feature = "tls"))]
+    NoTls,
+    Io(io::Error),
+    /// The response isn't a valid packet.
+    Aethon(Error),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoHost => write!(f, "The request has no Host header"),
+            Self::Resolve(host) => write!(f, "Failed to resolve {}", host),
+            Self::ConnectTimeout => write!(f, "Connecting to the host timed out"),
+            Self::ReadTimeout => write!(f, "Waiting for the response timed out"),
+            Self::Timeout => write!(f, "The request timed out"),
+            Self::TooManyRedirects => write!(f, "Too many redirects"),
+            #[cfg(feature = "tls")]
+            Self::Tls(e) => write!(f, "{}", e),
+            #[cfg(not(feature = "tls"))]
+            Self::NoTls => write!(f, "aethons:// needs the tls feature"),
+            Self::Io(e) => write!(f, "I/O error: {}", e),
+            Self::Aethon(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "tls")]
+            Self::Tls(e) => Some(e),
+            Self::Io(e) => Some(e),
+            Self::Aethon(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ClientError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[cfg(feature = "tls")]
+impl From<TlsError> for ClientError {
+    fn from(e: TlsError) -> Self {
+        Self::Tls(e)
+    }
+}
+
+impl From<Error> for ClientError {
+    fn from(e: Error) -> Self {
+        Self::Aethon(e)
+    }
+}
+
+impl From<CodecError> for ClientError {
+    fn from(e: CodecError) -> Self {
+        match e {
+            CodecError::Io(e) => Self::Io(e),
+            CodecError::Aethon(e) => Self::Aethon(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{serve, Challenge, Cookie, ETag, ServeOptions};
+    use std::{
+        net::SocketAddr,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+    use tokio::net::TcpListener;
+
+    /// Starts a server on a free port, returning its address and the number of accepted
+    /// connections.
+    async fn server<F>(handler: F) -> (SocketAddr, Arc<AtomicUsize>)
+    where
+        F: Fn(Request) -> Response + Clone + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&accepted);
+
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                counter.fetch_add(1, Ordering::Relaxed);
+                let handler = handler.clone();
+                tokio::spawn(async move {
+                    let handler = |req| {
+                        let res = handler(req);
+                        async {
+                            if res.headers().contains("Slow") {
+                                tokio::time::sleep(Duration::from_millis(200)).await;
+                            }
+                            res
+                        }
+                    };
+                    let _ = serve(socket, ServeOptions::default(), handler).await;
+                });
+            }
+        });
+
+        (addr, accepted)
+    }
+
+    fn request(addr: SocketAddr, method: Method, path: &str) -> Request {
+        let body = if method == Method::POST { "data" } else { "" };
+        Request::builder()
+            .method(method)
+            .path(path)
+            .typed_header(Host::new(addr.ip().to_string(), Some(addr.port())))
+            .body(body)
+            .build()
+            .unwrap()
+    }
+
+    fn echo_path(req: Request) -> Response {
+        let body = format!("{} {}", req.method(), req.path());
+        Response::builder().body(body).build().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reuses_connections() {
+        let (addr, accepted) = server(echo_path).await;
+        let client = Client::new();
+
+        for path in ["/", "/style.css", "/logo.png"] {
+            let res = client.send(request(addr, Method::GET, path)).await.unwrap();
+            assert_eq!(format!("GET {}", path).as_bytes(), res.body());
+        }
+
+        // Tests
+        assert_eq!(1, accepted.load(Ordering::Relaxed));
+        assert_eq!(1, client.inner.pool.idle(&Key { addr, tls: None }));
+    }
+
+    #[tokio::test]
+    async fn test_follows_redirects() {
+        let (addr, _) = server(|req: Request| match req.path() {
+            "/old" => redirect_to(Status::MovedPermanently, "/dir/new"),
+            "/dir/new" => redirect_to(Status::SeeOther, "done"),
+            _ => echo_path(req),
+        })
+        .await;
+        let (other, _) = server(|req: Request| {
+            let sent = |name| req.headers().contains(name);
+            let body = format!("{} {}", sent("Authorization"), sent("Cookie"));
+            Response::builder().body(body).build().unwrap()
+        })
+        .await;
+        let (cross_origin, _) = server(move |req: Request| {
+            let target = format!("aethon://{}{}", other, req.path());
+            redirect_to(Status::Found, &target)
+        })
+        .await;
+        let client = Client::new();
+        let res = client
+            .send(request(addr, Method::POST, "/old"))
+            .await
+            .unwrap();
+        let mut with_credentials = request(cross_origin, Method::GET, "/");
+        with_credentials
+            .headers_mut()
+            .typed_insert(Authorization::bearer("token"));
+        with_credentials.headers_mut().insert("Cookie", "id=1");
+
+        // Tests
+        assert_eq!(b"GET /dir/done", res.body());
+        let res = client.send(with_credentials).await.unwrap();
+        assert_eq!(b"false false", res.body());
+    }
+
+    fn redirect_to(status: Status, location: &str) -> Response {
+        Response::builder()
+            .status(status)
+            .typed_header(Location(location.into()))
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_redirect_limit() {
+        let (addr, _) = server(|_| redirect_to(Status::Found, "/loop")).await;
+        let client = Client::builder().max_redirects(3).build();
+        let no_follow = Client::builder().max_redirects(0).build();
+
+        // Tests
+        assert!(matches!(
+            client.send(request(addr, Method::GET, "/")).await,
+            Err(ClientError::TooManyRedirects)
+        ));
+        assert_eq!(
+            Status::Found,
+            no_follow
+                .send(request(addr, Method::GET, "/"))
+                .await
+                .unwrap()
+                .status()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_timeouts() {
+        let (addr, _) = server(|_| Response::builder().header("Slow", "1").build().unwrap()).await;
+        let read = Client::builder()
+            .read_timeout(Duration::from_millis(50))
+            .build();
+        let total = Client::builder().timeout(Duration::from_millis(50)).build();
+
+        // Tests
+        assert!(matches!(
+            read.send(request(addr, Method::GET, "/")).await,
+            Err(ClientError::ReadTimeout)
+        ));
+        assert!(matches!(
+            total.send(request(addr, Method::GET, "/")).await,
+            Err(ClientError::Timeout)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cookies() {
+        let (addr, _) = server(|req: Request| match req.path() {
+            "/account/login" => Response::builder()
+                .cookie(Cookie::parse("id=a3fWa; Path=/").unwrap())
+                .cookie(Cookie::new("scoped", "1"))
+                .build()
+                .unwrap(),
+            _ => Response::builder()
+                .body(req.headers().get("cookie").unwrap_or_default())
+                .build()
+                .unwrap(),
+        })
+        .await;
+        let jar = Arc::new(CookieJar::new());
+        let client = Client::builder().cookie_jar(Arc::clone(&jar)).build();
+        client
+            .send(request(addr, Method::POST, "/account/login"))
+            .await
+            .unwrap();
+
+        let mut req = request(addr, Method::GET, "/");
+        req.headers_mut().insert("Cookie", "lang=en");
+        let res = client.send(req).await.unwrap();
+
+        // Tests
+        assert_eq!(2, jar.len());
+        assert_eq!(b"lang=en; id=a3fWa", res.body());
+    }
+
+    #[tokio::test]
+    async fn test_credentials() {
+        let (addr, _) = server(|req: Request| {
+            let user = match req.headers().typed_get::<Authorization>() {
+                Ok(Some(auth)) => auth.user_password(),
+                _ => None,
+            };
+            match user {
+                Some((user, password)) if password == "sesame" => {
+                    Response::builder().body(user).build().unwrap()
+                }
+                _ => Response::builder()
+                    .status(Status::Unauthorized)
+                    .typed_header(WwwAuthenticate(vec![Challenge::basic("apollo")]))
+                    .build()
+                    .unwrap(),
+            }
+        })
+        .await;
+        let host = addr.ip().to_string();
+        let send = |credentials: Authorization| {
+            let client = Client::builder().credentials(&*host, credentials).build();
+            async move { client.send(request(addr, Method::POST, "/")).await.unwrap() }
+        };
+
+        // Tests
+        let res = send(Authorization::basic("ali", "sesame")).await;
+        assert_eq!(Status::OK, res.status());
+        assert_eq!(b"ali", res.body());
+        let wrong = send(Authorization::basic("ali", "open")).await;
+        assert_eq!(Status::Unauthorized, wrong.status());
+        let other_scheme = send(Authorization::bearer("token")).await;
+        assert_eq!(Status::Unauthorized, other_scheme.status());
+    }
+
+    #[cfg(feature = "gzip")]
+    #[tokio::test]
+    async fn test_decompresses_responses() {
+        let (addr, _) = server(|req: Request| {
+            let accepted = req.headers().get("Accept-Encoding").unwrap_or("none");
+            Response::builder()
+                .body(accepted.repeat(20))
+                .build()
+                .unwrap()
+                .compress(&req)
+        })
+        .await;
+        let client = Client::new();
+        let mut own = request(addr, Method::GET, "/");
+        own.headers_mut().insert("Accept-Encoding", "gzip");
+
+        // Tests
+        let res = client.send(request(addr, Method::GET, "/")).await.unwrap();
+        let accepted = AcceptEncoding::supported().encode();
+        assert_eq!(accepted.repeat(20).as_bytes(), res.body());
+        assert!(!res.headers().contains("Content-Encoding"));
+        let raw = client.send(own).await.unwrap();
+        assert_eq!(Some("gzip"), raw.headers().get("Content-Encoding"));
+        let head = client.send(request(addr, Method::HEAD, "/")).await.unwrap();
+        assert_eq!(Some("80"), head.headers().get("Content-Length"));
+        assert!(head.body().is_empty());
+        let limited = Client::builder().max_decompressed_len(79).build();
+        assert!(matches!(
+            limited.send(request(addr, Method::GET, "/")).await,
+            Err(ClientError::Aethon(Error::InvalidBody(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cache() {
+        let (addr, _) = server(|req: Request| {
+            let etag = ETag::strong("v1");
+            let res = match req.preconditions(Some(&etag), None) {
+                Some(status) => Response::builder().status(status),
+                None => Response::builder().body(format!("{} {}", req.path(), req.headers().len())),
+            };
+            let cache_control = match req.path() {
+                "/fresh" => "max-age=60",
+                _ => "no-cache",
+            };
+            res.typed_header(etag)
+                .header("Cache-Control", cache_control)
+                .build()
+                .unwrap()
+        })
+        .await;
+        let cache = Arc::new(Cache::new());
+        let client = Client::builder().cache(Arc::clone(&cache)).build();
+        let send = |path| client.send(request(addr, Method::GET, path));
+
+        // Tests
+        let first = send("/fresh").await.unwrap();
+        assert_eq!(first.body(), send("/fresh").await.unwrap().body());
+        let revalidated = send("/stale").await.unwrap();
+        assert_eq!(revalidated.body(), send("/stale").await.unwrap().body());
+        assert_eq!(Status::OK, send("/stale").await.unwrap().status());
+        assert_eq!(2, cache.len());
+    }
+
+    #[tokio::test]
+    async fn test_no_host() {
+        let req = Request::builder().build().unwrap();
+
+        // Tests
+        assert!(matches!(
+            Client::new().send(req).await,
+            Err(ClientError::NoHost)
+        ));
+    }
+
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn test_tls() {
+        use crate::TlsAcceptor;
+
+        let generated = rcgen::generate_simple_self_signed(["127.0.0.1".into()]).unwrap();
+        let cert = generated.cert.pem();
+        let acceptor = TlsAcceptor::builder()
+            .certificate(cert.clone(), generated.key_pair.serialize_pem())
+            .build()
+            .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    let stream = acceptor.accept(socket).await.unwrap();
+                    let handler = |req| async { echo_path(req) };
+                    let _ = serve(stream, ServeOptions::default(), handler).await;
+                });
+            }
+        });
+
+        let mut roots = TlsRoots::new();
+        roots.add_pem(cert.as_bytes()).unwrap();
+        let client = Client::builder().tls_roots(roots).build();
+        let url: Url = format!("aethons://{}/secret", addr).parse().unwrap();
+
+        // Tests
+        assert_eq!(b"GET /secret", client.get(&url).await.unwrap().body());
+        assert_eq!(
+            b"GET /",
+            client
+                .send_tls(request(addr, Method::GET, "/"))
+                .await
+                .unwrap()
+                .body()
+        );
+        let key = Key {
+            addr,
+            tls: Some("127.0.0.1".into()),
+        };
+        assert_eq!(1, client.inner.pool.idle(&key));
+        // The certificate isn't trusted
+        assert!(matches!(
+            Client::new().get(&url).await,
+            Err(ClientError::Tls(TlsError::Io(_)))
+        ));
+    }
+
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn test_connect_deadline() {
+        use tokio::net::UdpSocket;
+
+        // The host resolves slowly to a server that never completes the TLS handshake
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let echo = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let resolver = Resolver::Echo(echo.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut buf = [0; 64];
+            let (_, source) = echo.recv_from(&mut buf).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(400)).await;
+            let answer = format!("\x01\x01\x00{}", addr);
+            echo.send_to(answer.as_bytes(), source).await.unwrap();
+            let _socket = listener.accept().await.unwrap();
+            std::future::pending::<()>().await;
+        });
+        let client = Client::builder()
+            .resolver(resolver)
+            .connect_timeout(Duration::from_millis(500))
+            .build();
+        let req = Request::builder().header("Host", "slow").build().unwrap();
+        let start = Instant::now();
+
+        // Tests
+        assert!(matches!(
+            client.send_tls(req).await,
+            Err(ClientError::ConnectTimeout)
+        ));
+        assert!(start.elapsed() < Duration::from_millis(800));
+    }
+}
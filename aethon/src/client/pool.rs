@@ -0,0 +1,74 @@
+use super::Transport;
+use crate::ClientConnection;
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Idle keep-alive connections, grouped by where they're connected to.
+#[derive(Debug)]
+pub(crate) struct Pool {
+    idle: Mutex<HashMap<Key, Vec<Idle>>>,
+    idle_timeout: Duration,
+    max_idle_per_host: usize,
+}
+
+/// The address of a connection, and the name a TLS connection was made for.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Key {
+    pub addr: SocketAddr,
+    pub tls: Option<Box<str>>,
+}
+
+#[derive(Debug)]
+struct Idle {
+    conn: ClientConnection<Transport>,
+    since: Instant,
+}
+
+impl Pool {
+    pub fn new(idle_timeout: Duration, max_idle_per_host: usize) -> Self {
+        Self {
+            idle: Mutex::default(),
+            idle_timeout,
+            max_idle_per_host,
+        }
+    }
+
+    /// Takes the most recently used connection to `key`, dropping the ones idle for too long.
+    pub fn take(&self, key: &Key) -> Option<ClientConnection<Transport>> {
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        let conns = idle.get_mut(key)?;
+        conns.retain(|c| c.since.elapsed() < self.idle_timeout);
+        let conn = conns.pop().map(|c| c.conn);
+        if conns.is_empty() {
+            idle.remove(key);
+        }
+        conn
+    }
+
+    /// Keeps `conn` for later if it can still be used.
+    pub fn put(&self, key: Key, conn: ClientConnection<Transport>) {
+        if !conn.is_reusable() {
+            return;
+        }
+
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        let conns = idle.entry(key).or_default();
+        if conns.len() < self.max_idle_per_host {
+            conns.push(Idle {
+                conn,
+                since: Instant::now(),
+            });
+        }
+    }
+
+    /// Number of idle connections to `key`.
+    #[cfg(test)]
+    pub fn idle(&self, key: &Key) -> usize {
+        let idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        idle.get(key).map_or(0, Vec::len)
+    }
+}
@@ -0,0 +1,126 @@
+//! Name resolution, through the system or an Echo server.
+//!
+//! An Echo packet is a 3-byte header followed by a UTF-8 body:
+//!
+//! 1. Version, always `1`
+//! 2. Method, `0` for a query and `1` for an answer
+//! 3. Error, `0` for none, e.g. `2` if the name wasn't found
+//!
+//! A query's body is the name, an answer's body is its address, either an IP or an IP with a port.
+
+use super::ClientError;
+use std::net::{IpAddr, SocketAddr};
+use tokio::net::{lookup_host, UdpSocket};
+
+const ECHO_VERSION: u8 = 1;
+const QUERY: u8 = 0;
+const ANSWER: u8 = 1;
+const NO_ERROR: u8 = 0;
+
+/// How the client turns host names into addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Resolver {
+    /// The system's DNS.
+    #[default]
+    System,
+    /// An Echo server at this address.
+    Echo(SocketAddr),
+}
+
+impl Resolver {
+    /// Resolves `host`, `port` is used unless the answer has its own.
+    pub(crate) async fn resolve(&self, host: &str, port: u16) -> Result<SocketAddr, ClientError> {
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return Ok(SocketAddr::new(ip, port));
+        }
+
+        match self {
+            Resolver::System => lookup_host((host, port))
+                .await?
+                .next()
+                .ok_or_else(|| ClientError::Resolve(host.into())),
+            Resolver::Echo(server) => query_echo(*server, host, port).await,
+        }
+    }
+}
+
+async fn query_echo(server: SocketAddr, host: &str, port: u16) -> Result<SocketAddr, ClientError> {
+    let local: SocketAddr = match server {
+        SocketAddr::V4(_) => ([0, 0, 0, 0], 0).into(),
+        SocketAddr::V6(_) => ([0u16; 8], 0).into(),
+    };
+    let socket = UdpSocket::bind(local).await?;
+    socket.connect(server).await?;
+    socket.send(&encode_query(host)).await?;
+
+    let mut buf = [0; 1024];
+    let len = socket.recv(&mut buf).await?;
+    decode_answer(&buf[..len], port).ok_or_else(|| ClientError::Resolve(host.into()))
+}
+
+fn encode_query(host: &str) -> Vec<u8> {
+    let mut packet = vec![ECHO_VERSION, QUERY, NO_ERROR];
+    packet.extend_from_slice(host.as_bytes());
+    packet
+}
+
+/// Returns the address in an answer, `None` if the name wasn't found or the answer is malformed.
+fn decode_answer(packet: &[u8], port: u16) -> Option<SocketAddr> {
+    match packet {
+        [ECHO_VERSION, ANSWER, NO_ERROR, body @ ..] => {
+            let body = std::str::from_utf8(body).ok()?.trim();
+            body.parse()
+                .ok()
+                .or_else(|| Some(SocketAddr::new(body.parse().ok()?, port)))
+        }
+        // The name wasn't found or the server failed
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_echo_packets() {
+        // Tests
+        assert_eq!(b"\x01\x00\x00apollo", &encode_query("apollo")[..]);
+        assert_eq!(
+            Some(([127, 0, 0, 1], 8081).into()),
+            decode_answer(b"\x01\x01\x00127.0.0.1", 8081)
+        );
+        assert_eq!(
+            Some(([10, 0, 0, 1], 9000).into()),
+            decode_answer(b"\x01\x01\x0010.0.0.1:9000", 8081)
+        );
+        assert_eq!(None, decode_answer(b"\x01\x01\x02", 8081));
+        assert_eq!(None, decode_answer(b"\x01\x01\x00not an ip", 8081));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_through_echo() {
+        let echo = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let resolver = Resolver::Echo(echo.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            let mut buf = [0; 64];
+            let (len, source) = echo.recv_from(&mut buf).await.unwrap();
+            let answer: &[u8] = match &buf[3..len] {
+                b"apollo" => b"\x01\x01\x00127.0.0.1",
+                _ => b"\x01\x01\x02",
+            };
+            echo.send_to(answer, source).await.unwrap();
+        });
+
+        // Tests
+        assert_eq!(
+            SocketAddr::from(([127, 0, 0, 1], 8081)),
+            resolver.resolve("apollo", 8081).await.unwrap()
+        );
+        assert_eq!(
+            SocketAddr::from(([10, 0, 0, 2], 80)),
+            resolver.resolve("10.0.0.2", 80).await.unwrap()
+        );
+    }
+}
@@ -0,0 +1,566 @@
+use super::{
+    error::{ParseError, ParseErrorKind},
+    parser::{self, Framing, HeadLimits, ParseOptions},
+    Chunk, ChunkedDecoder, Error, Method, Request, Response, Status,
+};
+use bytes::BytesMut;
+use std::{collections::VecDeque, fmt, io, marker::PhantomData};
+use tokio_util::codec::{Decoder, Encoder};
+
+mod stream;
+pub use stream::{Body, Frame, StreamCodec};
+
+/// Codec used by servers, it decodes requests and encodes responses.
+pub type ServerCodec = AethonCodec<Request>;
+/// Codec used by clients, it decodes responses and encodes requests.
+pub type ClientCodec = AethonCodec<Response>;
+
+/// Frames Aethon packets over a byte stream.
+///
+/// `M` is the type of the decoded packets, both requests and responses can be encoded.
+/// Decoding is incremental: a partial packet yields `Ok(None)` and is resumed once more data
+/// arrives, without rescanning what was already seen.
+///
+/// Responses to `HEAD` requests, and `1**`, `204` and `304` responses, have no body: their
+/// `Content-Length` describes the body a `GET` would get. The codec remembers the method of the
+/// requests it encodes ([`ClientCodec`]) or decodes ([`ServerCodec`]) to frame their responses,
+/// which must then come in order. Only the last 1024 requests not answered yet are remembered,
+/// so a codec used in one direction, as one half of a split stream, doesn't grow.
+#[derive(Debug)]
+pub struct AethonCodec<M> {
+    state: State,
+    options: ParseOptions,
+    head_requests: HeadRequests,
+    _packet: PhantomData<fn() -> M>,
+}
+
+/// Whether each request not answered yet is a `HEAD` request, in order.
+#[derive(Debug, Default)]
+pub(crate) struct HeadRequests(VecDeque<bool>);
+
+impl HeadRequests {
+    /// How many requests are remembered, the oldest are forgotten beyond it.
+    const MAX: usize = 1024;
+
+    pub fn push(&mut self, method: &Method) {
+        if self.0.len() == Self::MAX {
+            self.0.pop_front();
+        }
+        self.0.push_back(*method == Method::HEAD);
+    }
+
+    /// Whether the oldest request not answered yet is a `HEAD` request.
+    pub fn next_is_head(&self) -> bool {
+        self.0.front() == Some(&true)
+    }
+
+    /// Forgets the oldest request once it's answered, returning whether it was a `HEAD` request.
+    pub fn pop(&mut self) -> bool {
+        self.0.pop_front().unwrap_or(false)
+    }
+
+    /// Returns whether a response with `status` to the oldest request has no body, forgetting the
+    /// request unless the response is informational.
+    pub fn answer(&mut self, status: Status) -> bool {
+        match status.is_informational() {
+            true => true,
+            false => self.pop() || !status.allows_body(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    /// Waiting for the head of the packet.
+    Head(HeadScan),
+    /// Waiting until the whole body arrives.
+    Body { len: usize },
+    /// Scanning a chunked body, `len` is the length of the packet scanned so far.
+    Chunked { decoder: ChunkedDecoder, len: usize },
+}
+
+/// Finds the end of a packet's head, resuming where the previous call stopped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HeadScan {
+    /// Looking for the end of the start line.
+    StartLine { scanned: usize },
+    /// Looking for the empty line dividing headers from the body.
+    Headers {
+        start: usize,
+        scanned: usize,
+        limits: HeadLimits,
+    },
+}
+
+impl HeadScan {
+    const fn new() -> Self {
+        Self::StartLine { scanned: 0 }
+    }
+
+    /// Returns the length of the head at the start of `src` and how its body is framed.
+    fn scan(
+        &mut self, src: &[u8], options: &ParseOptions,
+    ) -> Result<Option<(usize, Framing)>, Error> {
+        loop {
+            match *self {
+                Self::StartLine { scanned } => {
+                    match src[scanned..].iter().position(|&b| b == b'\n') {
+                        Some(i) => {
+                            let start = scanned + i + 1;
+                            // Strict parsing would never find the end of a head with \r\n lines
+                            if let Some(cr) = src[..start]
+                                .iter()
+                                .position(|&b| b == b'\r')
+                                .filter(|_| options.is_strict())
+                            {
+                                let kind = ParseErrorKind::CarriageReturn;
+                                return Err(ParseError::at(kind, src, cr).into());
+                            }
+                            *self = Self::Headers {
+                                start,
+                                scanned: start,
+                                limits: HeadLimits::default(),
+                            };
+                        }
+                        None if src.len() > options.max_line_len => {
+                            let kind = ParseErrorKind::HeaderTooLarge;
+                            return Err(ParseError::at(kind, src, 0).into());
+                        }
+                        None => {
+                            *self = Self::StartLine { scanned: src.len() };
+                            return Ok(None);
+                        }
+                    }
+                }
+                Self::Headers {
+                    start,
+                    scanned,
+                    mut limits,
+                } => {
+                    match parser::find_head_end(&src[scanned..], options) {
+                        Some((i, len)) => {
+                            let head_len = scanned + i + len;
+                            let packet = parser::split(&src[..head_len], options)?;
+                            let headers = packet.parse_headers(options)?;
+                            let framing = packet.framing(&headers, options)?;
+                            return Ok(Some((head_len, framing)));
+                        }
+                        None => {
+                            limits
+                                .check(&src[start..], options)
+                                .map_err(|e| e.shift(src, start))?;
+                            // The last bytes may be the start of \n\n or \n\r\n
+                            let scanned = src.len().saturating_sub(2).max(start);
+                            *self = Self::Headers {
+                                start,
+                                scanned,
+                                limits,
+                            };
+                            return Ok(None);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<M> AethonCodec<M> {
+    pub fn new() -> Self {
+        Self::with_options(ParseOptions::default())
+    }
+
+    /// A codec parsing packets as strictly as `options` say.
+    pub fn with_options(options: ParseOptions) -> Self {
+        Self {
+            state: State::Head(HeadScan::new()),
+            options,
+            head_requests: HeadRequests::default(),
+            _packet: PhantomData,
+        }
+    }
+
+    /// Returns the length of the packet at the start of `src` once it has fully arrived, and
+    /// whether it's only a head because `bodiless` says so of it.
+    fn packet_len(
+        &mut self, src: &BytesMut, bodiless: impl Fn(&[u8]) -> bool,
+    ) -> Result<Option<(usize, bool)>, Error> {
+        loop {
+            match self.state {
+                State::Head(mut scan) => {
+                    let Some((head_len, framing)) = scan.scan(src, &self.options)? else {
+                        self.state = State::Head(scan);
+                        return Ok(None);
+                    };
+                    if bodiless(&src[..head_len]) {
+                        return Ok(Some((head_len, true)));
+                    }
+                    self.state = match framing {
+                        Framing::Length(len) => State::Body {
+                            len: head_len + len,
+                        },
+                        // Packets are delimited by the stream, so the body can't be the rest of it
+                        Framing::Rest => State::Body { len: head_len },
+                        Framing::Chunked => State::Chunked {
+                            decoder: ChunkedDecoder::with_options(self.options),
+                            len: head_len,
+                        },
+                    };
+                }
+                State::Body { len } if src.len() >= len => return Ok(Some((len, false))),
+                State::Body { .. } => return Ok(None),
+                State::Chunked { mut decoder, len } => {
+                    let (consumed, chunk) =
+                        decoder.decode(&src[len..]).map_err(|e| e.shift(src, len))?;
+                    let len = len + consumed;
+                    match chunk {
+                        Some(Chunk::End(_)) => return Ok(Some((len, false))),
+                        Some(Chunk::Data(_)) => self.state = State::Chunked { decoder, len },
+                        None => {
+                            self.state = State::Chunked { decoder, len };
+                            return Ok(None);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Decodes the packet at the start of `src`, with `parse_head` if `bodiless` says it's only
+    /// a head.
+    fn decode_with(
+        &mut self, src: &mut BytesMut, bodiless: impl Fn(&[u8]) -> bool,
+        parse: fn(&[u8], &ParseOptions) -> Result<M, Error>,
+        parse_head: fn(&[u8], &ParseOptions) -> Result<M, Error>,
+    ) -> Result<Option<M>, CodecError> {
+        let (len, head_only) = match self.packet_len(src, bodiless) {
+            Ok(Some(packet)) => packet,
+            Ok(None) => return Ok(None),
+            Err(e) => {
+                self.state = State::Head(HeadScan::new());
+                return Err(e.into());
+            }
+        };
+
+        self.state = State::Head(HeadScan::new());
+        let packet = src.split_to(len);
+        let parse = if head_only { parse_head } else { parse };
+        Ok(Some(parse(&packet, &self.options)?))
+    }
+}
+
+impl ClientCodec {
+    /// Records that a request with `method` was sent without this codec, for example through
+    /// a [`FramedWrite`] with another codec, so its response is framed right.
+    ///
+    /// [`FramedWrite`]: tokio_util::codec::FramedWrite
+    pub fn request_sent(&mut self, method: &Method) {
+        self.head_requests.push(method);
+    }
+}
+
+/// Whether the response `head` has a status that can't carry a body.
+fn has_bodiless_status(head: &[u8]) -> bool {
+    let line = head.split(|&b| b == b'\n').next().unwrap_or_default();
+    std::str::from_utf8(line)
+        .ok()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|status| status.parse::<Status>().ok())
+        .is_some_and(|status| !status.allows_body())
+}
+
+impl<M> Default for AethonCodec<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for AethonCodec<Request> {
+    type Item = Request;
+    type Error = CodecError;
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let req = self.decode_with(src, |_| false, Request::parse_with, Request::parse_with)?;
+        if let Some(req) = &req {
+            self.head_requests.push(req.method());
+        }
+        Ok(req)
+    }
+}
+
+impl Decoder for AethonCodec<Response> {
+    type Item = Response;
+    type Error = CodecError;
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let to_head = self.head_requests.next_is_head();
+        let bodiless = |head: &[u8]| to_head || has_bodiless_status(head);
+        let res = self.decode_with(src, bodiless, Response::parse_with, Response::parse_head)?;
+        // Informational responses come before the final one
+        if res
+            .as_ref()
+            .is_some_and(|res| !res.status().is_informational())
+        {
+            self.head_requests.pop();
+        }
+        Ok(res)
+    }
+}
+
+impl<M> Encoder<Request> for AethonCodec<M> {
+    type Error = CodecError;
+    fn encode(&mut self, item: Request, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.head_requests.push(item.method());
+        dst.extend_from_slice(&item.to_bytes());
+        Ok(())
+    }
+}
+
+impl<M> Encoder<Response> for AethonCodec<M> {
+    type Error = CodecError;
+    fn encode(&mut self, item: Response, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        // The head keeps the Content-Length of the body left out
+        match self.head_requests.answer(item.status()) {
+            true => dst.extend_from_slice(&item.head_bytes()),
+            false => dst.extend_from_slice(&item.to_bytes()),
+        }
+        Ok(())
+    }
+}
+
+/// Error returned by [`AethonCodec`].
+#[derive(Debug)]
+pub enum CodecError {
+    /// The underlying stream failed.
+    Io(io::Error),
+    /// The received bytes aren't a valid packet.
+    Aethon(Error),
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "I/O error: {}", e),
+            Self::Aethon(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Aethon(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for CodecError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<Error> for CodecError {
+    fn from(e: Error) -> Self {
+        Self::Aethon(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Headers, Method, Status};
+    use futures::{SinkExt, StreamExt};
+    use tokio_util::codec::{FramedRead, FramedWrite};
+
+    fn request(body: &str) -> Request {
+        Request::new(1, Method::POST, "/", Headers::default(), body)
+    }
+
+    #[test]
+    fn test_decode_byte_by_byte() {
+        let bytes = request("Hello World").to_bytes();
+        let mut codec = ServerCodec::new();
+        let mut src = BytesMut::new();
+        let mut decoded = Vec::new();
+
+        for &b in &bytes {
+            src.extend_from_slice(&[b]);
+            if let Some(req) = codec.decode(&mut src).unwrap() {
+                decoded.push(req);
+            }
+        }
+
+        // Tests
+        assert_eq!(1, decoded.len());
+        assert_eq!(bytes, decoded[0].to_bytes());
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn test_decode_multiple_packets_in_one_buffer() {
+        let mut src = BytesMut::new();
+        src.extend_from_slice(&request("a").to_bytes());
+        src.extend_from_slice(&request("").to_bytes());
+        src.extend_from_slice(b"1 GET /next\n");
+        let mut codec = ServerCodec::new();
+
+        // Tests
+        assert_eq!(b"a", codec.decode(&mut src).unwrap().unwrap().body());
+        assert_eq!(Some(request("")), codec.decode(&mut src).unwrap());
+        assert!(codec.decode(&mut src).unwrap().is_none());
+        assert_eq!(b"1 GET /next\n", &src[..]);
+    }
+
+    #[test]
+    fn test_need_more_data_vs_parse_error() {
+        let mut codec = ClientCodec::new();
+        let mut partial = BytesMut::from("1 200\nContent-Length: 10\n\nHello");
+        let mut invalid = BytesMut::from("1 200\nContent-Length: ten\n\n");
+
+        // Tests
+        assert!(codec.decode(&mut partial).unwrap().is_none());
+        assert!(matches!(
+            ClientCodec::new().decode(&mut invalid),
+            Err(CodecError::Aethon(Error::ParseError(_)))
+        ));
+    }
+
+    #[test]
+    fn test_limits_before_packet_ends() {
+        let options = ParseOptions::default()
+            .with_max_line_len(32)
+            .with_max_headers(2)
+            .with_max_body_len(8);
+        let kind = |src: &str| match ServerCodec::with_options(options).decode(&mut src.into()) {
+            Err(CodecError::Aethon(Error::ParseError(e))) => Some((e.kind(), e.offset())),
+            _ => None,
+        };
+
+        // Tests
+        assert_eq!(
+            Some((ParseErrorKind::HeaderTooLarge, 0)),
+            kind("1 GET /a-path-longer-than-the-limit")
+        );
+        assert_eq!(
+            Some((ParseErrorKind::HeaderTooLarge, 8)),
+            kind("1 GET /\nName: a-value-longer-than-the-limit")
+        );
+        assert_eq!(
+            Some((ParseErrorKind::TooManyHeaders, 18)),
+            kind("1 GET /\na: 1\nb: 2\nc: 3\n")
+        );
+        assert_eq!(
+            Some((ParseErrorKind::BodyTooLarge, 24)),
+            kind("1 GET /\nContent-Length: 9\n\n")
+        );
+        assert_eq!(
+            Some((ParseErrorKind::BodyTooLarge, 36)),
+            kind("1 GET /\nTransfer-Encoding: chunked\n\n9\n")
+        );
+        assert_eq!(None, kind("1 GET /\na: 1\nb: 2\n"));
+    }
+
+    #[test]
+    fn test_conflicting_framing_before_body() {
+        let mut codec = ServerCodec::new();
+        let mut src = "1 POST /\nContent-Length: 1\nContent-Length: 2\n\n".into();
+
+        // Tests
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(CodecError::Aethon(Error::ParseError(e)))
+                if e.kind() == ParseErrorKind::ConflictingFraming
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_framed_over_split_stream() {
+        let (client, server) = tokio::io::duplex(4);
+        let mut writer = FramedWrite::new(client, ClientCodec::new());
+        let mut reader = FramedRead::new(server, ServerCodec::new());
+        let body = "A body longer than the duplex buffer".repeat(10);
+
+        tokio::spawn(async move {
+            writer.send(request(&body)).await.unwrap();
+            writer.send(request("")).await.unwrap();
+        });
+
+        // Tests
+        let expected = "A body longer than the duplex buffer".repeat(10);
+        assert_eq!(
+            expected.as_bytes(),
+            reader.next().await.unwrap().unwrap().body()
+        );
+        assert_eq!(request(""), reader.next().await.unwrap().unwrap());
+        assert!(reader.next().await.is_none());
+    }
+
+    #[test]
+    fn test_encode_response() {
+        let mut codec = ServerCodec::new();
+        let mut dst = BytesMut::new();
+        codec
+            .encode(
+                Response::new(1, Status::OK, Headers::default(), "Hi"),
+                &mut dst,
+            )
+            .unwrap();
+
+        // Tests
+        assert_eq!(&b"1 200\nContent-Length: 2\n\nHi"[..], &dst[..]);
+    }
+
+    #[test]
+    fn test_decode_bodiless_responses() {
+        let head = Request::new(1, Method::HEAD, "/", Headers::default(), "");
+        let mut codec = ClientCodec::new();
+        codec.encode(head, &mut BytesMut::new()).unwrap();
+        codec.encode(request(""), &mut BytesMut::new()).unwrap();
+        codec.request_sent(&Method::HEAD);
+        let mut src = BytesMut::new();
+        src.extend_from_slice(b"1 100\n\n\n1 200\nContent-Length: 5\n\n");
+        src.extend_from_slice(b"1 304\nContent-Length: 5\n\n");
+        src.extend_from_slice(b"1 200\nTransfer-Encoding: chunked\n\n1 GET");
+        let mut decode = || codec.decode(&mut src).unwrap().unwrap();
+
+        // Tests
+        assert_eq!(Status::Continue, decode().status());
+        let res = decode();
+        assert_eq!(Some("5"), res.headers().get("Content-Length"));
+        assert!(res.body().is_empty());
+        assert_eq!(Status::NotModified, decode().status());
+        assert!(decode().body().is_empty());
+        assert_eq!(b"1 GET", &src[..]);
+    }
+
+    #[test]
+    fn test_unanswered_requests_are_bounded() {
+        let mut src = BytesMut::new();
+        for _ in 0..HeadRequests::MAX + 10 {
+            src.extend_from_slice(b"1 GET /\n\n\n");
+        }
+        let mut codec = ServerCodec::new();
+        while codec.decode(&mut src).unwrap().is_some() {}
+
+        // Tests
+        assert_eq!(HeadRequests::MAX, codec.head_requests.0.len());
+    }
+
+    #[test]
+    fn test_encode_response_to_head() {
+        let mut src = BytesMut::from("1 HEAD /\n\n\n1 GET /\n\n\n");
+        let mut codec = ServerCodec::new();
+        let mut dst = BytesMut::new();
+        for _ in 0..2 {
+            codec.decode(&mut src).unwrap().unwrap();
+            let res = Response::new(1, Status::OK, Headers::default(), "Hi");
+            codec.encode(res, &mut dst).unwrap();
+        }
+
+        // Tests
+        assert_eq!(
+            &b"1 200\nContent-Length: 2\n\n1 200\nContent-Length: 2\n\nHi"[..],
+            &dst[..]
+        );
+    }
+}
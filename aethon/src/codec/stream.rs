@@ -0,0 +1,417 @@
+use super::{CodecError, HeadRequests, HeadScan};
+use crate::{
+    chunked::{self, CHUNKED},
+    parser::{Framing, ParseOptions},
+    Chunk, ChunkedDecoder, Error, Headers, Request, Response,
+};
+use bytes::{Buf, Bytes, BytesMut};
+use futures_util::{Sink, SinkExt, Stream, StreamExt};
+use std::{fmt, io, marker::PhantomData, pin::Pin};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// A piece of a packet whose body is streamed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Frame<M> {
+    /// The start line and headers, always the first frame of a packet.
+    ///
+    /// When encoding, a non-empty body of the head is sent as the first chunk.
+    Head(M),
+    /// A piece of the body.
+    Data(Bytes),
+    /// The end of the body with its trailers, always the last frame of a packet.
+    End(Headers),
+}
+
+/// Frames packets over a byte stream without buffering their bodies.
+///
+/// Decoding yields a [`Frame::Head`], any number of [`Frame::Data`] and a [`Frame::End`] per
+/// packet, for both chunked and `Content-Length` bodies. Encoding always uses chunked transfer
+/// encoding, so the length of the body doesn't have to be known when the head is sent.
+///
+/// Responses without a body are framed as by [`AethonCodec`]: decoding them yields a
+/// [`Frame::End`] right after their head, and encoding them only sends the head, dropping their
+/// [`Frame::Data`] and [`Frame::End`].
+///
+/// [`AethonCodec`]: super::AethonCodec
+#[derive(Debug)]
+pub struct StreamCodec<M> {
+    state: State,
+    options: ParseOptions,
+    head_requests: HeadRequests,
+    /// Whether the frames of the packet being encoded are dropped, its head has no body.
+    skipping_body: bool,
+    _packet: PhantomData<fn() -> M>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Head(HeadScan),
+    /// Reading a body of a known length.
+    Length(usize),
+    Chunked(ChunkedDecoder),
+}
+
+impl<M> StreamCodec<M> {
+    pub fn new() -> Self {
+        Self::with_options(ParseOptions::default())
+    }
+
+    /// A codec parsing packets as strictly as `options` say.
+    pub fn with_options(options: ParseOptions) -> Self {
+        Self {
+            state: State::Head(HeadScan::new()),
+            options,
+            head_requests: HeadRequests::default(),
+            skipping_body: false,
+            _packet: PhantomData,
+        }
+    }
+
+    /// Decodes the next frame, `bodiless` records a decoded head and says if it has no body.
+    fn decode_with(
+        &mut self, src: &mut BytesMut, parse_head: fn(&[u8], &ParseOptions) -> Result<M, Error>,
+        bodiless: fn(&mut HeadRequests, &M) -> bool,
+    ) -> Result<Option<Frame<M>>, CodecError> {
+        match self.decode_frame(src, parse_head, bodiless) {
+            Ok(frame) => Ok(frame),
+            Err(e) => {
+                self.state = State::Head(HeadScan::new());
+                Err(e.into())
+            }
+        }
+    }
+
+    fn decode_frame(
+        &mut self, src: &mut BytesMut, parse_head: fn(&[u8], &ParseOptions) -> Result<M, Error>,
+        bodiless: fn(&mut HeadRequests, &M) -> bool,
+    ) -> Result<Option<Frame<M>>, Error> {
+        match self.state {
+            State::Head(mut scan) => {
+                let Some((len, framing)) = scan.scan(src, &self.options)? else {
+                    self.state = State::Head(scan);
+                    return Ok(None);
+                };
+
+                let head = parse_head(&src.split_to(len), &self.options)?;
+                self.state = match framing {
+                    _ if bodiless(&mut self.head_requests, &head) => State::Length(0),
+                    Framing::Length(len) => State::Length(len),
+                    Framing::Rest => State::Length(0),
+                    Framing::Chunked => State::Chunked(ChunkedDecoder::with_options(self.options)),
+                };
+                Ok(Some(Frame::Head(head)))
+            }
+            State::Length(0) => {
+                self.state = State::Head(HeadScan::new());
+                Ok(Some(Frame::End(Headers::default())))
+            }
+            State::Length(_) if src.is_empty() => Ok(None),
+            State::Length(remaining) => {
+                let n = remaining.min(src.len());
+                self.state = State::Length(remaining - n);
+                Ok(Some(Frame::Data(src.split_to(n).freeze())))
+            }
+            State::Chunked(mut decoder) => {
+                let (consumed, chunk) = decoder.decode(src)?;
+                let frame = match chunk {
+                    Some(Chunk::Data(data)) => {
+                        // The data is at the end of the consumed bytes
+                        let len = data.len();
+                        src.advance(consumed - len);
+                        self.state = State::Chunked(decoder);
+                        return Ok(Some(Frame::Data(src.split_to(len).freeze())));
+                    }
+                    Some(Chunk::End(trailers)) => {
+                        self.state = State::Head(HeadScan::new());
+                        Some(Frame::End(trailers.into()))
+                    }
+                    None => {
+                        self.state = State::Chunked(decoder);
+                        None
+                    }
+                };
+                src.advance(consumed);
+                Ok(frame)
+            }
+        }
+    }
+}
+
+impl<M> Default for StreamCodec<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for StreamCodec<Request> {
+    type Item = Frame<Request>;
+    type Error = CodecError;
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        self.decode_with(src, Request::parse_head, |head_requests, req| {
+            head_requests.push(req.method());
+            false
+        })
+    }
+}
+
+impl Decoder for StreamCodec<Response> {
+    type Item = Frame<Response>;
+    type Error = CodecError;
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        self.decode_with(src, Response::parse_head, |head_requests, res| {
+            head_requests.answer(res.status())
+        })
+    }
+}
+
+/// Writes `frame`, `head` serializes the head of a packet into the buffer and returns its body.
+fn encode_frame<H>(
+    frame: Frame<H>, dst: &mut BytesMut, head: impl FnOnce(H, &mut Vec<u8>) -> Vec<u8>,
+) {
+    let mut buffer = Vec::new();
+    match frame {
+        Frame::Head(h) => {
+            let body = head(h, &mut buffer);
+            chunked::encode_chunk(&body, &mut buffer);
+        }
+        Frame::Data(data) => chunked::encode_chunk(&data, &mut buffer),
+        Frame::End(trailers) => chunked::encode_end(&trailers, &mut buffer),
+    }
+    dst.extend_from_slice(&buffer);
+}
+
+impl<M> Encoder<Frame<Request>> for StreamCodec<M> {
+    type Error = CodecError;
+    fn encode(&mut self, item: Frame<Request>, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if let Frame::Head(req) = &item {
+            self.head_requests.push(req.method());
+        }
+        encode_frame(item, dst, |mut req, buffer| {
+            let headers = req.headers_mut();
+            headers.remove(Headers::CONTENT_LENGTH);
+            headers.insert(Headers::TRANSFER_ENCODING, CHUNKED);
+            buffer.extend_from_slice(&req.head_bytes());
+            req.into_body()
+        });
+        Ok(())
+    }
+}
+
+impl<M> Encoder<Frame<Response>> for StreamCodec<M> {
+    type Error = CodecError;
+    fn encode(&mut self, item: Frame<Response>, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        match item {
+            // The head keeps the Content-Length of the body left out
+            Frame::Head(res) if self.head_requests.answer(res.status()) => {
+                self.skipping_body = true;
+                dst.extend_from_slice(&res.head_bytes());
+            }
+            Frame::Data(_) if self.skipping_body => {}
+            Frame::End(_) if self.skipping_body => self.skipping_body = false,
+            item => encode_frame(item, dst, |mut res, buffer| {
+                let headers = res.headers_mut();
+                headers.remove(Headers::CONTENT_LENGTH);
+                headers.insert(Headers::TRANSFER_ENCODING, CHUNKED);
+                buffer.extend_from_slice(&res.head_bytes());
+                res.into_body()
+            }),
+        }
+        Ok(())
+    }
+}
+
+/// A body produced by a stream of chunks, e.g. live logs.
+///
+/// ```no_run
+/// # async fn send(socket: tokio::net::TcpStream) -> Result<(), aethon::CodecError> {
+/// use aethon::{Body, Response, StreamCodec};
+/// use bytes::Bytes;
+/// use tokio_util::codec::FramedWrite;
+///
+/// let lines = futures::stream::iter(["first\n", "second\n"].map(|l| Ok(Bytes::from(l))));
+/// let mut framed = FramedWrite::new(socket, StreamCodec::<aethon::Request>::new());
+/// let head = Response::builder().build().unwrap();
+/// Body::new(lines).send(&mut framed, head).await
+/// # }
+/// ```
+pub struct Body {
+    stream: Pin<Box<dyn Stream<Item = io::Result<Bytes>> + Send>>,
+    trailers: Headers,
+}
+
+impl Body {
+    pub fn new(stream: impl Stream<Item = io::Result<Bytes>> + Send + 'static) -> Self {
+        Self {
+            stream: Box::pin(stream),
+            trailers: Headers::default(),
+        }
+    }
+
+    /// Trailers sent after the last chunk.
+    pub fn with_trailers(mut self, trailers: Headers) -> Self {
+        self.trailers = trailers;
+        self
+    }
+
+    /// Sends `head` followed by every chunk of the body and the trailers.
+    ///
+    /// An error of the stream is returned before the body is ended, the peer then never sees a
+    /// complete packet.
+    pub async fn send<S, M>(mut self, sink: &mut S, head: M) -> Result<(), CodecError>
+    where
+        S: Sink<Frame<M>, Error = CodecError> + Unpin,
+    {
+        sink.feed(Frame::Head(head)).await?;
+        while let Some(data) = self.stream.next().await {
+            sink.feed(Frame::Data(data?)).await?;
+        }
+        sink.send(Frame::End(self.trailers)).await
+    }
+}
+
+impl fmt::Debug for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Body")
+            .field("trailers", &self.trailers)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ClientCodec, Method, ServerCodec, Status};
+    use futures::stream;
+    use tokio_util::codec::{FramedRead, FramedWrite};
+
+    #[test]
+    fn test_decode_chunked_frames() {
+        let mut src = BytesMut::from(
+            "1 POST /\nTransfer-Encoding: chunked\n\n5\nHello\n6\n World\n0\nA: 1\n\n",
+        );
+        let mut codec = StreamCodec::<Request>::new();
+        let mut frames = Vec::new();
+        while let Some(frame) = codec.decode(&mut src).unwrap() {
+            frames.push(frame);
+        }
+
+        // Tests
+        assert_eq!(4, frames.len());
+        assert!(matches!(&frames[0], Frame::Head(req) if req.method() == &Method::POST));
+        assert_eq!(Frame::Data(Bytes::from("Hello")), frames[1]);
+        assert_eq!(Frame::Data(Bytes::from(" World")), frames[2]);
+        assert_eq!(Frame::End([("A", "1")].into_iter().collect()), frames[3]);
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn test_decode_length_frames() {
+        let mut src = BytesMut::from("1 200\nContent-Length: 5\n\nHel");
+        let mut codec = StreamCodec::<Response>::new();
+
+        // Tests
+        assert!(matches!(codec.decode(&mut src), Ok(Some(Frame::Head(_)))));
+        assert_eq!(
+            Some(Frame::Data(Bytes::from("Hel"))),
+            codec.decode(&mut src).unwrap()
+        );
+        assert_eq!(None, codec.decode(&mut src).unwrap());
+        src.extend_from_slice(b"lo");
+        assert_eq!(
+            Some(Frame::Data(Bytes::from("lo"))),
+            codec.decode(&mut src).unwrap()
+        );
+        assert_eq!(
+            Some(Frame::End(Headers::default())),
+            codec.decode(&mut src).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_encode_frames() {
+        let mut codec = StreamCodec::<Request>::new();
+        let mut dst = BytesMut::new();
+        let head = Response::new(1, Status::OK, Headers::default(), "Hi");
+        codec.encode(Frame::Head(head), &mut dst).unwrap();
+        codec
+            .encode(Frame::<Response>::Data(Bytes::from("!")), &mut dst)
+            .unwrap();
+        codec
+            .encode(Frame::<Response>::End(Headers::default()), &mut dst)
+            .unwrap();
+
+        // Tests
+        assert_eq!(
+            &b"1 200\nTransfer-Encoding: chunked\n\n2\nHi\n1\n!\n0\n\n"[..],
+            &dst[..]
+        );
+    }
+
+    #[test]
+    fn test_bodiless_responses() {
+        let head = Request::builder().method(Method::HEAD).build().unwrap();
+        let get = Request::builder().build().unwrap();
+        let mut client = ClientCodec::new();
+        let mut src = BytesMut::new();
+        client.encode(head.clone(), &mut src).unwrap();
+        client.encode(get, &mut src).unwrap();
+        let mut server = StreamCodec::<Request>::new();
+        let mut frames = Vec::new();
+        while let Some(frame) = server.decode(&mut src).unwrap() {
+            frames.push(frame);
+        }
+        let mut dst = BytesMut::new();
+        for _ in 0..2 {
+            let res = Response::builder().body("Hello").build().unwrap();
+            server.encode(Frame::Head(res), &mut dst).unwrap();
+            let data = Frame::<Response>::Data(Bytes::from(" World"));
+            server.encode(data, &mut dst).unwrap();
+            let end = Frame::<Response>::End(Headers::default());
+            server.encode(end, &mut dst).unwrap();
+        }
+        let mut streaming = StreamCodec::<Response>::new();
+        streaming
+            .encode(Frame::Head(head), &mut BytesMut::new())
+            .unwrap();
+        let mut responses =
+            BytesMut::from("1 200\nContent-Length: 5\n\n1 304\nContent-Length: 5\n\n");
+
+        // Tests
+        assert_eq!(4, frames.len());
+        assert!(matches!(frames[1], Frame::End(_)));
+        let res = client.decode(&mut dst).unwrap().unwrap();
+        assert_eq!(Some("5"), res.headers().get("Content-Length"));
+        assert!(res.body().is_empty());
+        let res = client.decode(&mut dst).unwrap().unwrap();
+        assert_eq!(b"Hello World", res.body());
+        assert!(dst.is_empty());
+        for _ in 0..2 {
+            let head = streaming.decode(&mut responses).unwrap();
+            assert!(matches!(head, Some(Frame::Head(_))));
+            let end = streaming.decode(&mut responses).unwrap();
+            assert_eq!(Some(Frame::End(Headers::default())), end);
+        }
+        assert!(responses.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stream_body() {
+        let (client, server) = tokio::io::duplex(8);
+        let mut writer = FramedWrite::new(client, StreamCodec::<Response>::new());
+        let mut reader = FramedRead::new(server, ServerCodec::new());
+        let chunks = ["live ", "logs ", "and more"].map(|c| Ok(Bytes::from(c)));
+        let body =
+            Body::new(stream::iter(chunks)).with_trailers([("Lines", "3")].into_iter().collect());
+
+        tokio::spawn(async move {
+            let head = Request::builder().method(Method::POST).build().unwrap();
+            body.send(&mut writer, head).await.unwrap();
+        });
+
+        // Tests
+        let req = reader.next().await.unwrap().unwrap();
+        assert_eq!(b"live logs and more", req.body());
+        assert_eq!(Some("3"), req.trailers().get("lines"));
+        assert!(reader.next().await.is_none());
+    }
+}
@@ -0,0 +1,291 @@
+//! Compressing bodies with the content codings enabled by the `gzip`, `deflate` and `zstd`
+//! features.
+
+use super::{
+    headers::{AcceptEncoding, Coding, ContentEncoding, ETag, Headers, TypedHeader},
+    request::Request,
+    response::Response,
+    status::Status,
+    Error,
+};
+use std::io;
+#[cfg(any(feature = "gzip", feature = "deflate", feature = "zstd"))]
+use std::io::Read;
+#[cfg(any(feature = "gzip", feature = "deflate"))]
+use std::io::Write;
+
+impl Coding {
+    /// Compresses `data` with this coding.
+    pub fn encode(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Coding::Identity => Ok(data.to_vec()),
+            #[cfg(feature = "gzip")]
+            Coding::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            #[cfg(feature = "deflate")]
+            Coding::Deflate => {
+                let mut encoder =
+                    flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            #[cfg(feature = "zstd")]
+            Coding::Zstd => zstd::encode_all(data, zstd::DEFAULT_COMPRESSION_LEVEL),
+            #[allow(unreachable_patterns)]
+            _ => Err(unsupported(self)),
+        }
+    }
+
+    /// Decompresses `data` encoded with this coding.
+    pub fn decode(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        self.decode_up_to(data, usize::MAX)
+    }
+
+    /// Decompresses the first `limit` bytes of `data` encoded with this coding, the decoder stops
+    /// there so a small body can't expand without bounds.
+    fn decode_up_to(&self, data: &[u8], limit: usize) -> io::Result<Vec<u8>> {
+        match self {
+            Coding::Identity => Ok(data[..data.len().min(limit)].to_vec()),
+            #[cfg(feature = "gzip")]
+            Coding::Gzip => read_up_to(flate2::read::GzDecoder::new(data), limit),
+            #[cfg(feature = "deflate")]
+            Coding::Deflate => read_up_to(flate2::read::ZlibDecoder::new(data), limit),
+            #[cfg(feature = "zstd")]
+            Coding::Zstd => read_up_to(zstd::stream::read::Decoder::new(data)?, limit),
+            #[allow(unreachable_patterns)]
+            _ => Err(unsupported(self)),
+        }
+    }
+}
+
+/// Reads the first `limit` bytes out of `decoder`.
+#[cfg(any(feature = "gzip", feature = "deflate", feature = "zstd"))]
+fn read_up_to(decoder: impl Read, limit: usize) -> io::Result<Vec<u8>> {
+    let mut decoded = Vec::new();
+    decoder.take(limit as u64).read_to_end(&mut decoded)?;
+    Ok(decoded)
+}
+
+fn unsupported(coding: &Coding) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("The {} coding isn't enabled", coding),
+    )
+}
+
+/// Undoes the `Content-Encoding` of a body, removing the header. Bodies decompressing to more
+/// than `max_len` bytes are rejected.
+fn decode_body(headers: &mut Headers, body: &mut Vec<u8>, max_len: usize) -> Result<(), Error> {
+    let Some(ContentEncoding(codings)) = headers.typed_get()? else {
+        return Ok(());
+    };
+    if !codings.iter().all(Coding::is_supported) {
+        return Err(Error::InvalidHeader {
+            name: ContentEncoding::NAME,
+            reason: "the feature of the coding isn't enabled",
+        });
+    }
+
+    // Codings are undone in the reverse order they were applied
+    for coding in codings.iter().rev() {
+        // One byte more than the limit tells a body that's too large from one that fits
+        *body = coding
+            .decode_up_to(body, max_len.saturating_add(1))
+            .map_err(|_| Error::InvalidBody("The body doesn't match its Content-Encoding"))?;
+        if body.len() > max_len {
+            return Err(Error::InvalidBody("The decompressed body is too large"));
+        }
+    }
+    headers.remove(ContentEncoding::NAME);
+    headers.remove(Headers::CONTENT_LENGTH);
+    Ok(())
+}
+
+impl Request {
+    /// Decompresses the body according to `Content-Encoding`, which is removed.
+    ///
+    /// The body isn't limited, use [`decompress_with_limit`] for bodies from untrusted peers.
+    ///
+    /// [`decompress_with_limit`]: Request::decompress_with_limit
+    pub fn decompress(self) -> Result<Request, Error> {
+        self.decompress_with_limit(usize::MAX)
+    }
+
+    /// Decompresses the body as [`decompress`] does, failing with [`Error::InvalidBody`] once it
+    /// exceeds `max_len` bytes.
+    ///
+    /// [`decompress`]: Request::decompress
+    pub fn decompress_with_limit(mut self, max_len: usize) -> Result<Request, Error> {
+        let mut body = std::mem::take(self.body_mut());
+        decode_body(self.headers_mut(), &mut body, max_len)?;
+        *self.body_mut() = body;
+        Ok(self)
+    }
+}
+
+impl Response {
+    /// Compresses the body with the best coding `req` accepts in its `Accept-Encoding` header.
+    ///
+    /// Only `200` responses with a body are compressed, once. They get `Vary: Accept-Encoding`
+    /// and a strong `ETag` becomes weak, since the compressed body differs from the original.
+    pub fn compress(mut self, req: &Request) -> Response {
+        let compressible = self.status() == Status::OK
+            && !self.body().is_empty()
+            && !self.headers().contains(ContentEncoding::NAME)
+            && !self.headers().is_chunked();
+        if !compressible {
+            return self;
+        }
+        if !self
+            .headers()
+            .get_all("Vary")
+            .flat_map(|v| v.split(','))
+            .any(|v| v.trim().eq_ignore_ascii_case(AcceptEncoding::NAME))
+        {
+            self.headers_mut().append("Vary", AcceptEncoding::NAME);
+        }
+
+        let accept = match req.headers().typed_get::<AcceptEncoding>() {
+            Ok(Some(accept)) => accept,
+            _ => return self,
+        };
+        let coding = match accept.negotiate(Coding::SUPPORTED) {
+            Some(Coding::Identity) | None => return self,
+            Some(coding) => coding,
+        };
+        let Ok(body) = coding.encode(self.body()) else {
+            return self;
+        };
+
+        *self.body_mut() = body;
+        let headers = self.headers_mut();
+        headers.remove(Headers::CONTENT_LENGTH);
+        headers.typed_insert(ContentEncoding(vec![coding]));
+        if let Ok(Some(etag)) = headers.typed_get::<ETag>() {
+            headers.typed_insert(ETag::weak(etag.tag()));
+        }
+        self
+    }
+
+    /// Decompresses the body according to `Content-Encoding`, which is removed.
+    ///
+    /// The body isn't limited, use [`decompress_with_limit`] for bodies from untrusted peers.
+    ///
+    /// [`decompress_with_limit`]: Response::decompress_with_limit
+    pub fn decompress(self) -> Result<Response, Error> {
+        self.decompress_with_limit(usize::MAX)
+    }
+
+    /// Decompresses the body as [`decompress`] does, failing with [`Error::InvalidBody`] once it
+    /// exceeds `max_len` bytes.
+    ///
+    /// [`decompress`]: Response::decompress
+    pub fn decompress_with_limit(mut self, max_len: usize) -> Result<Response, Error> {
+        let mut body = std::mem::take(self.body_mut());
+        decode_body(self.headers_mut(), &mut body, max_len)?;
+        *self.body_mut() = body;
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(body: &str) -> Response {
+        Response::builder()
+            .typed_header(ETag::strong("v1"))
+            .body(body)
+            .build()
+            .unwrap()
+    }
+
+    fn accepting(accept_encoding: &str) -> Request {
+        Request::builder()
+            .header("Accept-Encoding", accept_encoding)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_identity() {
+        let res = response("Hello").compress(&accepting("br, identity"));
+
+        // Tests
+        assert_eq!(b"Hello", res.body());
+        assert_eq!(Some("Accept-Encoding"), res.headers().get("Vary"));
+        assert_eq!(Some("\"v1\""), res.headers().get("ETag"));
+        assert_eq!(b"Hello", res.clone().decompress().unwrap().body());
+        assert_eq!(
+            Some(Error::InvalidHeader {
+                name: "Content-Encoding",
+                reason: "unknown coding"
+            }),
+            Response::builder()
+                .header("Content-Encoding", "br")
+                .body("?")
+                .build()
+                .unwrap()
+                .decompress()
+                .err()
+        );
+    }
+
+    #[cfg(all(feature = "gzip", feature = "deflate", feature = "zstd"))]
+    #[test]
+    fn test_round_trip() {
+        let body = "Hello, world! ".repeat(100);
+
+        // Tests
+        for coding in [Coding::Gzip, Coding::Deflate, Coding::Zstd] {
+            let res = response(&body).compress(&accepting(coding.name()));
+            assert!(res.body().len() < body.len());
+            assert_eq!(Some(coding.name()), res.headers().get("Content-Encoding"));
+            assert_eq!(Some("W/\"v1\""), res.headers().get("ETag"));
+
+            let parsed = Response::try_from(res.to_bytes().as_slice()).unwrap();
+            let decompressed = parsed.decompress().unwrap();
+            assert_eq!(body.as_bytes(), decompressed.body());
+            assert!(!decompressed.headers().contains("Content-Encoding"));
+            assert!(!decompressed.headers().contains("Content-Length"));
+        }
+        assert_eq!(
+            Some("zstd"),
+            response(&body)
+                .compress(&accepting("gzip;q=0.5, zstd"))
+                .headers()
+                .get("Content-Encoding")
+        );
+        let corrupt = Response::builder()
+            .header("Content-Encoding", "gzip")
+            .body("not gzip")
+            .build()
+            .unwrap();
+        assert!(matches!(corrupt.decompress(), Err(Error::InvalidBody(_))));
+    }
+
+    #[cfg(all(feature = "gzip", feature = "zstd"))]
+    #[test]
+    fn test_decompression_limit() {
+        let body = vec![0; 1024 * 1024];
+
+        // Tests
+        for coding in [Coding::Gzip, Coding::Zstd] {
+            let mut bomb = response("");
+            *bomb.body_mut() = coding.encode(&body).unwrap();
+            bomb.headers_mut()
+                .typed_insert(ContentEncoding(vec![coding]));
+            assert!(bomb.body().len() < 4 * 1024);
+            assert_eq!(
+                Err(Error::InvalidBody("The decompressed body is too large")),
+                bomb.clone().decompress_with_limit(64 * 1024).map(|_| ())
+            );
+            let fits = bomb.decompress_with_limit(body.len()).unwrap();
+            assert_eq!(body, fits.body());
+        }
+    }
+}
@@ -0,0 +1,407 @@
+//! Persistent connections, reused for many exchanges.
+//!
+//! A connection stays open until one side sends `Connection: close`, the server closes it after
+//! an idle timeout or after serving its maximal number of requests. Requests may be pipelined,
+//! the server answers them in order.
+
+use super::{
+    ClientCodec, CodecError, Connection, Error, Headers, KeepAlive, ParseErrorKind, ParseOptions,
+    Request, Response, ServerCodec, Status, Version,
+};
+use futures_util::{SinkExt, StreamExt};
+use std::{future::Future, io, time::Duration};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    time::timeout,
+};
+use tokio_util::codec::{Decoder, Encoder, Framed};
+
+/// Options of [`serve`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServeOptions {
+    /// How long to wait for the next request before closing the connection.
+    pub idle_timeout: Duration,
+    /// How many requests to serve before closing the connection, `None` for no limit.
+    pub max_requests: Option<u32>,
+    /// How strictly requests are parsed.
+    pub parse: ParseOptions,
+}
+
+impl Default for ServeOptions {
+    /// A 30 second idle timeout without a limit of requests, requests are parsed leniently with
+    /// bodies up to 16 MiB.
+    fn default() -> Self {
+        Self {
+            idle_timeout: Duration::from_secs(30),
+            max_requests: None,
+            parse: ParseOptions::default().with_max_body_len(16 * 1024 * 1024),
+        }
+    }
+}
+
+/// Serves requests on a persistent connection until it's closed.
+///
+/// Responses are sent in the order of the requests, so pipelined requests are handled one after
+/// another. Responses advertise the idle timeout with `Keep-Alive`, the last one carries
+/// `Connection: close`. A malformed request is answered with `400 Bad Request`, or `413`, `414`
+/// or `431` if it exceeds the limits of [`ServeOptions::parse`], and its error is returned once
+/// the connection is closed. Responses to `HEAD` requests are sent without their body, keeping
+/// the `Content-Length` it would have.
+pub async fn serve<IO, F, Fut>(
+    io: IO, options: ServeOptions, mut handler: F,
+) -> Result<(), CodecError>
+where
+    IO: AsyncRead + AsyncWrite + Unpin,
+    F: FnMut(Request) -> Fut,
+    Fut: Future<Output = Response>,
+{
+    let mut framed = Framed::new(io, ServerCodec::with_options(options.parse));
+    let mut served = 0;
+
+    let result = loop {
+        let req = match timeout(options.idle_timeout, framed.next()).await {
+            // Idle or closed by the client
+            Err(_) | Ok(None) => break Ok(()),
+            Ok(Some(Ok(req))) => req,
+            Ok(Some(Err(CodecError::Aethon(e)))) => {
+                let mut res = Response::new(Version::V1, error_status(&e), Headers::default(), "");
+                res.headers_mut().typed_insert(Connection::Close);
+                framed.send(res).await?;
+                break Err(e.into());
+            }
+            Ok(Some(Err(e))) => return Err(e),
+        };
+
+        served += 1;
+        let last = !req.keep_alive() || options.max_requests.is_some_and(|max| served >= max);
+        let mut res = handler(req).await;
+        if last {
+            res.headers_mut().typed_insert(Connection::Close);
+        } else if res.keep_alive() {
+            res.headers_mut().typed_insert(KeepAlive {
+                timeout: Some(options.idle_timeout),
+                max: options.max_requests.map(|max| max - served),
+            });
+        }
+
+        let close = !res.keep_alive();
+        framed.send(res).await?;
+        if close {
+            break Ok(());
+        }
+    };
+
+    close::<_, _, Response>(&mut framed, options.idle_timeout).await?;
+    result
+}
+
+/// Returns the status answering a request that failed to parse with `e`.
+fn error_status(e: &Error) -> Status {
+    let Error::ParseError(e) = e else {
+        return Status::BadRequest;
+    };
+    match e.kind() {
+        ParseErrorKind::HeaderTooLarge | ParseErrorKind::TooManyHeaders => {
+            Status::RequestHeaderFieldsTooLarge
+        }
+        ParseErrorKind::PathTooLong => Status::UriTooLong,
+        ParseErrorKind::BodyTooLarge => Status::PayloadTooLarge,
+        _ => Status::BadRequest,
+    }
+}
+
+/// Closes the connection cleanly: shuts down the writing side, so the peer reads everything that
+/// was sent, and waits for the peer to close its side.
+async fn close<IO, C, I>(framed: &mut Framed<IO, C>, wait: Duration) -> Result<(), CodecError>
+where
+    IO: AsyncRead + AsyncWrite + Unpin,
+    C: Decoder<Error = CodecError> + Encoder<I, Error = CodecError>,
+{
+    SinkExt::<I>::close(framed).await?;
+    // Packets sent after the close are dropped
+    let _ = timeout(wait, async {
+        while let Some(Ok(_)) = framed.next().await {}
+    })
+    .await;
+    Ok(())
+}
+
+/// The client side of a persistent connection.
+#[derive(Debug)]
+pub struct ClientConnection<IO> {
+    framed: Framed<IO, ClientCodec>,
+    reusable: bool,
+}
+
+impl<IO: AsyncRead + AsyncWrite + Unpin> ClientConnection<IO> {
+    pub fn new(io: IO) -> Self {
+        Self {
+            framed: Framed::new(io, ClientCodec::new()),
+            reusable: true,
+        }
+    }
+
+    /// Whether more requests can be sent, it's `false` once either side sent `Connection: close`
+    /// or an exchange failed.
+    pub fn is_reusable(&self) -> bool {
+        self.reusable
+    }
+
+    /// Sends a request and waits for its response.
+    pub async fn send(&mut self, req: Request) -> Result<Response, CodecError> {
+        let mut responses = self.pipeline([req]).await?;
+        Ok(responses.remove(0))
+    }
+
+    /// Sends all the requests at once and returns their responses in the same order.
+    ///
+    /// Fails if the server closes the connection before answering all of them.
+    pub async fn pipeline(
+        &mut self, reqs: impl IntoIterator<Item = Request>,
+    ) -> Result<Vec<Response>, CodecError> {
+        if !self.reusable {
+            return Err(
+                io::Error::new(io::ErrorKind::NotConnected, "The connection is closed").into(),
+            );
+        }
+
+        // Only reusable again once every response arrived, an error or a cancelled exchange leaves
+        // the stream in an unknown state
+        self.reusable = false;
+        let mut reusable = true;
+        let mut sent = 0;
+        for req in reqs {
+            reusable &= req.keep_alive();
+            self.framed.feed(req).await?;
+            sent += 1;
+        }
+        SinkExt::<Request>::flush(&mut self.framed).await?;
+
+        let mut responses = Vec::with_capacity(sent);
+        for _ in 0..sent {
+            let res = self.framed.next().await.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "The connection was closed before all responses arrived",
+                )
+            })??;
+            reusable &= res.keep_alive();
+            responses.push(res);
+        }
+        self.reusable = reusable;
+        Ok(responses)
+    }
+
+    /// Ends the connection, waiting up to `wait` for the server to close its side.
+    pub async fn close(mut self, wait: Duration) -> Result<(), CodecError> {
+        close::<_, _, Request>(&mut self.framed, wait).await
+    }
+
+    pub fn into_inner(self) -> IO {
+        self.framed.into_inner()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Method;
+    use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt, DuplexStream};
+
+    fn request(path: &str) -> Request {
+        Request::builder().path(path).build().unwrap()
+    }
+
+    /// Serves a connection answering with the path of every request.
+    fn server(
+        options: ServeOptions,
+    ) -> (
+        DuplexStream,
+        tokio::task::JoinHandle<Result<(), CodecError>>,
+    ) {
+        let (client, server) = duplex(64);
+        let handle = tokio::spawn(serve(server, options, |req: Request| async move {
+            Response::builder().body(req.path()).build().unwrap()
+        }));
+        (client, handle)
+    }
+
+    #[tokio::test]
+    async fn test_sequential_requests() {
+        let (io, server) = server(ServeOptions::default());
+        let mut conn = ClientConnection::new(io);
+        let first = conn.send(request("/first")).await.unwrap();
+        let second = conn.send(request("/second")).await.unwrap();
+
+        // Tests
+        assert_eq!(b"/first", first.body());
+        assert_eq!(b"/second", second.body());
+        assert_eq!(
+            Ok(Some(KeepAlive {
+                timeout: Some(Duration::from_secs(30)),
+                max: None
+            })),
+            second.headers().typed_get()
+        );
+        assert!(conn.is_reusable());
+        conn.close(Duration::from_secs(1)).await.unwrap();
+        assert!(server.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_pipelined_requests() {
+        let (io, _server) = server(ServeOptions::default());
+        let mut conn = ClientConnection::new(io);
+        let paths = ["/style.css", "/logo.png", "/script.js"];
+        let responses = conn.pipeline(paths.map(request)).await.unwrap();
+
+        // Tests
+        let bodies: Vec<&[u8]> = responses.iter().map(Response::body).collect();
+        assert_eq!(paths.map(str::as_bytes).to_vec(), bodies);
+    }
+
+    #[tokio::test]
+    async fn test_head_request() {
+        let (io, _server) = server(ServeOptions::default());
+        let mut conn = ClientConnection::new(io);
+        let head = Request::builder()
+            .method(Method::HEAD)
+            .path("/hello")
+            .build()
+            .unwrap();
+        let res = conn.send(head).await.unwrap();
+        let next = conn.send(request("/next")).await.unwrap();
+
+        // Tests
+        assert_eq!(Some("6"), res.headers().get("Content-Length"));
+        assert!(res.body().is_empty());
+        assert_eq!(b"/next", next.body());
+    }
+
+    #[tokio::test]
+    async fn test_close_handshake() {
+        let (io, server) = server(ServeOptions::default());
+        let mut conn = ClientConnection::new(io);
+        let mut last = request("/last");
+        last.headers_mut().typed_insert(Connection::Close);
+        let res = conn.send(last).await.unwrap();
+
+        // Tests
+        assert!(!res.keep_alive());
+        assert!(!conn.is_reusable());
+        assert!(conn.send(request("/")).await.is_err());
+        conn.close(Duration::from_secs(1)).await.unwrap();
+        assert!(server.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_max_requests() {
+        let options = ServeOptions {
+            max_requests: Some(2),
+            ..Default::default()
+        };
+        let (io, _server) = server(options);
+        let mut conn = ClientConnection::new(io);
+        let result = conn
+            .pipeline([request("/1"), request("/2"), request("/3")])
+            .await;
+
+        // Tests
+        assert!(result.is_err());
+        assert!(!conn.is_reusable());
+    }
+
+    #[tokio::test]
+    async fn test_idle_timeout() {
+        let options = ServeOptions {
+            idle_timeout: Duration::from_millis(20),
+            ..Default::default()
+        };
+        let (mut io, server) = server(options);
+
+        // Tests
+        assert!(server.await.unwrap().is_ok());
+        assert_eq!(0, io.read(&mut [0; 8]).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_failed_exchange() {
+        let (io, mut server) = duplex(64);
+        let mut conn = ClientConnection::new(io);
+        server.write_all(b"1 OK\n\n\n").await.unwrap();
+
+        // Tests
+        assert!(matches!(
+            conn.send(request("/")).await,
+            Err(CodecError::Aethon(_))
+        ));
+        assert!(!conn.is_reusable());
+        assert!(conn.send(request("/")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bad_request() {
+        let (mut io, server) = server(ServeOptions::default());
+        io.write_all(b"one GET /\n\n\n").await.unwrap();
+        let mut response = Vec::new();
+        io.read_to_end(&mut response).await.unwrap();
+        drop(io);
+
+        // Tests
+        let res = Response::try_from(response.as_slice()).unwrap();
+        assert_eq!(Status::BadRequest, res.status());
+        assert!(!res.keep_alive());
+        assert!(server.await.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_conflicting_framing() {
+        // Each packet smuggles a second request if its first framing header is believed
+        let packets: [&[u8]; 2] = [
+            b"1 POST /\nContent-Length: 0\nContent-Length: 12\n\n1 GET /admin\n\n\n",
+            b"1 POST /\nTransfer-Encoding: chunked\nTransfer-Encoding: identity\n\n0\n\n",
+        ];
+
+        // Tests
+        for packet in packets {
+            let (mut io, server) = server(ServeOptions::default());
+            io.write_all(packet).await.unwrap();
+            let mut response = Vec::new();
+            io.read_to_end(&mut response).await.unwrap();
+            drop(io);
+
+            let res = Response::try_from(response.as_slice()).unwrap();
+            assert_eq!(Status::BadRequest, res.status());
+            assert!(!res.keep_alive());
+            assert!(!response.windows(6).any(|w| w == b"/admin"));
+            assert!(server.await.unwrap().is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_limits() {
+        let options = ServeOptions {
+            parse: ParseOptions::default()
+                .with_max_headers(2)
+                .with_max_body_len(4),
+            ..Default::default()
+        };
+        let status = |packet: &'static [u8]| async move {
+            let (mut io, _server) = server(options);
+            io.write_all(packet).await.unwrap();
+            let mut response = Vec::new();
+            io.read_to_end(&mut response).await.unwrap();
+            Response::try_from(response.as_slice()).unwrap().status()
+        };
+
+        // Tests
+        assert_eq!(
+            Status::PayloadTooLarge,
+            status(b"1 POST /\nContent-Length: 5\n\n").await
+        );
+        assert_eq!(
+            Status::RequestHeaderFieldsTooLarge,
+            status(b"1 GET /\na: 1\nb: 2\nc: 3\n").await
+        );
+    }
+}
@@ -0,0 +1,377 @@
+//! Storage of the cookies a client received, following RFC 6265.
+
+use super::{Cookie, Cookies, Response, SetCookie, TypedHeader, Url};
+use std::{
+    fmt::Write as _,
+    fs, io,
+    net::IpAddr,
+    path::Path,
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Keeps the cookies set by servers and picks the ones to send back with each request.
+///
+/// A jar is shared by reference, e.g. between a [`Client`](crate::Client) and the code saving it
+/// to disk.
+///
+/// ```
+/// use aethon::{Cookie, CookieJar, Url};
+///
+/// let jar = CookieJar::new();
+/// let url: Url = "aethon://example/account/login".parse().unwrap();
+/// jar.store(&url, Cookie::new("id", "a3fWa"));
+///
+/// let cookies = jar.header(&"aethon://example/account/".parse().unwrap()).unwrap();
+/// assert_eq!(Some("a3fWa"), cookies.get("id"));
+/// assert_eq!(None, jar.header(&"aethon://example/".parse().unwrap()));
+/// ```
+#[derive(Debug, Default)]
+pub struct CookieJar {
+    entries: Mutex<Vec<Entry>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    name: Box<str>,
+    value: Box<str>,
+    /// The host that set the cookie, or the domain from its attribute.
+    domain: Box<str>,
+    /// The cookie had no `Domain`, it isn't sent to subdomains.
+    host_only: bool,
+    path: Box<str>,
+    /// `None` for a session cookie.
+    expires: Option<SystemTime>,
+    secure: bool,
+    http_only: bool,
+}
+
+impl Entry {
+    fn matches(&self, url: &Url, now: SystemTime) -> bool {
+        let domain = match self.host_only {
+            true => url.host() == &*self.domain,
+            false => domain_matches(url.host(), &self.domain),
+        };
+        domain
+            && path_matches(url.path(), &self.path)
+            && (!self.secure || url.is_secure())
+            && !self.is_expired(now)
+    }
+
+    fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+}
+
+impl CookieJar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores a cookie set by the response to `url`, returns whether it was accepted.
+    ///
+    /// A cookie replaces the one with the same name, domain and path, an expired cookie only
+    /// removes it. Cookies for another domain and `Secure` cookies set over `aethon://` are
+    /// rejected.
+    pub fn store(&self, url: &Url, cookie: Cookie) -> bool {
+        let now = SystemTime::now();
+        let expires = cookie.expiry(now);
+        let (domain, host_only) = match cookie.domain {
+            Some(domain) if domain_matches(url.host(), &domain) => (domain, false),
+            Some(_) => return false,
+            None => (url.host().into(), true),
+        };
+        if cookie.secure && !url.is_secure() {
+            return false;
+        }
+
+        let entry = Entry {
+            expires,
+            path: cookie
+                .path
+                .unwrap_or_else(|| default_path(url.path()).into()),
+            name: cookie.name,
+            value: cookie.value,
+            domain,
+            host_only,
+            secure: cookie.secure,
+            http_only: cookie.http_only,
+        };
+
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|e| {
+            (&e.name, &e.domain, &e.path) != (&entry.name, &entry.domain, &entry.path)
+                && !e.is_expired(now)
+        });
+        if !entry.is_expired(now) {
+            entries.push(entry);
+        }
+        true
+    }
+
+    /// Stores the cookies set by `res`, the response to `url`, malformed ones are ignored.
+    pub fn store_response(&self, url: &Url, res: &Response) {
+        for value in res.headers().get_all(SetCookie::NAME) {
+            if let Ok(cookie) = Cookie::parse(value) {
+                self.store(url, cookie);
+            }
+        }
+    }
+
+    /// Returns the cookies to send to `url`, the ones with longer paths first.
+    pub fn cookies(&self, url: &Url) -> Vec<Cookie> {
+        let now = SystemTime::now();
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let mut matching: Vec<&Entry> = entries.iter().filter(|e| e.matches(url, now)).collect();
+        matching.sort_by_key(|e| std::cmp::Reverse(e.path.len()));
+
+        matching
+            .into_iter()
+            .map(|e| Cookie {
+                name: e.name.clone(),
+                value: e.value.clone(),
+                expires: e.expires,
+                max_age: None,
+                domain: (!e.host_only).then(|| e.domain.clone()),
+                path: Some(e.path.clone()),
+                secure: e.secure,
+                http_only: e.http_only,
+            })
+            .collect()
+    }
+
+    /// Returns the `Cookie` header of a request to `url`, `None` if no cookie matches.
+    pub fn header(&self, url: &Url) -> Option<Cookies> {
+        let cookies = self.cookies(url);
+        if cookies.is_empty() {
+            return None;
+        }
+        Some(Cookies(
+            cookies.into_iter().map(|c| (c.name, c.value)).collect(),
+        ))
+    }
+
+    /// Number of stored cookies, including expired ones not removed yet.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    /// Writes the cookies that outlive the session to `path` in the Netscape cookie file format,
+    /// which curl and browsers also read.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let now = SystemTime::now();
+        let mut file = String::from("# Netscape HTTP Cookie File\n");
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+
+        for entry in entries.iter().filter(|e| !e.is_expired(now)) {
+            let Some(expires) = entry.expires else {
+                continue;
+            };
+            let flag = |b: bool| if b { "TRUE" } else { "FALSE" };
+            // Writing into a String can't fail
+            let _ = writeln!(
+                file,
+                "{}{}{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                if entry.http_only { "#HttpOnly_" } else { "" },
+                if entry.host_only { "" } else { "." },
+                entry.domain,
+                flag(!entry.host_only),
+                entry.path,
+                flag(entry.secure),
+                expires
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+                entry.name,
+                entry.value
+            );
+        }
+
+        fs::write(path, file)
+    }
+
+    /// Reads a jar written by [`CookieJar::save`].
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = fs::read_to_string(path)?;
+        let mut entries = Vec::new();
+
+        for (i, line) in file.lines().enumerate() {
+            let (http_only, line) = match line.strip_prefix("#HttpOnly_") {
+                Some(line) => (true, line),
+                None => (false, line),
+            };
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let entry = parse_line(line, http_only).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Malformed cookie on line {}", i + 1),
+                )
+            })?;
+            entries.push(entry);
+        }
+
+        Ok(Self {
+            entries: Mutex::new(entries),
+        })
+    }
+}
+
+/// Parses a line of a Netscape cookie file without its `#HttpOnly_` prefix.
+fn parse_line(line: &str, http_only: bool) -> Option<Entry> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    let [domain, subdomains, path, secure, expires, name, value] = fields[..] else {
+        return None;
+    };
+    let flag = |s: &str| match s {
+        "TRUE" => Some(true),
+        "FALSE" => Some(false),
+        _ => None,
+    };
+
+    Some(Entry {
+        name: name.into(),
+        value: value.into(),
+        domain: domain.trim_start_matches('.').to_ascii_lowercase().into(),
+        host_only: !flag(subdomains)?,
+        path: path.into(),
+        // 0 marks a session cookie
+        expires: match expires.parse().ok()? {
+            0 => None,
+            secs => Some(UNIX_EPOCH + Duration::from_secs(secs)),
+        },
+        secure: flag(secure)?,
+        http_only,
+    })
+}
+
+/// Whether a cookie for `domain` can be sent to `host`, the host or one of its subdomains.
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain
+        || (host.len() > domain.len()
+            && host.ends_with(domain)
+            && host[..host.len() - domain.len()].ends_with('.')
+            && host.parse::<IpAddr>().is_err())
+}
+
+/// Whether a cookie for `cookie_path` can be sent with a request for `path`.
+fn path_matches(path: &str, cookie_path: &str) -> bool {
+    path == cookie_path
+        || (path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || path[cookie_path.len()..].starts_with('/')))
+}
+
+/// The directory of the request's path, the path of cookies set without one.
+fn default_path(path: &str) -> &str {
+    match path.rfind('/') {
+        Some(0) | None => "/",
+        Some(i) => &path[..i],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        s.parse().unwrap()
+    }
+
+    fn names(jar: &CookieJar, to: &str) -> Vec<String> {
+        jar.cookies(&url(to))
+            .into_iter()
+            .map(|c| c.name.into())
+            .collect()
+    }
+
+    #[test]
+    fn test_matching() {
+        let jar = CookieJar::new();
+        let origin = url("aethons://www.example/docs/guide");
+        let parse = |s| Cookie::parse(s).unwrap();
+
+        // Tests
+        assert!(jar.store(&origin, parse("host=1")));
+        assert!(jar.store(&origin, parse("domain=1; Domain=example; Path=/")));
+        assert!(jar.store(&origin, parse("secure=1; Secure; Path=/")));
+        assert!(jar.store(&origin, parse("deep=1; Path=/docs/guide")));
+        assert!(!jar.store(&origin, parse("other=1; Domain=other")));
+        assert!(!jar.store(&url("aethon://example/"), parse("s=1; Secure")));
+
+        assert_eq!(
+            vec!["deep", "host", "domain", "secure"],
+            names(&jar, "aethons://www.example/docs/guide")
+        );
+        assert_eq!(
+            vec!["host", "domain"],
+            names(&jar, "aethon://www.example/docs")
+        );
+        assert_eq!(vec!["domain"], names(&jar, "aethon://api.example/docs"));
+        assert!(names(&jar, "aethon://www.example/documents").contains(&"domain".into()));
+        assert!(!names(&jar, "aethon://www.example/documents").contains(&"host".into()));
+        assert_eq!(None, jar.header(&url("aethon://example2/")));
+    }
+
+    #[test]
+    fn test_replace_and_expire() {
+        let jar = CookieJar::new();
+        let origin = url("aethon://example/");
+
+        jar.store(&origin, Cookie::new("id", "1"));
+        jar.store(&origin, Cookie::new("id", "2"));
+        // Tests
+        assert_eq!(Some("2"), jar.header(&origin).unwrap().get("id"));
+
+        jar.store(&origin, Cookie::parse("id=; Max-Age=0").unwrap());
+        assert!(jar.is_empty());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let jar = CookieJar::new();
+        let origin = url("aethons://example/app/");
+        jar.store(
+            &origin,
+            Cookie::parse("a=1; Max-Age=3600; HttpOnly").unwrap(),
+        );
+        jar.store(
+            &origin,
+            Cookie::parse("b=2; Max-Age=3600; Domain=example; Secure").unwrap(),
+        );
+        jar.store(&origin, Cookie::new("session", "3"));
+
+        let path = std::env::temp_dir().join(format!("aethon-cookies-{}", std::process::id()));
+        jar.save(&path).unwrap();
+        let loaded = CookieJar::load(&path).unwrap();
+        let file = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // Tests
+        assert!(file.contains("#HttpOnly_example\tFALSE\t/app\tFALSE\t"));
+        assert!(file.contains("\n.example\tTRUE\t/app\tTRUE\t"));
+        assert_eq!(2, loaded.len());
+        assert_eq!(vec!["a", "b"], names(&loaded, "aethons://example/app/x"));
+        assert!(loaded.cookies(&origin)[0].http_only);
+    }
+
+    #[test]
+    fn test_default_path() {
+        // Tests
+        assert_eq!("/", default_path("/"));
+        assert_eq!("/", default_path("/index.html"));
+        assert_eq!("/docs", default_path("/docs/guide"));
+        assert_eq!("/docs/guide", default_path("/docs/guide/"));
+    }
+}
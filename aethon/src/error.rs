@@ -4,7 +4,20 @@ use std::fmt;
 pub enum Error {
     WrongMethod,
     WrongStatus,
-    ParseError(&'static str),
+    WrongVersion,
+    /// The packet is malformed, see [`ParseError::kind`] for why.
+    ParseError(ParseError),
+    /// A builder was given values that can't be written as a valid packet.
+    BuildError(&'static str),
+    /// A header's value doesn't match its expected format.
+    InvalidHeader {
+        name: &'static str,
+        reason: &'static str,
+    },
+    /// The URL is malformed or can't be built.
+    InvalidUrl(&'static str),
+    /// The body can't be decoded.
+    InvalidBody(&'static str),
 }
 
 impl fmt::Display for Error {
@@ -12,7 +25,158 @@ impl fmt::Display for Error {
         match self {
             Self::WrongMethod => write!(f, "The request METHOD is invalid"),
             Self::WrongStatus => write!(f, "The response STATUS is invalid"),
+            Self::WrongVersion => write!(f, "The protocol VERSION is invalid"),
             Self::ParseError(e) => write!(f, "Failed to parse the Aethon packet: {}", e),
+            Self::BuildError(e) => write!(f, "Failed to build the Aethon packet: {}", e),
+            Self::InvalidHeader { name, reason } => {
+                write!(f, "The {} header is invalid: {}", name, reason)
+            }
+            Self::InvalidUrl(e) => write!(f, "The URL is invalid: {}", e),
+            Self::InvalidBody(e) => write!(f, "The body is invalid: {}", e),
         }
     }
 }
+
+impl std::error::Error for Error {}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Self::ParseError(e)
+    }
+}
+
+/// Where and why parsing a packet failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    kind: ParseErrorKind,
+    offset: usize,
+    line: usize,
+}
+
+impl ParseError {
+    /// An error at `offset` of `input`.
+    pub(crate) fn at(kind: ParseErrorKind, input: &[u8], offset: usize) -> Self {
+        Self {
+            kind,
+            offset,
+            line: line_of(input, offset),
+        }
+    }
+
+    /// Moves an error found in the part of `input` starting at `base` to its position in `input`.
+    pub(crate) fn shift(self, input: &[u8], base: usize) -> Self {
+        Self {
+            kind: self.kind,
+            offset: base + self.offset,
+            line: line_of(input, base) + self.line - 1,
+        }
+    }
+
+    pub fn kind(&self) -> ParseErrorKind {
+        self.kind
+    }
+
+    /// Byte offset of the error from the start of the input.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Line of the error, starting at 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+/// The line `offset` is on, starting at 1.
+fn line_of(input: &[u8], offset: usize) -> usize {
+    let offset = offset.min(input.len());
+    input[..offset].iter().filter(|&&b| b == b'\n').count() + 1
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {}, byte {}",
+            self.kind, self.line, self.offset
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Why parsing a packet failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseErrorKind {
+    /// The start line or a header block isn't valid UTF-8.
+    InvalidUtf8,
+    InvalidVersion,
+    InvalidMethod,
+    InvalidPath,
+    InvalidStatus,
+    /// A header line has no `:`.
+    MissingColon,
+    InvalidHeaderName,
+    EmptyHeaderValue,
+    /// A `\r`, only accepted before `\n` when parsing leniently.
+    CarriageReturn,
+    /// Whitespace where strict parsing doesn't allow it, e.g. at the end of a line.
+    Whitespace,
+    InvalidContentLength,
+    /// Both `Content-Length` and `Transfer-Encoding` were sent.
+    ConflictingFraming,
+    UnsupportedTransferEncoding,
+    /// The body is shorter than its `Content-Length`, or its last chunk is missing.
+    IncompleteBody,
+    InvalidChunkSize,
+    /// The data of a chunk isn't followed by a newline.
+    MissingChunkEnd,
+    /// Data was decoded after the end of a chunked body.
+    BodyEnded,
+    /// The start line or a header line is longer than [`ParseOptions`] allow.
+    ///
+    /// [`ParseOptions`]: crate::ParseOptions
+    HeaderTooLarge,
+    /// There are more headers, or trailers, than [`ParseOptions`] allow.
+    ///
+    /// [`ParseOptions`]: crate::ParseOptions
+    TooManyHeaders,
+    /// The path is longer than [`ParseOptions`] allow.
+    ///
+    /// [`ParseOptions`]: crate::ParseOptions
+    PathTooLong,
+    /// The body is longer than [`ParseOptions`] allow.
+    ///
+    /// [`ParseOptions`]: crate::ParseOptions
+    BodyTooLarge,
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            Self::InvalidUtf8 => "Invalid UTF-8",
+            Self::InvalidVersion => "Invalid version",
+            Self::InvalidMethod => "Invalid method",
+            Self::InvalidPath => "Invalid path",
+            Self::InvalidStatus => "Invalid status",
+            Self::MissingColon => "Header line without a colon",
+            Self::InvalidHeaderName => "Invalid header name",
+            Self::EmptyHeaderValue => "Header value can't be empty",
+            Self::CarriageReturn => "Unexpected carriage return",
+            Self::Whitespace => "Unexpected whitespace",
+            Self::InvalidContentLength => "Content-Length isn't a number",
+            Self::ConflictingFraming => "Content-Length can't be used with chunked encoding",
+            Self::UnsupportedTransferEncoding => "Unsupported Transfer-Encoding",
+            Self::IncompleteBody => "Body is incomplete",
+            Self::InvalidChunkSize => "Invalid chunk size",
+            Self::MissingChunkEnd => "Chunk isn't ended by a newline",
+            Self::BodyEnded => "Chunked body has already ended",
+            Self::HeaderTooLarge => "Line is too long",
+            Self::TooManyHeaders => "Too many headers",
+            Self::PathTooLong => "Path is too long",
+            Self::BodyTooLarge => "Body is too large",
+        };
+        f.write_str(description)
+    }
+}
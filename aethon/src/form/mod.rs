@@ -0,0 +1,181 @@
+//! Form bodies, `application/x-www-form-urlencoded` and `multipart/form-data`.
+
+mod multipart;
+pub(crate) use multipart::boundary;
+pub use multipart::{Multipart, MultipartDecoder, MultipartEvent, Part};
+
+use super::{
+    headers::{ContentType, TypedHeader},
+    request::{Request, RequestBuilder},
+    url, Error,
+};
+
+/// The fields of an `application/x-www-form-urlencoded` body, in order.
+///
+/// ```
+/// use aethon::Form;
+///
+/// let form: Form = [("q", "aethon packets"), ("page", "2")].into_iter().collect();
+/// assert_eq!("q=aethon+packets&page=2", form.encode());
+/// assert_eq!(Some("2"), Form::parse(b"q=a&page=2").get("page"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Form(Vec<(String, String)>);
+
+impl Form {
+    /// Media type of url-encoded form bodies.
+    pub const MEDIA_TYPE: &'static str = "application/x-www-form-urlencoded";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses an url-encoded body, `+` is decoded as a space and invalid UTF-8 is replaced.
+    pub fn parse(body: &[u8]) -> Self {
+        String::from_utf8_lossy(body)
+            .split('&')
+            .filter(|p| !p.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (url::decode(key, true), url::decode(value, true))
+            })
+            .collect()
+    }
+
+    /// Adds a field, keys may repeat.
+    pub fn append(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.0.push((key.into(), value.into()));
+    }
+
+    /// Returns the first value of the field `key`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.iter().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+
+    /// Returns every value of the field `key`.
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> {
+        self.iter().filter(move |(k, _)| *k == key).map(|(_, v)| v)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Url-encodes the fields, spaces become `+`.
+    pub fn encode(&self) -> String {
+        self.0
+            .iter()
+            .map(|(k, v)| format!("{}={}", url::encode_component(k), url::encode_component(v)))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Form {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
+    }
+}
+
+impl Request {
+    /// Parses an `application/x-www-form-urlencoded` body.
+    pub fn form(&self) -> Result<Form, Error> {
+        match self.headers().typed_get::<ContentType>()? {
+            Some(content_type) if content_type.is(Form::MEDIA_TYPE) => Ok(Form::parse(self.body())),
+            _ => Err(Error::InvalidHeader {
+                name: ContentType::NAME,
+                reason: "expected an url-encoded form",
+            }),
+        }
+    }
+
+    /// Parses a `multipart/form-data` body, the data of each part is borrowed from the body.
+    ///
+    /// Use a [`MultipartDecoder`] instead to parse the body while it arrives.
+    pub fn multipart(&self) -> Result<Vec<(Part, &[u8])>, Error> {
+        let mut decoder = MultipartDecoder::from_headers(self.headers())?;
+        let body = self.body();
+        let mut parts = Vec::new();
+        let (mut len, mut start) = (0, 0);
+
+        loop {
+            match decoder.decode(&body[len..])? {
+                (n, Some(MultipartEvent::Part(part))) => {
+                    len += n;
+                    start = len;
+                    parts.push((part, &body[len..len]));
+                }
+                (n, Some(MultipartEvent::Data(_))) => {
+                    len += n;
+                    if let Some((_, data)) = parts.last_mut() {
+                        *data = &body[start..len];
+                    }
+                }
+                (_, Some(MultipartEvent::End)) => return Ok(parts),
+                (_, None) => return Err(Error::InvalidBody("the multipart body is incomplete")),
+            }
+        }
+    }
+}
+
+impl RequestBuilder {
+    /// Sets an url-encoded `form` as the body, with its `Content-Type`.
+    pub fn form(self, form: &Form) -> Self {
+        self.typed_header(ContentType::new(Form::MEDIA_TYPE))
+            .body(form.encode())
+    }
+
+    /// Sets a `multipart/form-data` body, with its `Content-Type`.
+    pub fn multipart(self, multipart: Multipart) -> Self {
+        self.typed_header(multipart.content_type())
+            .body(multipart.into_body())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_form() {
+        let mut form = Form::new();
+        form.append("name", "Jane Doe");
+        form.append("tag", "a&b=c");
+        form.append("tag", "é");
+        let req = Request::builder().form(&form).build().unwrap();
+
+        // Tests
+        assert_eq!("name=Jane+Doe&tag=a%26b%3Dc&tag=%C3%A9", form.encode());
+        assert_eq!(
+            Some("application/x-www-form-urlencoded"),
+            req.headers().get("Content-Type")
+        );
+        assert_eq!(Ok(form.clone()), req.form());
+        assert_eq!(
+            vec!["a&b=c", "é"],
+            req.form().unwrap().get_all("tag").collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Form::from_iter([("a", ""), ("b", "1 2")]),
+            Form::parse(b"a&&b=1%202")
+        );
+        assert!(Request::builder()
+            .body("a=1")
+            .build()
+            .unwrap()
+            .form()
+            .is_err());
+    }
+}
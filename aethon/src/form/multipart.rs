@@ -0,0 +1,450 @@
+//! `multipart/form-data` bodies, fields and files each sent in their own part.
+//!
+//! Parts are separated by a boundary line, `--` and the boundary, and start with headers like a
+//! packet. The last boundary is followed by `--`:
+//!
+//! ```text
+//! --b\r\nContent-Disposition: form-data; name="a"\r\n\r\n1\r\n--b--\r\n
+//! ```
+
+use crate::{
+    headers::{ContentDisposition, ContentType, Headers, HeadersRef, TypedHeader},
+    parser::{self, HeadLimits, ParseOptions},
+    Error,
+};
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+/// Returns a random boundary for a multipart body.
+pub(crate) fn boundary() -> String {
+    format!("{:016x}", RandomState::new().build_hasher().finish())
+}
+
+/// A `multipart/form-data` body being built.
+///
+/// ```
+/// use aethon::{ContentType, Multipart, Request};
+///
+/// let req = Request::builder()
+///     .multipart(
+///         Multipart::new()
+///             .text("title", "Notes")
+///             .file("file", "notes.txt", ContentType::text(), b"Hello"),
+///     )
+///     .build()
+///     .unwrap();
+/// let parts = req.multipart().unwrap();
+/// assert_eq!(Some("notes.txt"), parts[1].0.filename());
+/// assert_eq!(b"Hello", parts[1].1);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Multipart {
+    boundary: String,
+    body: Vec<u8>,
+}
+
+impl Multipart {
+    pub fn new() -> Self {
+        Self::with_boundary(boundary())
+    }
+
+    /// A body separating its parts with `boundary`, which must not appear in their data.
+    pub fn with_boundary(boundary: impl Into<String>) -> Self {
+        Self {
+            boundary: boundary.into(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Adds a text field.
+    pub fn text(self, name: &str, value: &str) -> Self {
+        self.part(ContentDisposition::form_data(name), None, value.as_bytes())
+    }
+
+    /// Adds a file.
+    pub fn file(self, name: &str, filename: &str, content_type: ContentType, data: &[u8]) -> Self {
+        let disposition = ContentDisposition::form_data(name).with_param("filename", filename);
+        self.part(disposition, Some(content_type), data)
+    }
+
+    /// Adds a part with any headers.
+    pub fn part(
+        mut self, disposition: ContentDisposition, content_type: Option<ContentType>, data: &[u8],
+    ) -> Self {
+        let mut head = format!("--{}\r\n", self.boundary);
+        head.push_str(&format!(
+            "{}: {}\r\n",
+            ContentDisposition::NAME,
+            disposition.encode()
+        ));
+        if let Some(content_type) = content_type {
+            head.push_str(&format!(
+                "{}: {}\r\n",
+                ContentType::NAME,
+                content_type.encode()
+            ));
+        }
+        head.push_str("\r\n");
+
+        self.body.extend_from_slice(head.as_bytes());
+        self.body.extend_from_slice(data);
+        self.body.extend_from_slice(b"\r\n");
+        self
+    }
+
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// `multipart/form-data` with the boundary.
+    pub fn content_type(&self) -> ContentType {
+        ContentType::new("multipart/form-data").with_param("boundary", self.boundary.as_str())
+    }
+
+    /// Returns the body, ended by the last boundary.
+    pub fn into_body(mut self) -> Vec<u8> {
+        self.body
+            .extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+        self.body
+    }
+}
+
+impl Default for Multipart {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The head of a part, returned by [`MultipartDecoder::decode`] before its data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Part {
+    headers: Headers,
+    disposition: Option<ContentDisposition>,
+}
+
+impl Part {
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// Returns the `Content-Disposition` of the part, if it's valid.
+    pub fn disposition(&self) -> Option<&ContentDisposition> {
+        self.disposition.as_ref()
+    }
+
+    /// Name of the form field.
+    pub fn name(&self) -> Option<&str> {
+        self.disposition.as_ref()?.name()
+    }
+
+    /// Name of the uploaded file, `None` for text fields.
+    pub fn filename(&self) -> Option<&str> {
+        self.disposition.as_ref()?.filename()
+    }
+
+    /// Returns the `Content-Type` of the part, text fields usually don't have one.
+    pub fn content_type(&self) -> Option<ContentType> {
+        self.headers.typed_get().ok().flatten()
+    }
+}
+
+/// A piece of a multipart body returned by [`MultipartDecoder::decode`].
+#[derive(Debug, PartialEq)]
+pub enum MultipartEvent<'a> {
+    /// A part starts, its data follows.
+    Part(Part),
+    /// Data of the current part, which may be returned in several pieces.
+    Data(&'a [u8]),
+    /// The last boundary was decoded, anything after it is ignored.
+    End,
+}
+
+/// Incremental decoder of a `multipart/form-data` body, so that large uploads don't have to be
+/// held in memory.
+///
+/// ```
+/// use aethon::{MultipartDecoder, MultipartEvent};
+///
+/// let mut decoder = MultipartDecoder::new("b");
+/// let body = b"--b\r\nContent-Disposition: form-data; name=a\r\n\r\n1\r\n--b--\r\n";
+/// let (consumed, event) = decoder.decode(body).unwrap();
+/// assert!(matches!(event, Some(MultipartEvent::Part(p)) if p.name() == Some("a")));
+/// let (_, event) = decoder.decode(&body[consumed..]).unwrap();
+/// assert_eq!(Some(MultipartEvent::Data(b"1")), event);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultipartDecoder {
+    /// `\n--` and the boundary, the line ending before it belongs to the delimiter too.
+    delimiter: Box<[u8]>,
+    state: State,
+    options: ParseOptions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    /// Skipping what comes before the first boundary, which may start the body.
+    Preamble {
+        started: bool,
+    },
+    /// Reading the rest of a boundary line, either `--` or the line ending.
+    Boundary,
+    /// Reading the headers of a part.
+    Headers,
+    /// Reading the data of a part until the next boundary.
+    Data,
+    Done,
+}
+
+impl MultipartDecoder {
+    /// Maximal length of the rest of a boundary line, which may hold whitespace.
+    const MAX_BOUNDARY_PADDING: usize = 64;
+
+    pub fn new(boundary: &str) -> Self {
+        Self {
+            delimiter: format!("\n--{}", boundary).into_bytes().into(),
+            state: State::Preamble { started: false },
+            options: ParseOptions::default(),
+        }
+    }
+
+    /// A decoder for the boundary in the `Content-Type` of `headers`.
+    pub fn from_headers(headers: &Headers) -> Result<Self, Error> {
+        let boundary = headers
+            .typed_get::<ContentType>()?
+            .filter(|content_type| content_type.media_type().starts_with("multipart/"))
+            .and_then(|content_type| content_type.param("boundary").map(Self::new));
+
+        boundary.ok_or(Error::InvalidHeader {
+            name: ContentType::NAME,
+            reason: "expected a multipart body with a boundary",
+        })
+    }
+
+    /// Limits the headers of each part as `options` do, the default limits otherwise.
+    ///
+    /// They're always parsed leniently since parts use `\r\n` line endings.
+    pub fn with_options(mut self, options: ParseOptions) -> Self {
+        self.options = ParseOptions::lenient()
+            .with_max_line_len(options.max_line_len)
+            .with_max_headers(options.max_headers);
+        self
+    }
+
+    /// Whether the last boundary was decoded.
+    pub fn is_done(&self) -> bool {
+        self.state == State::Done
+    }
+
+    /// Decodes the next piece of the body at the start of `buf`.
+    ///
+    /// Returns the number of consumed bytes, which the caller must drop from `buf` before the
+    /// next call, and the decoded piece if there was enough data for one. Bytes that may start
+    /// a boundary are kept until it's known whether they do.
+    pub fn decode<'a>(
+        &mut self, buf: &'a [u8],
+    ) -> Result<(usize, Option<MultipartEvent<'a>>), Error> {
+        let mut consumed = 0;
+        let invalid = |reason| Err(Error::InvalidBody(reason));
+
+        loop {
+            let rest = &buf[consumed..];
+            match self.state {
+                State::Preamble { started } => {
+                    // The first boundary has no line ending before it when it starts the body
+                    let dash_boundary = &self.delimiter[1..];
+                    if !started && rest.len() < dash_boundary.len() {
+                        if !dash_boundary.starts_with(rest) {
+                            self.state = State::Preamble { started: true };
+                            continue;
+                        }
+                        return Ok((consumed, None));
+                    }
+                    if !started && rest.starts_with(dash_boundary) {
+                        consumed += dash_boundary.len();
+                        self.state = State::Boundary;
+                        continue;
+                    }
+
+                    self.state = State::Preamble { started: true };
+                    match self.find_delimiter(rest) {
+                        Some(i) => {
+                            consumed += i + self.delimiter.len();
+                            self.state = State::Boundary;
+                        }
+                        None => {
+                            let skipped = rest.len().saturating_sub(self.delimiter.len());
+                            return Ok((consumed + skipped, None));
+                        }
+                    }
+                }
+                State::Boundary => {
+                    if rest.starts_with(b"--") {
+                        self.state = State::Done;
+                        return Ok((consumed + 2, Some(MultipartEvent::End)));
+                    }
+                    let Some(i) = rest.iter().position(|&b| b == b'\n') else {
+                        if rest.len() > Self::MAX_BOUNDARY_PADDING {
+                            return invalid("the boundary line is too long");
+                        }
+                        return Ok((consumed, None));
+                    };
+                    if !rest[..i].iter().all(|&b| matches!(b, b' ' | b'\t' | b'\r')) {
+                        return invalid("the boundary isn't followed by a line ending");
+                    }
+                    consumed += i + 1;
+                    self.state = State::Headers;
+                }
+                State::Headers => {
+                    let (block, len) = match rest {
+                        [b'\n', ..] => (&rest[..0], 1),
+                        [b'\r', b'\n', ..] => (&rest[..0], 2),
+                        [b'\r'] | [] => return Ok((consumed, None)),
+                        [_, ..] => match parser::find_head_end(rest, &self.options) {
+                            Some((i, end)) => (&rest[..i], i + end),
+                            None => {
+                                HeadLimits::default().check(rest, &self.options)?;
+                                return Ok((consumed, None));
+                            }
+                        },
+                    };
+
+                    let Ok(block) = std::str::from_utf8(block) else {
+                        return invalid("the headers of a part aren't UTF-8");
+                    };
+                    let headers: Headers = HeadersRef::parse(block, &self.options)?.into();
+                    let disposition = headers.typed_get().ok().flatten();
+                    self.state = State::Data;
+                    let part = Part {
+                        headers,
+                        disposition,
+                    };
+                    return Ok((consumed + len, Some(MultipartEvent::Part(part))));
+                }
+                State::Data => {
+                    let Some(i) = self.find_delimiter(rest) else {
+                        // The end may be the start of a delimiter and its `\r`
+                        let n = rest.len().saturating_sub(self.delimiter.len());
+                        return match n {
+                            0 => Ok((consumed, None)),
+                            n => Ok((consumed + n, Some(MultipartEvent::Data(&rest[..n])))),
+                        };
+                    };
+
+                    let end = match i.checked_sub(1) {
+                        Some(cr) if rest[cr] == b'\r' => cr,
+                        _ => i,
+                    };
+                    if end > 0 {
+                        return Ok((consumed + end, Some(MultipartEvent::Data(&rest[..end]))));
+                    }
+                    consumed += i + self.delimiter.len();
+                    self.state = State::Boundary;
+                }
+                State::Done => return Ok((buf.len(), None)),
+            }
+        }
+    }
+
+    fn find_delimiter(&self, buf: &[u8]) -> Option<usize> {
+        buf.windows(self.delimiter.len())
+            .position(|window| *window == *self.delimiter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &[u8] = b"preamble\r\n--XyZ\r\n\
+        Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+        Hello\r\n--XyZ  \r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"a b.txt\"\r\n\
+        Content-Type: text/plain\r\n\r\n\
+        line 1\r\n--XY\n--Xy\r\n--XyZ--\r\nepilogue";
+
+    /// Decodes `body` giving the decoder `step` more bytes at a time.
+    fn decode(body: &[u8], step: usize) -> Result<Vec<(Part, Vec<u8>)>, Error> {
+        let mut decoder = MultipartDecoder::new("XyZ");
+        let mut parts: Vec<(Part, Vec<u8>)> = Vec::new();
+        let (mut len, mut available) = (0, 0);
+
+        while !decoder.is_done() {
+            match decoder.decode(&body[len..available])? {
+                (n, Some(MultipartEvent::Part(part))) => {
+                    parts.push((part, Vec::new()));
+                    len += n;
+                }
+                (n, Some(MultipartEvent::Data(data))) => {
+                    parts.last_mut().unwrap().1.extend_from_slice(data);
+                    len += n;
+                }
+                (n, Some(MultipartEvent::End)) => len += n,
+                (n, None) if available == body.len() => {
+                    assert_eq!(0, n);
+                    return Err(Error::InvalidBody("incomplete"));
+                }
+                (n, None) => {
+                    len += n;
+                    available = body.len().min(available + step);
+                }
+            }
+        }
+        Ok(parts)
+    }
+
+    #[test]
+    fn test_decode() {
+        // Tests
+        for step in [1, 2, 7, BODY.len()] {
+            let parts = decode(BODY, step).unwrap();
+            assert_eq!(2, parts.len());
+            assert_eq!(Some("title"), parts[0].0.name());
+            assert_eq!(None, parts[0].0.filename());
+            assert_eq!(None, parts[0].0.content_type());
+            assert_eq!(b"Hello", parts[0].1.as_slice());
+            assert_eq!(Some("a b.txt"), parts[1].0.filename());
+            assert!(parts[1].0.content_type().unwrap().is("text/plain"));
+            assert_eq!(b"line 1\r\n--XY\n--Xy".as_slice(), parts[1].1);
+        }
+        assert!(decode(b"--XyZ\r\nA: 1\r\n\r\ndata", 4).is_err());
+        assert!(decode(b"--XyZ\r\nA\r\n\r\ndata\r\n--XyZ--", 4).is_err());
+        assert!(decode(b"--XyZ!\r\n\r\n\r\n--XyZ--", 4).is_err());
+        assert!(decode(b"--XyZ--", 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_encode() {
+        let multipart = Multipart::with_boundary("XyZ").text("title", "Hello").file(
+            "file",
+            "a b.txt",
+            ContentType::text(),
+            b"Bye",
+        );
+
+        // Tests
+        assert_eq!(
+            "multipart/form-data; boundary=XyZ",
+            multipart.content_type().encode()
+        );
+        assert_eq!(
+            b"--XyZ\r\nContent-Disposition: form-data; name=title\r\n\r\nHello\r\n\
+            --XyZ\r\nContent-Disposition: form-data; name=file; filename=\"a b.txt\"\r\n\
+            Content-Type: text/plain; charset=utf-8\r\n\r\nBye\r\n--XyZ--\r\n"
+                .as_slice(),
+            multipart.clone().into_body()
+        );
+        let parts = decode(&multipart.into_body(), 3).unwrap();
+        assert_eq!(b"Bye", parts[1].1.as_slice());
+        let quoted = Multipart::with_boundary("XyZ").file(
+            "file",
+            "a;b\"c.txt",
+            ContentType::octet_stream(),
+            b"",
+        );
+        let parts = decode(&quoted.into_body(), 5).unwrap();
+        assert_eq!(Some("a;b\"c.txt"), parts[0].0.filename());
+        assert_ne!(Multipart::new().boundary(), Multipart::new().boundary());
+    }
+}
@@ -0,0 +1,317 @@
+//! Credentials and the challenges asking for them.
+
+use super::{invalid, is_token, split_quoted, unescape, TypedHeader};
+use crate::Error;
+
+/// `Authorization: <scheme> <credentials>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Authorization {
+    scheme: Box<str>,
+    credentials: Box<str>,
+}
+
+impl Authorization {
+    pub fn new(scheme: impl Into<Box<str>>, credentials: impl Into<Box<str>>) -> Self {
+        Self {
+            scheme: scheme.into(),
+            credentials: credentials.into(),
+        }
+    }
+
+    /// `Basic` credentials, `user:password` in base64.
+    pub fn basic(user: &str, password: &str) -> Self {
+        Self::new(
+            "Basic",
+            base64_encode(format!("{}:{}", user, password).as_bytes()),
+        )
+    }
+
+    /// `Bearer` credentials, e.g. an access token.
+    pub fn bearer(token: impl Into<Box<str>>) -> Self {
+        Self::new("Bearer", token)
+    }
+
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+
+    pub fn credentials(&self) -> &str {
+        &self.credentials
+    }
+
+    /// Decodes `Basic` credentials into the user and the password.
+    pub fn user_password(&self) -> Option<(String, String)> {
+        if !self.scheme.eq_ignore_ascii_case("Basic") {
+            return None;
+        }
+        let decoded = String::from_utf8(base64_decode(&self.credentials)?).ok()?;
+        let (user, password) = decoded.split_once(':')?;
+        Some((user.to_string(), password.to_string()))
+    }
+
+    /// Returns the token of `Bearer` credentials.
+    pub fn token(&self) -> Option<&str> {
+        match self.scheme.eq_ignore_ascii_case("Bearer") {
+            true => Some(&self.credentials),
+            false => None,
+        }
+    }
+}
+
+impl TypedHeader for Authorization {
+    const NAME: &'static str = "Authorization";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        let (scheme, credentials) = value
+            .trim()
+            .split_once(' ')
+            .ok_or(invalid::<Self>("expected a scheme and credentials"))?;
+        let credentials = credentials.trim();
+
+        if !is_token(scheme) || credentials.is_empty() {
+            return Err(invalid::<Self>("expected a scheme and credentials"));
+        }
+        Ok(Authorization::new(scheme, credentials))
+    }
+
+    fn encode(&self) -> String {
+        format!("{} {}", self.scheme, self.credentials)
+    }
+}
+
+/// A scheme the server accepts and its parameters, e.g. `Basic realm="apollo"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Challenge {
+    pub scheme: Box<str>,
+    /// Parameters with lowercase names and unquoted values.
+    pub params: Vec<(Box<str>, Box<str>)>,
+}
+
+impl Challenge {
+    pub fn new(scheme: impl Into<Box<str>>) -> Self {
+        Self {
+            scheme: scheme.into(),
+            params: Vec::new(),
+        }
+    }
+
+    /// A `Basic` challenge for the protection space `realm`.
+    pub fn basic(realm: impl Into<Box<str>>) -> Self {
+        Self::new("Basic").with_param("realm", realm)
+    }
+
+    /// A `Bearer` challenge for the protection space `realm`.
+    pub fn bearer(realm: impl Into<Box<str>>) -> Self {
+        Self::new("Bearer").with_param("realm", realm)
+    }
+
+    /// Adds a parameter, e.g. `error="invalid_token"`.
+    pub fn with_param(mut self, key: impl Into<Box<str>>, value: impl Into<Box<str>>) -> Self {
+        self.params.push((key.into(), value.into()));
+        self
+    }
+
+    /// Returns the value of the parameter `key`, ignoring case.
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_ref())
+    }
+
+    pub fn realm(&self) -> Option<&str> {
+        self.param("realm")
+    }
+
+    /// Whether `credentials` answer this challenge, schemes are compared ignoring case.
+    pub fn accepts(&self, credentials: &Authorization) -> bool {
+        self.scheme.eq_ignore_ascii_case(credentials.scheme())
+    }
+}
+
+/// `WWW-Authenticate: Basic realm="apollo", Bearer realm="api"`, sent with a `401 Unauthorized`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WwwAuthenticate(pub Vec<Challenge>);
+
+impl TypedHeader for WwwAuthenticate {
+    const NAME: &'static str = "WWW-Authenticate";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        let mut challenges: Vec<Challenge> = Vec::new();
+        for item in split_quoted(value, ',').into_iter().map(str::trim) {
+            if item.is_empty() {
+                continue;
+            }
+            // A challenge starts with its scheme, the items after it are its parameters
+            let param = match (item.find(' '), item.find('=')) {
+                (Some(space), eq) if eq.is_none_or(|eq| space < eq) => {
+                    challenges.push(Challenge::new(&item[..space]));
+                    item[space + 1..].trim()
+                }
+                (None, None) => {
+                    challenges.push(Challenge::new(item));
+                    ""
+                }
+                _ => item,
+            };
+
+            let challenge = challenges
+                .last_mut()
+                .ok_or(invalid::<Self>("expected a scheme"))?;
+            if !is_token(&challenge.scheme) {
+                return Err(invalid::<Self>("invalid scheme"));
+            }
+            if param.is_empty() {
+                continue;
+            }
+            let (key, value) = param
+                .split_once('=')
+                .ok_or(invalid::<Self>("parameter without a value"))?;
+            let (key, value) = (key.trim(), value.trim());
+            let value = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+                Some(quoted) => unescape(quoted),
+                None if is_token(value) => value.to_string(),
+                None => return Err(invalid::<Self>("invalid parameter value")),
+            };
+            if !is_token(key) {
+                return Err(invalid::<Self>("invalid parameter name"));
+            }
+            challenge
+                .params
+                .push((key.to_ascii_lowercase().into(), value.into()));
+        }
+
+        match challenges.is_empty() {
+            true => Err(invalid::<Self>("expected at least one challenge")),
+            false => Ok(WwwAuthenticate(challenges)),
+        }
+    }
+
+    fn encode(&self) -> String {
+        let challenges: Vec<String> = self
+            .0
+            .iter()
+            .map(|challenge| {
+                let params: Vec<String> = challenge
+                    .params
+                    .iter()
+                    .map(|(k, v)| {
+                        format!("{}=\"{}\"", k, v.replace('\\', "\\\\").replace('"', "\\\""))
+                    })
+                    .collect();
+                match params.is_empty() {
+                    true => challenge.scheme.to_string(),
+                    false => format!("{} {}", challenge.scheme, params.join(", ")),
+                }
+            })
+            .collect();
+        challenges.join(", ")
+    }
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => s.push(BASE64[(n >> (18 - 6 * i)) as usize & 0x3f] as char),
+                false => s.push('='),
+            }
+        }
+    }
+    s
+}
+
+/// Decodes padded base64, `None` if it's malformed.
+fn base64_decode(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(s.len() / 4 * 3);
+    for (i, chunk) in s.as_bytes().chunks(4).enumerate() {
+        let last = i == s.len() / 4 - 1;
+        let padding = chunk.iter().rev().take_while(|&&b| b == b'=').count();
+        if padding > 2 || (padding > 0 && !last) {
+            return None;
+        }
+
+        let mut n = 0u32;
+        for (j, &b) in chunk[..4 - padding].iter().enumerate() {
+            let value = BASE64.iter().position(|&c| c == b)? as u32;
+            n |= value << (18 - 6 * j);
+        }
+        bytes.extend(n.to_be_bytes()[1..4 - padding].iter());
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authorization() {
+        let auth = Authorization::decode("Bearer abc.def").unwrap();
+        let basic = Authorization::basic("Aladdin", "open sesame");
+
+        // Tests
+        assert_eq!("Bearer", auth.scheme());
+        assert_eq!("abc.def", auth.credentials());
+        assert_eq!(Some("abc.def"), auth.token());
+        assert_eq!(None, auth.user_password());
+        assert!(Authorization::decode("Bearer").is_err());
+        assert_eq!("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==", basic.encode());
+        assert_eq!(
+            Some(("Aladdin".to_string(), "open sesame".to_string())),
+            basic.user_password()
+        );
+        assert_eq!(
+            None,
+            Authorization::new("Basic", "QWxh=ZGRp").user_password()
+        );
+    }
+
+    #[test]
+    fn test_base64() {
+        // Tests
+        for (decoded, encoded) in [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v")] {
+            assert_eq!(encoded, base64_encode(decoded.as_bytes()));
+            assert_eq!(Some(decoded.as_bytes().to_vec()), base64_decode(encoded));
+        }
+        assert_eq!(None, base64_decode("Zg="));
+        assert_eq!(None, base64_decode("Z==="));
+        assert_eq!(None, base64_decode("Zm9*"));
+    }
+
+    #[test]
+    fn test_www_authenticate() {
+        let header = WwwAuthenticate::decode(
+            "Basic realm=\"apollo, \\\"main\\\"\", charset=UTF-8, Bearer, Custom a=b",
+        )
+        .unwrap();
+
+        // Tests
+        assert_eq!(
+            vec![
+                Challenge::basic("apollo, \"main\"").with_param("charset", "UTF-8"),
+                Challenge::new("Bearer"),
+                Challenge::new("Custom").with_param("a", "b"),
+            ],
+            header.0
+        );
+        assert_eq!(
+            "Basic realm=\"apollo, \\\"main\\\"\", charset=\"UTF-8\", Bearer, Custom a=\"b\"",
+            header.encode()
+        );
+        assert!(header.0[1].accepts(&Authorization::bearer("t")));
+        assert!(WwwAuthenticate::decode("realm=\"x\"").is_err());
+        assert!(WwwAuthenticate::decode("Basic realm=a b").is_err());
+        assert!(WwwAuthenticate::decode("").is_err());
+    }
+}
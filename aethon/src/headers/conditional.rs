@@ -0,0 +1,197 @@
+//! Validators and the conditional request headers checking them.
+
+use super::{date, invalid, ETag, TypedHeader};
+use crate::Error;
+use std::time::SystemTime;
+
+macro_rules! date_header {
+    ($(#[$doc:meta])* $name:ident, $header:literal) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub struct $name(pub SystemTime);
+
+        impl TypedHeader for $name {
+            const NAME: &'static str = $header;
+
+            fn decode(value: &str) -> Result<Self, Error> {
+                date::parse(value)
+                    .map($name)
+                    .ok_or(invalid::<Self>("expected an IMF-fixdate"))
+            }
+
+            fn encode(&self) -> String {
+                date::format(self.0)
+            }
+        }
+    };
+}
+
+date_header!(
+    /// `Last-Modified: Wed, 21 Oct 2015 07:28:00 GMT`, when the content last changed.
+    LastModified,
+    "Last-Modified"
+);
+date_header!(
+    /// `Expires: Wed, 21 Oct 2015 07:28:00 GMT`, when the response becomes stale.
+    Expires,
+    "Expires"
+);
+date_header!(
+    /// `If-Modified-Since: Wed, 21 Oct 2015 07:28:00 GMT`, the client has this version.
+    IfModifiedSince,
+    "If-Modified-Since"
+);
+date_header!(
+    /// `If-Unmodified-Since: Wed, 21 Oct 2015 07:28:00 GMT`, the request only applies to this
+    /// version.
+    IfUnmodifiedSince,
+    "If-Unmodified-Since"
+);
+
+/// `*` or a list of tags.
+fn decode_tags<H: TypedHeader>(value: &str) -> Result<Option<Vec<ETag>>, Error> {
+    if value.trim() == "*" {
+        return Ok(None);
+    }
+
+    let mut tags = Vec::new();
+    let mut rest = value.trim();
+    while !rest.is_empty() {
+        // A tag can contain commas, it ends at its closing quote
+        let start = if rest.starts_with("W/") { 3 } else { 1 };
+        let end = rest
+            .get(start..)
+            .and_then(|r| r.find('"'))
+            .ok_or(invalid::<H>("expected a list of quoted tags"))?;
+        tags.push(rest[..start + end + 1].parse()?);
+
+        rest = rest[start + end + 1..].trim_start();
+        rest = match rest.strip_prefix(',') {
+            Some(rest) => rest.trim_start(),
+            None if rest.is_empty() => rest,
+            None => return Err(invalid::<H>("expected a list of quoted tags")),
+        };
+    }
+
+    match tags.is_empty() {
+        true => Err(invalid::<H>("expected a list of quoted tags")),
+        false => Ok(Some(tags)),
+    }
+}
+
+fn encode_tags(tags: &[ETag]) -> String {
+    let tags: Vec<String> = tags.iter().map(ETag::to_string).collect();
+    tags.join(", ")
+}
+
+/// `If-None-Match: "33a64df5", "b2c5"`, the client has one of these versions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IfNoneMatch {
+    /// `*`, the client has some version.
+    Any,
+    Tags(Vec<ETag>),
+}
+
+impl IfNoneMatch {
+    /// Whether the current version is one the client has, tags are compared weakly.
+    pub fn matches(&self, current: Option<&ETag>) -> bool {
+        match (self, current) {
+            (IfNoneMatch::Any, current) => current.is_some(),
+            (IfNoneMatch::Tags(tags), Some(current)) => tags.iter().any(|t| t.weak_eq(current)),
+            (IfNoneMatch::Tags(_), None) => false,
+        }
+    }
+}
+
+impl TypedHeader for IfNoneMatch {
+    const NAME: &'static str = "If-None-Match";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        Ok(decode_tags::<Self>(value)?.map_or(IfNoneMatch::Any, IfNoneMatch::Tags))
+    }
+
+    fn encode(&self) -> String {
+        match self {
+            IfNoneMatch::Any => "*".to_string(),
+            IfNoneMatch::Tags(tags) => encode_tags(tags),
+        }
+    }
+}
+
+/// `If-Match: "33a64df5"`, the request only applies to one of these versions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IfMatch {
+    /// `*`, the request applies to any existing version.
+    Any,
+    Tags(Vec<ETag>),
+}
+
+impl IfMatch {
+    /// Whether the current version is one the request applies to, tags are compared strongly.
+    pub fn matches(&self, current: Option<&ETag>) -> bool {
+        match (self, current) {
+            (IfMatch::Any, current) => current.is_some(),
+            (IfMatch::Tags(tags), Some(current)) => tags.iter().any(|t| t.strong_eq(current)),
+            (IfMatch::Tags(_), None) => false,
+        }
+    }
+}
+
+impl TypedHeader for IfMatch {
+    const NAME: &'static str = "If-Match";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        Ok(decode_tags::<Self>(value)?.map_or(IfMatch::Any, IfMatch::Tags))
+    }
+
+    fn encode(&self) -> String {
+        match self {
+            IfMatch::Any => "*".to_string(),
+            IfMatch::Tags(tags) => encode_tags(tags),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_tag_lists() {
+        let inm = IfNoneMatch::decode("W/\"a,b\", \"c\"").unwrap();
+
+        // Tests
+        assert_eq!(
+            IfNoneMatch::Tags(vec![ETag::weak("a,b"), ETag::strong("c")]),
+            inm
+        );
+        assert_eq!("W/\"a,b\", \"c\"", inm.encode());
+        assert!(inm.matches(Some(&ETag::strong("a,b"))));
+        assert!(!inm.matches(None));
+        assert_eq!(Ok(IfMatch::Any), IfMatch::decode(" * "));
+        assert!(IfMatch::Any.matches(Some(&ETag::weak("x"))));
+        assert!(!IfMatch::decode("W/\"c\"")
+            .unwrap()
+            .matches(Some(&ETag::weak("c"))));
+        assert!(IfNoneMatch::decode("").is_err());
+        assert!(IfNoneMatch::decode("\"a\" \"b\"").is_err());
+        assert!(IfNoneMatch::decode("abc").is_err());
+    }
+
+    #[test]
+    fn test_date_headers() {
+        let time = UNIX_EPOCH + Duration::from_secs(1445412480);
+
+        // Tests
+        assert_eq!(
+            Ok(LastModified(time)),
+            LastModified::decode("Wed, 21 Oct 2015 07:28:00 GMT")
+        );
+        assert_eq!(
+            "Wed, 21 Oct 2015 07:28:00 GMT",
+            IfModifiedSince(time).encode()
+        );
+        assert!(Expires::decode("0").is_err());
+    }
+}
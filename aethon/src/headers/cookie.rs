@@ -0,0 +1,239 @@
+//! Cookies, set by the server with `Set-Cookie` and sent back by the client with `Cookie`.
+
+use super::{date, invalid, is_token, TypedHeader};
+use crate::Error;
+use std::{
+    fmt::{self, Display},
+    time::{Duration, SystemTime},
+};
+
+/// A cookie with the attributes of its `Set-Cookie` header.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Cookie {
+    pub name: Box<str>,
+    pub value: Box<str>,
+    /// When the cookie expires, it's a session cookie without `expires` nor `max_age`.
+    pub expires: Option<SystemTime>,
+    /// How long the cookie lives, it wins over `expires`.
+    pub max_age: Option<Duration>,
+    /// Domain the cookie is also sent to the subdomains of, only the setting host without it.
+    pub domain: Option<Box<str>>,
+    /// Path prefix the cookie is sent for, the directory of the setting request without it.
+    pub path: Option<Box<str>>,
+    /// The cookie is only sent over `aethons://`.
+    pub secure: bool,
+    /// The cookie isn't exposed to scripts.
+    pub http_only: bool,
+}
+
+impl Cookie {
+    /// Creates a session cookie.
+    pub fn new(name: impl Into<Box<str>>, value: impl Into<Box<str>>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Parses a `Set-Cookie` value, unknown and malformed attributes are ignored.
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        let mut attributes = s.split(';');
+        let (name, value) = attributes
+            .next()
+            .and_then(|pair| pair.split_once('='))
+            .ok_or(invalid::<SetCookie>("expected name=value"))?;
+        let (name, value) = (name.trim(), value.trim());
+        if !is_token(name) {
+            return Err(invalid::<SetCookie>("invalid cookie name"));
+        }
+        if !is_cookie_value(value) {
+            return Err(invalid::<SetCookie>("invalid cookie value"));
+        }
+
+        let mut cookie = Cookie::new(name, value);
+        for attribute in attributes {
+            let (key, arg) = match attribute.split_once('=') {
+                Some((key, arg)) => (key.trim(), arg.trim()),
+                None => (attribute.trim(), ""),
+            };
+            match key.to_ascii_lowercase().as_str() {
+                "expires" => cookie.expires = date::parse(arg).or(cookie.expires),
+                "max-age" => {
+                    if let Ok(secs) = arg.parse::<i64>() {
+                        // Zero or negative expire the cookie right away
+                        cookie.max_age = Some(Duration::from_secs(secs.max(0) as u64));
+                    }
+                }
+                "domain" if !arg.is_empty() => {
+                    let domain = arg.trim_start_matches('.').to_ascii_lowercase();
+                    cookie.domain = Some(domain.into());
+                }
+                "path" if arg.starts_with('/') => cookie.path = Some(arg.into()),
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                _ => {}
+            }
+        }
+
+        Ok(cookie)
+    }
+
+    /// When the cookie expires if it's received at `now`, `None` for a session cookie.
+    pub fn expiry(&self, now: SystemTime) -> Option<SystemTime> {
+        match self.max_age {
+            Some(max_age) => now.checked_add(max_age),
+            None => self.expires,
+        }
+    }
+}
+
+/// Serializes the cookie as a `Set-Cookie` value.
+impl Display for Cookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+        if let Some(expires) = self.expires {
+            write!(f, "; Expires={}", date::format(expires))?;
+        }
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={}", max_age.as_secs())?;
+        }
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={}", domain)?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, "; Path={}", path)?;
+        }
+        if self.secure {
+            write!(f, "; Secure")?;
+        }
+        if self.http_only {
+            write!(f, "; HttpOnly")?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `s` can be a cookie value, it may be quoted.
+fn is_cookie_value(s: &str) -> bool {
+    let s = s
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(s);
+    s.bytes()
+        .all(|b| b.is_ascii_graphic() && !b"\",;\\".contains(&b))
+}
+
+/// `Set-Cookie: id=a3fWa; Max-Age=2592000; Path=/`
+///
+/// A response sets each cookie with its own header and `Expires` contains a comma, read them
+/// with [`Headers::get_all`](crate::Headers::get_all) and [`Cookie::parse`] instead of
+/// `typed_get`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetCookie(pub Cookie);
+
+impl TypedHeader for SetCookie {
+    const NAME: &'static str = "Set-Cookie";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        Cookie::parse(value).map(SetCookie)
+    }
+
+    fn encode(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+/// `Cookie: id=a3fWa; lang=en`, the cookies a client sends back.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Cookies(pub Vec<(Box<str>, Box<str>)>);
+
+impl Cookies {
+    /// Returns the value of the first cookie called `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(n, _)| n.as_ref() == name)
+            .map(|(_, v)| v.as_ref())
+    }
+}
+
+impl TypedHeader for Cookies {
+    const NAME: &'static str = "Cookie";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        value
+            .split(';')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (name, value) = pair
+                    .split_once('=')
+                    .ok_or(invalid::<Self>("expected name=value"))?;
+                if !is_token(name) || !is_cookie_value(value) {
+                    return Err(invalid::<Self>("invalid cookie"));
+                }
+                Ok((name.into(), value.into()))
+            })
+            .collect::<Result<_, _>>()
+            .map(Cookies)
+    }
+
+    fn encode(&self) -> String {
+        let pairs: Vec<String> = self
+            .0
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        pairs.join("; ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn test_set_cookie() {
+        let cookie = Cookie::parse(
+            "id=a3fWa; Expires=Wed, 21 Oct 2015 07:28:00 GMT; Domain=.Example; Path=/docs; \
+             Secure; HttpOnly; SameSite=Lax; Max-Age=oops",
+        )
+        .unwrap();
+
+        // Tests
+        assert_eq!(("id", "a3fWa"), (&*cookie.name, &*cookie.value));
+        assert_eq!(
+            Some(UNIX_EPOCH + Duration::from_secs(1445412480)),
+            cookie.expires
+        );
+        assert_eq!(None, cookie.max_age);
+        assert_eq!(Some("example"), cookie.domain.as_deref());
+        assert_eq!(Some("/docs"), cookie.path.as_deref());
+        assert!(cookie.secure && cookie.http_only);
+        assert_eq!(
+            "id=a3fWa; Expires=Wed, 21 Oct 2015 07:28:00 GMT; Domain=example; Path=/docs; \
+             Secure; HttpOnly",
+            cookie.to_string()
+        );
+        assert_eq!(
+            Some(Duration::ZERO),
+            Cookie::parse("a=; Max-Age=-1").unwrap().max_age
+        );
+        assert!(Cookie::parse("no value").is_err());
+        assert!(Cookie::parse("a b=c").is_err());
+        assert!(Cookie::parse("a=b c").is_err());
+    }
+
+    #[test]
+    fn test_cookies() {
+        let cookies = Cookies::decode("id=a3fWa; lang=\"en\"").unwrap();
+
+        // Tests
+        assert_eq!(Some("a3fWa"), cookies.get("id"));
+        assert_eq!(Some("\"en\""), cookies.get("lang"));
+        assert_eq!("id=a3fWa; lang=\"en\"", cookies.encode());
+        assert!(Cookies::decode("id").is_err());
+    }
+}
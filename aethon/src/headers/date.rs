@@ -0,0 +1,119 @@
+//! Dates in headers use the IMF-fixdate format, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Formats `time` with a precision of one second, times before 1970 are clamped to the epoch.
+pub(crate) fn format(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let days = (secs / 86400) as i64;
+    let (year, month, day) = civil_from_days(days);
+    // 1970-01-01 was a Thursday
+    let weekday = ((days + 4) % 7) as usize;
+
+    format!(
+        "{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT",
+        DAYS[weekday],
+        day,
+        MONTHS[month as usize - 1],
+        year,
+        secs % 86400 / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+/// Parses an IMF-fixdate, returns `None` if it's malformed.
+pub(crate) fn parse(s: &str) -> Option<SystemTime> {
+    let (weekday, rest) = s.trim().split_once(", ")?;
+    let mut parts = rest.split(' ');
+    let day: u32 = parts.next().filter(|d| d.len() == 2)?.parse().ok()?;
+    let month = parts.next()?;
+    let month = MONTHS.iter().position(|m| *m == month)? as u32 + 1;
+    let year: i64 = parts.next().filter(|y| y.len() == 4)?.parse().ok()?;
+    let time = parts.next()?;
+    if parts.next()? != "GMT" || parts.next().is_some() {
+        return None;
+    }
+
+    let mut time = time.split(':').map(|t| match t.len() {
+        2 => t.parse::<u64>().ok(),
+        _ => None,
+    });
+    let (hours, minutes, seconds) = (time.next()??, time.next()??, time.next()??);
+    if time.next().is_some() || hours > 23 || minutes > 59 || seconds > 60 {
+        return None;
+    }
+
+    let days = days_from_civil(year, month, day);
+    if !(1..=31).contains(&day)
+        || civil_from_days(days) != (year, month, day)
+        || DAYS[((days + 4).rem_euclid(7)) as usize] != weekday
+        || days < 0
+    {
+        return None;
+    }
+
+    let secs = days as u64 * 86400 + hours * 3600 + minutes * 60 + seconds;
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let yoe = year - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Proleptic Gregorian date of a day since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_date() {
+        let time = UNIX_EPOCH + Duration::from_secs(784111777);
+
+        // Tests
+        assert_eq!("Sun, 06 Nov 1994 08:49:37 GMT", format(time));
+        assert_eq!("Thu, 01 Jan 1970 00:00:00 GMT", format(UNIX_EPOCH));
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let time = UNIX_EPOCH + Duration::from_secs(784111777);
+        let leap = UNIX_EPOCH + Duration::from_secs(951782400);
+
+        // Tests
+        assert_eq!(Some(time), parse("Sun, 06 Nov 1994 08:49:37 GMT"));
+        assert_eq!(Some(leap), parse("Tue, 29 Feb 2000 00:00:00 GMT"));
+        assert_eq!(None, parse("Mon, 06 Nov 1994 08:49:37 GMT"));
+        assert_eq!(None, parse("Wed, 29 Feb 2001 00:00:00 GMT"));
+        assert_eq!(None, parse("Sun, 06 Nov 1994 08:49:37"));
+        assert_eq!(None, parse("Sunday, 06-Nov-94 08:49:37 GMT"));
+    }
+}
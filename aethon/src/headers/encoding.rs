@@ -0,0 +1,216 @@
+//! Content codings, bodies compressed for the transfer.
+
+use super::{invalid, is_token, parse_quality, with_quality, TypedHeader};
+use crate::Error;
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+/// A content coding of `Content-Encoding` and `Accept-Encoding`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Coding {
+    /// No compression.
+    Identity,
+    Gzip,
+    Deflate,
+    Zstd,
+}
+
+impl Coding {
+    /// Codings enabled by cargo features, in order of preference.
+    pub const SUPPORTED: &'static [Coding] = &[
+        #[cfg(feature = "zstd")]
+        Coding::Zstd,
+        #[cfg(feature = "gzip")]
+        Coding::Gzip,
+        #[cfg(feature = "deflate")]
+        Coding::Deflate,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Coding::Identity => "identity",
+            Coding::Gzip => "gzip",
+            Coding::Deflate => "deflate",
+            Coding::Zstd => "zstd",
+        }
+    }
+
+    /// Whether bodies can be encoded and decoded with this coding.
+    pub fn is_supported(&self) -> bool {
+        *self == Coding::Identity || Self::SUPPORTED.contains(self)
+    }
+}
+
+impl FromStr for Coding {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "identity" => Ok(Coding::Identity),
+            "gzip" | "x-gzip" => Ok(Coding::Gzip),
+            "deflate" => Ok(Coding::Deflate),
+            "zstd" => Ok(Coding::Zstd),
+            _ => Err(invalid::<ContentEncoding>("unknown coding")),
+        }
+    }
+}
+
+impl Display for Coding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// `Content-Encoding: gzip`, the codings applied to the body in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentEncoding(pub Vec<Coding>);
+
+impl TypedHeader for ContentEncoding {
+    const NAME: &'static str = "Content-Encoding";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        let codings = value
+            .split(',')
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match codings.is_empty() {
+            true => Err(invalid::<Self>("expected at least one coding")),
+            false => Ok(ContentEncoding(codings)),
+        }
+    }
+
+    fn encode(&self) -> String {
+        let names: Vec<&str> = self.0.iter().map(Coding::name).collect();
+        names.join(", ")
+    }
+}
+
+/// `Accept-Encoding: zstd, gzip;q=0.8, *;q=0.1`, the codings the client can decode.
+///
+/// Codings are kept by name with their quality in thousandths, so unknown ones like `br` are
+/// kept too.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AcceptEncoding(pub Vec<(Box<str>, u16)>);
+
+impl AcceptEncoding {
+    /// Accepts the codings this build supports.
+    pub fn supported() -> Self {
+        Self(
+            Coding::SUPPORTED
+                .iter()
+                .map(|c| (c.name().into(), 1000))
+                .collect(),
+        )
+    }
+
+    /// Returns the quality given to `coding`, `identity` is acceptable unless it's excluded.
+    pub fn quality(&self, coding: Coding) -> u16 {
+        let find = |name: &str| {
+            self.0
+                .iter()
+                .find(|(c, _)| c.eq_ignore_ascii_case(name))
+                .map(|(_, q)| *q)
+        };
+        match (find(coding.name()), find("*"), coding) {
+            (Some(q), _, _) | (None, Some(q), _) => q,
+            (None, None, Coding::Identity) => 1000,
+            (None, None, _) => 0,
+        }
+    }
+
+    /// Picks the best of `available`, earlier ones win ties.
+    ///
+    /// `identity` is the fallback when none of them is acceptable, `None` is returned if it's
+    /// excluded too.
+    pub fn negotiate(&self, available: &[Coding]) -> Option<Coding> {
+        available
+            .iter()
+            .map(|c| (self.quality(*c), *c))
+            .filter(|(q, _)| *q > 0)
+            .fold(None, |best: Option<(u16, Coding)>, (q, c)| match best {
+                Some((bq, _)) if bq >= q => best,
+                _ => Some((q, c)),
+            })
+            .map(|(_, c)| c)
+            .or_else(|| (self.quality(Coding::Identity) > 0).then_some(Coding::Identity))
+    }
+}
+
+impl TypedHeader for AcceptEncoding {
+    const NAME: &'static str = "Accept-Encoding";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .map(|item| {
+                let (coding, params) = item.split_once(';').unwrap_or((item, ""));
+                let coding = coding.trim();
+                if !is_token(coding) {
+                    return Err(invalid::<Self>("invalid coding"));
+                }
+
+                let quality = match params.trim() {
+                    "" => 1000,
+                    params => params
+                        .strip_prefix("q=")
+                        .and_then(|q| parse_quality(q.trim()))
+                        .ok_or(invalid::<Self>("invalid quality value"))?,
+                };
+                Ok((coding.to_ascii_lowercase().into(), quality))
+            })
+            .collect::<Result<_, _>>()
+            .map(AcceptEncoding)
+    }
+
+    fn encode(&self) -> String {
+        self.0
+            .iter()
+            .map(|(coding, q)| with_quality(coding, *q))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_encoding() {
+        let header = ContentEncoding::decode("deflate, X-Gzip").unwrap();
+
+        // Tests
+        assert_eq!(ContentEncoding(vec![Coding::Deflate, Coding::Gzip]), header);
+        assert_eq!("deflate, gzip", header.encode());
+        assert!(ContentEncoding::decode("br").is_err());
+        assert!(ContentEncoding::decode("").is_err());
+    }
+
+    #[test]
+    fn test_accept_encoding() {
+        let accept = AcceptEncoding::decode("br, gzip;q=0.8, deflate;q=0.9").unwrap();
+        let no_identity = AcceptEncoding::decode("gzip, *;q=0").unwrap();
+        let all = [Coding::Zstd, Coding::Gzip, Coding::Deflate];
+
+        // Tests
+        assert_eq!("br, gzip;q=0.8, deflate;q=0.9", accept.encode());
+        assert_eq!(800, accept.quality(Coding::Gzip));
+        assert_eq!(0, accept.quality(Coding::Zstd));
+        assert_eq!(1000, accept.quality(Coding::Identity));
+        assert_eq!(Some(Coding::Deflate), accept.negotiate(&all));
+        assert_eq!(Some(Coding::Identity), accept.negotiate(&all[..1]));
+        assert_eq!(Some(Coding::Gzip), no_identity.negotiate(&all));
+        assert_eq!(None, no_identity.negotiate(&[Coding::Zstd]));
+        assert_eq!(
+            Some(Coding::Identity),
+            AcceptEncoding(Vec::new()).negotiate(&all)
+        );
+        assert!(AcceptEncoding::decode("gzip;q=2").is_err());
+    }
+}
@@ -0,0 +1,476 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+};
+
+use super::{
+    chunked,
+    error::{ParseError, ParseErrorKind},
+    parser::{offset_in, Framing, ParseOptions},
+    Error,
+};
+
+mod auth;
+mod conditional;
+mod cookie;
+mod date;
+mod encoding;
+mod range;
+mod typed;
+pub use auth::{Authorization, Challenge, WwwAuthenticate};
+pub use conditional::{
+    Expires, IfMatch, IfModifiedSince, IfNoneMatch, IfUnmodifiedSince, LastModified,
+};
+pub use cookie::{Cookie, Cookies, SetCookie};
+pub use encoding::{AcceptEncoding, Coding, ContentEncoding};
+pub use range::{ByteRange, ContentRange, IfRange, Range};
+pub(crate) use typed::{invalid, is_token, parse_quality, split_quoted, unescape, with_quality};
+pub use typed::{
+    Accept, CacheControl, Connection, ContentDisposition, ContentLength, ContentType, Date, ETag,
+    Host, KeepAlive, Location, MediaRange, TypedHeader,
+};
+
+/// `Headers` keeps headers in insertion order.
+///
+/// Names are case-insensitive and a name can have many values, e.g. repeated cookies.
+#[derive(Debug, Clone, Default)]
+pub struct Headers(Vec<(Box<str>, Box<str>)>);
+
+impl Headers {
+    /// Name of the header framing the body.
+    pub(crate) const CONTENT_LENGTH: &'static str = "Content-Length";
+    /// Name of the header announcing a chunked body.
+    pub(crate) const TRANSFER_ENCODING: &'static str = "Transfer-Encoding";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes the header block terminated by an empty line.
+    ///
+    /// `Content-Length` is added for non-empty bodies if it's missing, so the packet can be framed.
+    pub(crate) fn write_block(&self, f: &mut impl fmt::Write, body_len: usize) -> fmt::Result {
+        write!(f, "{}", self)?;
+
+        let framed = body_len > 0
+            && !self.contains(Self::CONTENT_LENGTH)
+            && !self.contains(Self::TRANSFER_ENCODING);
+        if framed {
+            writeln!(f, "{}: {}", Self::CONTENT_LENGTH, body_len)?;
+        }
+        // Headers are divided from the body by \n\n, even if there are none
+        if self.0.is_empty() && !framed {
+            writeln!(f)?;
+        }
+
+        writeln!(f)
+    }
+
+    /// Sets `key` to `value`, replacing all its previous values.
+    ///
+    /// The header keeps the position of its first occurrence, the first old value is returned.
+    pub fn insert(
+        &mut self, key: impl Into<Box<str>>, value: impl Into<Box<str>>,
+    ) -> Option<Box<str>> {
+        let key = key.into();
+
+        match self.position(&key) {
+            Some(first) => {
+                let mut i = 0;
+                self.0.retain(|(k, _)| {
+                    let keep = i <= first || !k.eq_ignore_ascii_case(&key);
+                    i += 1;
+                    keep
+                });
+                Some(std::mem::replace(&mut self.0[first], (key, value.into())).1)
+            }
+            None => {
+                self.0.push((key, value.into()));
+                None
+            }
+        }
+    }
+
+    /// Adds another value to `key`, keeping the previous ones.
+    pub fn append(&mut self, key: impl Into<Box<str>>, value: impl Into<Box<str>>) {
+        self.0.push((key.into(), value.into()));
+    }
+
+    /// Returns the first value of `key`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.position(key).map(|i| self.0[i].1.as_ref())
+    }
+
+    /// Returns all values of `key` in insertion order.
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.0
+            .iter()
+            .filter(move |(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_ref())
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Removes all values of `key`, returning the first one.
+    pub fn remove(&mut self, key: &str) -> Option<Box<str>> {
+        let first = self.position(key)?;
+        let (_, value) = self.0.remove(first);
+        self.0.retain(|(k, _)| !k.eq_ignore_ascii_case(key));
+        Some(value)
+    }
+
+    /// Parses the header `H`, returns `Ok(None)` if it's missing.
+    pub fn typed_get<H: TypedHeader>(&self) -> Result<Option<H>, Error> {
+        decode_values(self.get_all(H::NAME))
+    }
+
+    /// Sets the header `H`, replacing its previous values.
+    pub fn typed_insert<H: TypedHeader>(&mut self, header: H) {
+        self.insert(H::NAME, header.encode());
+    }
+
+    /// Iterates over the headers in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(k, v)| (k.as_ref(), v.as_ref()))
+    }
+
+    /// Returns the number of values, repeated headers are counted once per value.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Checks that the headers can be written and parsed back unchanged.
+    pub(crate) fn validate(&self, body_len: usize) -> Result<(), Error> {
+        for (k, v) in self.iter() {
+            if !is_token(k) {
+                return Err(Error::BuildError("Header names must be non-empty tokens"));
+            }
+            if v.trim().is_empty() {
+                return Err(Error::BuildError("Header values can't be empty"));
+            }
+            if v.contains(['\n', '\r']) {
+                return Err(Error::BuildError("Header values can't contain newlines"));
+            }
+        }
+
+        if self.contains(Self::TRANSFER_ENCODING) {
+            return match (self.is_chunked(), self.contains(Self::CONTENT_LENGTH)) {
+                (true, false) => Ok(()),
+                (true, true) => Err(Error::BuildError(
+                    "Content-Length can't be used with chunked encoding",
+                )),
+                (false, _) => Err(Error::BuildError("Unsupported Transfer-Encoding")),
+            };
+        }
+
+        match self.get_all(Self::CONTENT_LENGTH).collect::<Vec<_>>()[..] {
+            [] => Ok(()),
+            [len] if ContentLength::decode(len) == Ok(ContentLength(body_len as u64)) => Ok(()),
+            _ => Err(Error::BuildError("Content-Length doesn't match the body")),
+        }
+    }
+
+    /// Whether the body uses chunked transfer encoding.
+    pub(crate) fn is_chunked(&self) -> bool {
+        self.get(Self::TRANSFER_ENCODING)
+            .is_some_and(|te| te.trim().eq_ignore_ascii_case(chunked::CHUNKED))
+    }
+
+    fn position(&self, key: &str) -> Option<usize> {
+        self.0.iter().position(|(k, _)| k.eq_ignore_ascii_case(key))
+    }
+}
+
+/// Names are compared ignoring case, the order of the headers matters.
+impl PartialEq for Headers {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self
+                .0
+                .iter()
+                .zip(other.0.iter())
+                .all(|((k1, v1), (k2, v2))| k1.eq_ignore_ascii_case(k2) && v1 == v2)
+    }
+}
+
+impl Eq for Headers {}
+
+impl Display for Headers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (k, v) in self.0.iter() {
+            writeln!(f, "{}: {}", k, v)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<HeadersRef<'_>> for Headers {
+    fn from(headers: HeadersRef<'_>) -> Self {
+        headers.iter().collect()
+    }
+}
+
+impl From<BTreeMap<Box<str>, Box<str>>> for Headers {
+    fn from(map: BTreeMap<Box<str>, Box<str>>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl<K: Into<Box<str>>, V: Into<Box<str>>> FromIterator<(K, V)> for Headers {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        Headers(
+            iter.into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
+    }
+}
+
+impl<K: Into<Box<str>>, V: Into<Box<str>>> Extend<(K, V)> for Headers {
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        self.0
+            .extend(iter.into_iter().map(|(k, v)| (k.into(), v.into())));
+    }
+}
+
+/// Borrowed view of a header block.
+///
+/// The block is validated once when parsed, the headers are then read straight from the input
+/// without allocating.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct HeadersRef<'a>(&'a str);
+
+impl<'a> HeadersRef<'a> {
+    /// Parses a header block, errors are positioned in `block`.
+    pub(crate) fn parse(block: &'a str, options: &ParseOptions) -> Result<Self, ParseError> {
+        let error = |kind, offset| ParseError::at(kind, block.as_bytes(), offset);
+        let mut end = 0;
+        let mut count = 0;
+
+        for line in block.split_inclusive('\n') {
+            let content = line.strip_suffix('\n').unwrap_or(line);
+            if content.len() > options.max_line_len {
+                return Err(error(ParseErrorKind::HeaderTooLarge, end));
+            }
+            let content = options
+                .strip_cr(content)
+                .map_err(|e| e.shift(block.as_bytes(), end))?;
+            if content.is_empty() {
+                break;
+            }
+            count += 1;
+            if count > options.max_headers {
+                return Err(error(ParseErrorKind::TooManyHeaders, end));
+            }
+
+            let (key, value) = content
+                .split_once(':')
+                .ok_or(error(ParseErrorKind::MissingColon, end + content.len()))?;
+            let name = match options.is_strict() {
+                true => key,
+                false => key.trim(),
+            };
+            if !is_token(name) {
+                let kind = match is_token(key.trim()) {
+                    true => ParseErrorKind::Whitespace,
+                    false => ParseErrorKind::InvalidHeaderName,
+                };
+                return Err(error(kind, end));
+            }
+
+            let trimmed = value.trim();
+            if trimmed.is_empty() {
+                return Err(error(ParseErrorKind::EmptyHeaderValue, end + key.len() + 1));
+            }
+            if options.is_strict() && trimmed.len() != value.trim_start().len() {
+                let offset = offset_in(block, trimmed) + trimmed.len();
+                return Err(error(ParseErrorKind::Whitespace, offset));
+            }
+
+            end += line.len();
+        }
+
+        Ok(HeadersRef(&block[..end]))
+    }
+
+    /// Returns the value of the first header called `key`, ignoring case.
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        self.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v)
+    }
+
+    /// Returns all values of `key` in the order they were received.
+    pub fn get_all<'k>(&self, key: &'k str) -> impl Iterator<Item = &'a str> + 'k
+    where
+        'a: 'k,
+    {
+        self.iter()
+            .filter(move |(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v)
+    }
+
+    /// Parses the header `H`, returns `Ok(None)` if it's missing.
+    pub fn typed_get<H: TypedHeader>(&self) -> Result<Option<H>, Error> {
+        decode_values(self.get_all(H::NAME))
+    }
+
+    /// Iterates over the headers in the order they were received.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, &'a str)> {
+        self.0
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(k, v)| (k.trim(), v.trim()))
+    }
+
+    /// Returns the number of headers.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns how the end of the body is found, errors are positioned in the block.
+    ///
+    /// A `Content-Length` over the body limit of `options` is an error. So are framing headers a
+    /// peer could read differently: `Transfer-Encoding` sent twice, or `Content-Length` sent
+    /// twice with different values.
+    pub(crate) fn framing(&self, options: &ParseOptions) -> Result<Framing, ParseError> {
+        let error =
+            |kind, value: &str| ParseError::at(kind, self.0.as_bytes(), offset_in(self.0, value));
+        let mut encodings = self.get_all(Headers::TRANSFER_ENCODING);
+        let te = encodings.next();
+        if let Some(second) = encodings.next() {
+            return Err(error(ParseErrorKind::ConflictingFraming, second));
+        }
+        let mut lengths = self.get_all(Headers::CONTENT_LENGTH);
+        let len = lengths.next();
+        if let Some(other) = lengths.find(|other| Some(*other) != len) {
+            return Err(error(ParseErrorKind::ConflictingFraming, other));
+        }
+
+        match (te, len) {
+            (Some(_), Some(len)) => Err(error(ParseErrorKind::ConflictingFraming, len)),
+            (Some(te), None) if te.eq_ignore_ascii_case(chunked::CHUNKED) => Ok(Framing::Chunked),
+            (Some(te), None) => Err(error(ParseErrorKind::UnsupportedTransferEncoding, te)),
+            // usize::from_str accepts a leading +
+            (None, Some(len)) => match len.bytes().all(|b| b.is_ascii_digit()) {
+                true => match len.parse() {
+                    Ok(n) if n > options.max_body_len => {
+                        Err(error(ParseErrorKind::BodyTooLarge, len))
+                    }
+                    Ok(n) => Ok(Framing::Length(n)),
+                    Err(_) => Err(error(ParseErrorKind::InvalidContentLength, len)),
+                },
+                false => Err(error(ParseErrorKind::InvalidContentLength, len)),
+            },
+            (None, None) => Ok(Framing::Rest),
+        }
+    }
+}
+
+/// Decodes a header from all its values, repeated values are joined with `, `.
+fn decode_values<'a, H: TypedHeader>(
+    mut values: impl Iterator<Item = &'a str>,
+) -> Result<Option<H>, Error> {
+    let Some(first) = values.next() else {
+        return Ok(None);
+    };
+
+    match values.next() {
+        None => H::decode(first).map(Some),
+        Some(second) => {
+            let mut joined = format!("{}, {}", first, second);
+            for value in values {
+                joined.push_str(", ");
+                joined.push_str(value);
+            }
+            H::decode(&joined).map(Some)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_insensitive_lookup() {
+        let mut headers = Headers::new();
+        headers.insert("Content-Type", "text/html");
+
+        // Tests
+        assert_eq!(Some("text/html"), headers.get("content-type"));
+        assert!(headers.contains("CONTENT-TYPE"));
+        assert_eq!(None, headers.get("Content-Length"));
+    }
+
+    #[test]
+    fn test_append_keeps_every_value() {
+        let mut headers = Headers::new();
+        headers.append("Cookie", "a=1");
+        headers.append("Host", "example");
+        headers.append("cookie", "b=2");
+
+        // Tests
+        assert_eq!(Some("a=1"), headers.get("Cookie"));
+        assert_eq!(
+            vec!["a=1", "b=2"],
+            headers.get_all("COOKIE").collect::<Vec<_>>()
+        );
+        assert_eq!(3, headers.len());
+        assert_eq!(
+            "Cookie: a=1\nHost: example\ncookie: b=2\n",
+            headers.to_string()
+        );
+    }
+
+    #[test]
+    fn test_insert_replaces_every_value() {
+        let mut headers = Headers::new();
+        headers.append("a", "1");
+        headers.append("b", "2");
+        headers.append("A", "3");
+        let old = headers.insert("A", "4");
+
+        // Tests
+        assert_eq!(Some("1".into()), old);
+        assert_eq!(
+            vec![("A", "4"), ("b", "2")],
+            headers.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut headers: Headers = [("a", "1"), ("b", "2"), ("A", "3")].into_iter().collect();
+
+        // Tests
+        assert_eq!(Some("1".into()), headers.remove("a"));
+        assert_eq!(None, headers.remove("a"));
+        assert_eq!(vec![("b", "2")], headers.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_parsed_repeated_headers_are_kept() {
+        let headers: Headers = HeadersRef::parse("z: 1\na: 2\nZ: 3", &ParseOptions::default())
+            .unwrap()
+            .into();
+
+        // Tests
+        assert_eq!(vec!["1", "3"], headers.get_all("z").collect::<Vec<_>>());
+        assert_eq!(
+            vec![("z", "1"), ("a", "2"), ("Z", "3")],
+            headers.iter().collect::<Vec<_>>()
+        );
+    }
+}
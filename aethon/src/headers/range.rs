@@ -0,0 +1,308 @@
+//! Byte ranges, to fetch only part of a body.
+
+use super::{date, invalid, ETag, TypedHeader};
+use crate::Error;
+use std::{ops, time::SystemTime};
+
+/// A range of a `Range` header, bounds are inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange {
+    /// `first-last`
+    FromTo(u64, u64),
+    /// `first-`, up to the end.
+    From(u64),
+    /// `-length`, the last `length` bytes.
+    Last(u64),
+}
+
+impl ByteRange {
+    /// Returns the bytes of a `len` long body this range selects, `None` if it's unsatisfiable.
+    pub fn resolve(&self, len: u64) -> Option<ops::Range<u64>> {
+        match *self {
+            // The last byte is clamped first, so the end can't overflow
+            ByteRange::FromTo(first, last) if first < len => Some(first..last.min(len - 1) + 1),
+            ByteRange::From(first) if first < len => Some(first..len),
+            ByteRange::Last(length) if length > 0 && len > 0 => Some(len - length.min(len)..len),
+            _ => None,
+        }
+    }
+}
+
+/// `Range: bytes=0-499, -500`, the parts of the body the client wants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Range(pub Vec<ByteRange>);
+
+impl Range {
+    /// Returns the satisfiable ranges of a `len` long body in order, overlapping and adjacent
+    /// ones are merged.
+    pub fn resolve(&self, len: u64) -> Vec<ops::Range<u64>> {
+        let mut ranges: Vec<_> = self.0.iter().filter_map(|r| r.resolve(len)).collect();
+        ranges.sort_by_key(|r| r.start);
+
+        let mut merged: Vec<ops::Range<u64>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+}
+
+impl TypedHeader for Range {
+    const NAME: &'static str = "Range";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        let (unit, specs) = value
+            .split_once('=')
+            .ok_or(invalid::<Self>("expected bytes=ranges"))?;
+        if !unit.trim().eq_ignore_ascii_case("bytes") {
+            return Err(invalid::<Self>("only bytes ranges are supported"));
+        }
+
+        let number = |s: &str| {
+            // u64::from_str accepts a leading +
+            match !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) {
+                true => s.parse().map_err(|_| invalid::<Self>("range too large")),
+                false => Err(invalid::<Self>("expected a number")),
+            }
+        };
+        let ranges = specs
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|spec| {
+                let (first, last) = spec
+                    .split_once('-')
+                    .ok_or(invalid::<Self>("expected first-last"))?;
+                match (first, last) {
+                    ("", length) => Ok(ByteRange::Last(number(length)?)),
+                    (first, "") => Ok(ByteRange::From(number(first)?)),
+                    (first, last) => {
+                        let (first, last) = (number(first)?, number(last)?);
+                        match first <= last {
+                            true => Ok(ByteRange::FromTo(first, last)),
+                            false => Err(invalid::<Self>("range ends before it starts")),
+                        }
+                    }
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match ranges.is_empty() {
+            true => Err(invalid::<Self>("expected at least one range")),
+            false => Ok(Range(ranges)),
+        }
+    }
+
+    fn encode(&self) -> String {
+        let specs: Vec<String> = self
+            .0
+            .iter()
+            .map(|range| match range {
+                ByteRange::FromTo(first, last) => format!("{}-{}", first, last),
+                ByteRange::From(first) => format!("{}-", first),
+                ByteRange::Last(length) => format!("-{}", length),
+            })
+            .collect();
+        format!("bytes={}", specs.join(", "))
+    }
+}
+
+/// `Content-Range: bytes 0-499/1234`, the part of the body a `206 Partial Content` carries.
+///
+/// `bytes */1234` answers an unsatisfiable range with `416 Range Not Satisfiable`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentRange {
+    /// First and last byte, inclusive, `None` for `*`.
+    pub range: Option<(u64, u64)>,
+    /// Length of the whole body, `None` if it's unknown.
+    pub complete_length: Option<u64>,
+}
+
+impl ContentRange {
+    /// The header for the half-open `range` of a `len` long body, `range` can't be empty.
+    pub fn new(range: ops::Range<u64>, len: u64) -> Self {
+        debug_assert!(!range.is_empty(), "Content-Range of an empty range");
+        Self {
+            range: Some((range.start, range.end - 1)),
+            complete_length: Some(len),
+        }
+    }
+
+    /// The header of a `416 Range Not Satisfiable` for a `len` long body.
+    pub fn unsatisfied(len: u64) -> Self {
+        Self {
+            range: None,
+            complete_length: Some(len),
+        }
+    }
+}
+
+impl TypedHeader for ContentRange {
+    const NAME: &'static str = "Content-Range";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        let rest = value
+            .trim()
+            .strip_prefix("bytes ")
+            .ok_or(invalid::<Self>("expected bytes"))?;
+        let (range, complete_length) = rest
+            .split_once('/')
+            .ok_or(invalid::<Self>("expected range/length"))?;
+        let number = |s: &str| {
+            s.trim()
+                .parse::<u64>()
+                .map_err(|_| invalid::<Self>("expected a number"))
+        };
+
+        let complete_length = match complete_length.trim() {
+            "*" => None,
+            length => Some(number(length)?),
+        };
+        let range = match range.trim() {
+            "*" if complete_length.is_some() => None,
+            range => {
+                let (first, last) = range
+                    .split_once('-')
+                    .ok_or(invalid::<Self>("expected first-last"))?;
+                let (first, last) = (number(first)?, number(last)?);
+                if first > last || complete_length.is_some_and(|len| last >= len) {
+                    return Err(invalid::<Self>("invalid range"));
+                }
+                Some((first, last))
+            }
+        };
+
+        Ok(ContentRange {
+            range,
+            complete_length,
+        })
+    }
+
+    fn encode(&self) -> String {
+        let range = match self.range {
+            Some((first, last)) => format!("{}-{}", first, last),
+            None => "*".to_string(),
+        };
+        let length = match self.complete_length {
+            Some(length) => length.to_string(),
+            None => "*".to_string(),
+        };
+        format!("bytes {}/{}", range, length)
+    }
+}
+
+/// `If-Range: "33a64df5"`, the range only applies to this version, otherwise the whole body is
+/// sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IfRange {
+    ETag(ETag),
+    /// The `Last-Modified` date of the version.
+    Date(SystemTime),
+}
+
+impl IfRange {
+    /// Whether the current version is the one the client has, tags are compared strongly and
+    /// dates have to be equal.
+    pub fn matches(&self, etag: Option<&ETag>, last_modified: Option<SystemTime>) -> bool {
+        match self {
+            IfRange::ETag(tag) => etag.is_some_and(|etag| tag.strong_eq(etag)),
+            IfRange::Date(date) => last_modified.is_some_and(|lm| {
+                // Dates only have a precision of one second on the wire
+                date::format(lm) == date::format(*date)
+            }),
+        }
+    }
+}
+
+impl TypedHeader for IfRange {
+    const NAME: &'static str = "If-Range";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        let value = value.trim();
+        if value.starts_with('"') || value.starts_with("W/") {
+            return value.parse().map(IfRange::ETag);
+        }
+        date::parse(value)
+            .map(IfRange::Date)
+            .ok_or(invalid::<Self>("expected a tag or an IMF-fixdate"))
+    }
+
+    fn encode(&self) -> String {
+        match self {
+            IfRange::ETag(tag) => tag.to_string(),
+            IfRange::Date(date) => date::format(*date),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range() {
+        let range = Range::decode("bytes=0-499, 1000-, -200, 400-600").unwrap();
+
+        // Tests
+        assert_eq!(
+            Range(vec![
+                ByteRange::FromTo(0, 499),
+                ByteRange::From(1000),
+                ByteRange::Last(200),
+                ByteRange::FromTo(400, 600),
+            ]),
+            range
+        );
+        assert_eq!("bytes=0-499, 1000-, -200, 400-600", range.encode());
+        assert_eq!(vec![0..601, 1000..1200], range.resolve(1200));
+        assert_eq!(vec![0..100], range.resolve(100));
+        assert_eq!(Some(0..10), ByteRange::Last(50).resolve(10));
+        assert_eq!(None, ByteRange::From(10).resolve(10));
+        assert_eq!(None, ByteRange::Last(0).resolve(10));
+        let unbounded = Range::decode("bytes=0-18446744073709551615").unwrap();
+        assert_eq!(vec![0..10], unbounded.resolve(10));
+        assert_eq!(Some(5..10), ByteRange::FromTo(5, u64::MAX).resolve(10));
+        assert!(Range::decode("bytes=5-1").is_err());
+        assert!(Range::decode("bytes=+1-2").is_err());
+        assert!(Range::decode("items=0-1").is_err());
+        assert!(Range::decode("bytes=").is_err());
+    }
+
+    #[test]
+    fn test_content_range() {
+        // Tests
+        assert_eq!(
+            Ok(ContentRange::new(0..500, 1234)),
+            ContentRange::decode("bytes 0-499/1234")
+        );
+        assert_eq!("bytes */1234", ContentRange::unsatisfied(1234).encode());
+        assert_eq!(
+            Ok(ContentRange {
+                range: Some((5, 9)),
+                complete_length: None
+            }),
+            ContentRange::decode("bytes 5-9/*")
+        );
+        assert!(ContentRange::decode("bytes */*").is_err());
+        assert!(ContentRange::decode("bytes 0-1234/1234").is_err());
+    }
+
+    #[test]
+    fn test_if_range() {
+        let date = "Wed, 21 Oct 2015 07:28:00 GMT";
+        let lm = date::parse(date).unwrap();
+
+        // Tests
+        assert!(IfRange::decode("\"v1\"")
+            .unwrap()
+            .matches(Some(&ETag::strong("v1")), None));
+        assert!(!IfRange::decode("W/\"v1\"")
+            .unwrap()
+            .matches(Some(&ETag::weak("v1")), None));
+        assert!(IfRange::decode(date).unwrap().matches(None, Some(lm)));
+        assert!(IfRange::decode("yesterday").is_err());
+    }
+}
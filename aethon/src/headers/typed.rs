@@ -0,0 +1,997 @@
+use super::{date, Error};
+use std::{
+    borrow::Cow,
+    fmt::{self, Display},
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+
+/// A header with a parsed representation.
+///
+/// Repeated headers are joined with `, ` before being decoded, so list headers like `Accept`
+/// see all their values and singleton headers like `Content-Length` reject conflicting ones.
+pub trait TypedHeader: Sized {
+    /// Name of the header.
+    const NAME: &'static str;
+
+    /// Parses the header's value.
+    fn decode(value: &str) -> Result<Self, Error>;
+
+    /// Serializes the header's value.
+    fn encode(&self) -> String;
+}
+
+/// Returns the error for a malformed `H`.
+pub(crate) fn invalid<H: TypedHeader>(reason: &'static str) -> Error {
+    Error::InvalidHeader {
+        name: H::NAME,
+        reason,
+    }
+}
+
+/// Whether `s` is a non-empty token made of visible ASCII characters without separators.
+pub(crate) fn is_token(s: &str) -> bool {
+    !s.is_empty()
+        && s.bytes()
+            .all(|b| b.is_ascii_graphic() && !b"\"(),/:;<=>?@[\\]{}".contains(&b))
+}
+
+/// `k=v` parameters of a header.
+type Params = Vec<(Box<str>, Box<str>)>;
+
+/// Splits `k=v` parameters, unquoting quoted values.
+fn parse_params<H: TypedHeader>(params: &str, separator: char) -> Result<Params, Error> {
+    split_quoted(params, separator)
+        .into_iter()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|param| {
+            let (k, v) = param
+                .split_once('=')
+                .ok_or(invalid::<H>("parameter without a value"))?;
+            let (k, v) = (k.trim(), v.trim());
+            if !is_token(k) {
+                return Err(invalid::<H>("invalid parameter name"));
+            }
+            Ok((k.to_ascii_lowercase().into(), unquote(v).into()))
+        })
+        .collect()
+}
+
+/// Splits `value` on the separators outside of quoted strings.
+pub(crate) fn split_quoted(value: &str, separator: char) -> Vec<&str> {
+    let (mut items, mut start) = (Vec::new(), 0);
+    let (mut quoted, mut escaped) = (false, false);
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => {
+                items.push(&value[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    items.push(&value[start..]);
+    items
+}
+
+/// Removes the backslashes of a quoted string's escapes.
+pub(crate) fn unescape(quoted: &str) -> String {
+    let mut s = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => s.extend(chars.next()),
+            c => s.push(c),
+        }
+    }
+    s
+}
+
+/// Returns the content of a quoted string, other values as they are.
+fn unquote(s: &str) -> Cow<'_, str> {
+    match s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Some(quoted) if quoted.contains('\\') => Cow::Owned(unescape(quoted)),
+        Some(quoted) => Cow::Borrowed(quoted),
+        None => Cow::Borrowed(s),
+    }
+}
+
+/// Writes `v` quoted if it isn't a token.
+fn write_param_value(f: &mut impl fmt::Write, v: &str) -> fmt::Result {
+    if is_token(v) {
+        write!(f, "{}", v)
+    } else {
+        write!(f, "\"{}\"", v.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+/// `Content-Type: text/html; charset=utf-8`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentType {
+    media_type: Box<str>,
+    params: Params,
+}
+
+impl ContentType {
+    /// Creates a content type from a `type/subtype` media type.
+    pub fn new(media_type: impl Into<Box<str>>) -> Self {
+        Self {
+            media_type: media_type.into(),
+            params: Vec::new(),
+        }
+    }
+
+    pub fn text() -> Self {
+        Self::new("text/plain").with_param("charset", "utf-8")
+    }
+
+    pub fn html() -> Self {
+        Self::new("text/html").with_param("charset", "utf-8")
+    }
+
+    pub fn json() -> Self {
+        Self::new("application/json")
+    }
+
+    pub fn octet_stream() -> Self {
+        Self::new("application/octet-stream")
+    }
+
+    /// Adds a parameter, e.g. `charset`.
+    pub fn with_param(mut self, key: impl Into<Box<str>>, value: impl Into<Box<str>>) -> Self {
+        self.params.push((key.into(), value.into()));
+        self
+    }
+
+    /// Returns the `type/subtype` part.
+    pub fn media_type(&self) -> &str {
+        &self.media_type
+    }
+
+    /// Returns the value of the parameter `key`, ignoring case.
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_ref())
+    }
+
+    pub fn charset(&self) -> Option<&str> {
+        self.param("charset")
+    }
+
+    /// Whether the media types match, ignoring parameters and case.
+    pub fn is(&self, media_type: &str) -> bool {
+        self.media_type.eq_ignore_ascii_case(media_type)
+    }
+}
+
+impl TypedHeader for ContentType {
+    const NAME: &'static str = "Content-Type";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        let (media_type, params) = value.split_once(';').unwrap_or((value, ""));
+        let media_type = media_type.trim();
+
+        match media_type.split_once('/') {
+            Some((t, s)) if is_token(t) && is_token(s) => Ok(ContentType {
+                media_type: media_type.to_ascii_lowercase().into(),
+                params: parse_params::<Self>(params, ';')?,
+            }),
+            _ => Err(invalid::<Self>("expected a type/subtype media type")),
+        }
+    }
+
+    fn encode(&self) -> String {
+        let mut s = self.media_type.to_string();
+        for (k, v) in &self.params {
+            s.push_str("; ");
+            s.push_str(k);
+            s.push('=');
+            // Writing into a String can't fail
+            let _ = write_param_value(&mut s, v);
+        }
+        s
+    }
+}
+
+/// `Content-Disposition: form-data; name="file"; filename="notes.txt"`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentDisposition {
+    disposition: Box<str>,
+    params: Params,
+}
+
+impl ContentDisposition {
+    pub fn new(disposition: impl Into<Box<str>>) -> Self {
+        Self {
+            disposition: disposition.into(),
+            params: Vec::new(),
+        }
+    }
+
+    /// The field `name` of a `multipart/form-data` body.
+    pub fn form_data(name: impl Into<Box<str>>) -> Self {
+        Self::new("form-data").with_param("name", name)
+    }
+
+    /// Adds a parameter, e.g. `filename`.
+    pub fn with_param(mut self, key: impl Into<Box<str>>, value: impl Into<Box<str>>) -> Self {
+        self.params.push((key.into(), value.into()));
+        self
+    }
+
+    /// Returns the disposition type, e.g. `form-data` or `attachment`.
+    pub fn disposition(&self) -> &str {
+        &self.disposition
+    }
+
+    /// Returns the value of the parameter `key`, ignoring case.
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_ref())
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.param("name")
+    }
+
+    pub fn filename(&self) -> Option<&str> {
+        self.param("filename")
+    }
+}
+
+impl TypedHeader for ContentDisposition {
+    const NAME: &'static str = "Content-Disposition";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        let (disposition, params) = value.split_once(';').unwrap_or((value, ""));
+        let disposition = disposition.trim();
+        if !is_token(disposition) {
+            return Err(invalid::<Self>("expected a disposition type"));
+        }
+
+        Ok(ContentDisposition {
+            disposition: disposition.to_ascii_lowercase().into(),
+            params: parse_params::<Self>(params, ';')?,
+        })
+    }
+
+    fn encode(&self) -> String {
+        let mut s = self.disposition.to_string();
+        for (k, v) in &self.params {
+            s.push_str("; ");
+            s.push_str(k);
+            s.push('=');
+            // Writing into a String can't fail
+            let _ = write_param_value(&mut s, v);
+        }
+        s
+    }
+}
+
+/// `Content-Length: 42`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentLength(pub u64);
+
+impl TypedHeader for ContentLength {
+    const NAME: &'static str = "Content-Length";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        let value = value.trim();
+        if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid::<Self>("expected a number"));
+        }
+
+        value
+            .parse()
+            .map(ContentLength)
+            .map_err(|_| invalid::<Self>("the number is too large"))
+    }
+
+    fn encode(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+/// `Host: example:8081`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Host {
+    host: Box<str>,
+    port: Option<u16>,
+}
+
+impl Host {
+    pub fn new(host: impl Into<Box<str>>, port: Option<u16>) -> Self {
+        Self {
+            host: host.into(),
+            port,
+        }
+    }
+
+    /// Returns the host name, IPv6 addresses are without brackets.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+}
+
+impl TypedHeader for Host {
+    const NAME: &'static str = "Host";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        let value = value.trim();
+        let (host, port) = match value.strip_prefix('[') {
+            // [::1]:8081
+            Some(rest) => {
+                let (host, rest) = rest
+                    .split_once(']')
+                    .ok_or(invalid::<Self>("unclosed IPv6 address"))?;
+                match rest {
+                    "" => (host, None),
+                    _ => (
+                        host,
+                        Some(
+                            rest.strip_prefix(':')
+                                .ok_or(invalid::<Self>("expected a port"))?,
+                        ),
+                    ),
+                }
+            }
+            None => match value.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (value, None),
+            },
+        };
+
+        if host.is_empty() || host.contains(char::is_whitespace) {
+            return Err(invalid::<Self>("invalid host name"));
+        }
+        let port = port
+            .map(|p| p.parse().map_err(|_| invalid::<Self>("invalid port")))
+            .transpose()?;
+
+        Ok(Host::new(host, port))
+    }
+
+    fn encode(&self) -> String {
+        let host = match self.host.contains(':') {
+            true => format!("[{}]", self.host),
+            false => self.host.to_string(),
+        };
+        match self.port {
+            Some(port) => format!("{}:{}", host, port),
+            None => host,
+        }
+    }
+}
+
+/// Parses a `q` parameter into thousandths, e.g. `0.8` into `800`.
+pub(crate) fn parse_quality(q: &str) -> Option<u16> {
+    let (int, frac) = q.split_once('.').unwrap_or((q, ""));
+    if frac.len() > 3 || !frac.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let frac: u16 = format!("{:0<3}", frac).parse().ok()?;
+    match int {
+        "0" => Some(frac),
+        "1" if frac == 0 => Some(1000),
+        _ => None,
+    }
+}
+
+/// Writes `item` with its quality, which is left out when it's `1`.
+pub(crate) fn with_quality(item: &str, quality: u16) -> String {
+    match quality {
+        1000 => item.to_string(),
+        q => format!("{};q={}", item, format!("0.{:03}", q).trim_end_matches('0')),
+    }
+}
+
+/// A media range of the `Accept` header, e.g. `text/*;q=0.8`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaRange {
+    media_type: Box<str>,
+    /// Quality in thousandths, `1000` is the default.
+    quality: u16,
+}
+
+impl MediaRange {
+    pub fn new(media_type: impl Into<Box<str>>, quality: u16) -> Self {
+        Self {
+            media_type: media_type.into(),
+            quality: quality.min(1000),
+        }
+    }
+
+    pub fn media_type(&self) -> &str {
+        &self.media_type
+    }
+
+    /// Returns the quality in thousandths, between `0` and `1000`.
+    pub fn quality(&self) -> u16 {
+        self.quality
+    }
+
+    /// Whether the range includes `media_type`, e.g. `text/*` includes `text/html`.
+    pub fn matches(&self, media_type: &str) -> bool {
+        match self.media_type.strip_suffix("/*") {
+            Some("*") => true,
+            Some(t) => media_type
+                .split_once('/')
+                .is_some_and(|(mt, _)| mt.eq_ignore_ascii_case(t)),
+            None => self.media_type.eq_ignore_ascii_case(media_type),
+        }
+    }
+}
+
+/// `Accept: text/html, application/json;q=0.9, */*;q=0.1`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Accept(pub Vec<MediaRange>);
+
+impl Accept {
+    /// Returns the quality given to `media_type` by the most specific matching range.
+    pub fn quality(&self, media_type: &str) -> u16 {
+        self.0
+            .iter()
+            .filter(|r| r.matches(media_type))
+            .max_by_key(|r| (!r.media_type.ends_with('*'), !r.media_type.starts_with('*')))
+            .map_or(0, |r| r.quality)
+    }
+
+    /// Whether `media_type` is acceptable.
+    pub fn accepts(&self, media_type: &str) -> bool {
+        self.quality(media_type) > 0
+    }
+
+    /// Picks the best of `available`, earlier ones win ties.
+    pub fn negotiate<'a>(&self, available: &[&'a str]) -> Option<&'a str> {
+        available
+            .iter()
+            .map(|t| (self.quality(t), *t))
+            .filter(|(q, _)| *q > 0)
+            .fold(None, |best: Option<(u16, &str)>, (q, t)| match best {
+                Some((bq, _)) if bq >= q => best,
+                _ => Some((q, t)),
+            })
+            .map(|(_, t)| t)
+    }
+}
+
+impl TypedHeader for Accept {
+    const NAME: &'static str = "Accept";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        split_quoted(value, ',')
+            .into_iter()
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+            .map(|range| {
+                let (media_type, params) = range.split_once(';').unwrap_or((range, ""));
+                let media_type = media_type.trim();
+                if !media_type
+                    .split_once('/')
+                    .is_some_and(|(t, s)| is_token(t) && is_token(s))
+                {
+                    return Err(invalid::<Self>("expected a type/subtype media range"));
+                }
+
+                let quality = match parse_params::<Self>(params, ';')?
+                    .iter()
+                    .find(|(k, _)| k.as_ref() == "q")
+                {
+                    Some((_, q)) => {
+                        parse_quality(q).ok_or(invalid::<Self>("invalid quality value"))?
+                    }
+                    None => 1000,
+                };
+                Ok(MediaRange::new(media_type.to_ascii_lowercase(), quality))
+            })
+            .collect::<Result<_, _>>()
+            .map(Accept)
+    }
+
+    fn encode(&self) -> String {
+        self.0
+            .iter()
+            .map(|r| with_quality(&r.media_type, r.quality))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// `Location: /new/path`, where a redirect points to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location(pub Box<str>);
+
+impl TypedHeader for Location {
+    const NAME: &'static str = "Location";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        let value = value.trim();
+        if value.is_empty() || value.contains(char::is_whitespace) {
+            return Err(invalid::<Self>("expected a path or an URL"));
+        }
+        Ok(Location(value.into()))
+    }
+
+    fn encode(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+/// `Cache-Control: max-age=60, must-revalidate`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CacheControl {
+    pub no_cache: bool,
+    pub no_store: bool,
+    pub no_transform: bool,
+    pub must_revalidate: bool,
+    pub public: bool,
+    pub private: bool,
+    pub immutable: bool,
+    pub max_age: Option<Duration>,
+    pub s_max_age: Option<Duration>,
+    /// Directives without a dedicated field, e.g. `stale-while-revalidate=30`.
+    pub extensions: Vec<(Box<str>, Option<Box<str>>)>,
+}
+
+impl TypedHeader for CacheControl {
+    const NAME: &'static str = "Cache-Control";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        let mut cc = CacheControl::default();
+
+        for directive in split_quoted(value, ',')
+            .into_iter()
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+        {
+            let (name, arg) = match directive.split_once('=') {
+                Some((name, arg)) => (name.trim(), Some(unquote(arg.trim()))),
+                None => (directive, None),
+            };
+            if !is_token(name) {
+                return Err(invalid::<Self>("invalid directive"));
+            }
+            let seconds = || {
+                arg.as_deref()
+                    .and_then(|a| a.parse().ok())
+                    .map(Duration::from_secs)
+                    .ok_or(invalid::<Self>("expected a number of seconds"))
+            };
+
+            match name.to_ascii_lowercase().as_str() {
+                "no-cache" => cc.no_cache = true,
+                "no-store" => cc.no_store = true,
+                "no-transform" => cc.no_transform = true,
+                "must-revalidate" => cc.must_revalidate = true,
+                "public" => cc.public = true,
+                "private" => cc.private = true,
+                "immutable" => cc.immutable = true,
+                "max-age" => cc.max_age = Some(seconds()?),
+                "s-maxage" => cc.s_max_age = Some(seconds()?),
+                _ => cc.extensions.push((name.into(), arg.map(Into::into))),
+            }
+        }
+
+        Ok(cc)
+    }
+
+    fn encode(&self) -> String {
+        let flags = [
+            (self.no_cache, "no-cache"),
+            (self.no_store, "no-store"),
+            (self.no_transform, "no-transform"),
+            (self.must_revalidate, "must-revalidate"),
+            (self.public, "public"),
+            (self.private, "private"),
+            (self.immutable, "immutable"),
+        ];
+        let mut directives: Vec<String> = flags
+            .iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, name)| name.to_string())
+            .collect();
+
+        if let Some(max_age) = self.max_age {
+            directives.push(format!("max-age={}", max_age.as_secs()));
+        }
+        if let Some(s_max_age) = self.s_max_age {
+            directives.push(format!("s-maxage={}", s_max_age.as_secs()));
+        }
+        for (name, arg) in &self.extensions {
+            let mut directive = name.to_string();
+            if let Some(arg) = arg {
+                directive.push('=');
+                // Writing into a String can't fail
+                let _ = write_param_value(&mut directive, arg);
+            }
+            directives.push(directive);
+        }
+
+        directives.join(", ")
+    }
+}
+
+/// `ETag: "33a64df5"` or `ETag: W/"33a64df5"` for weak validators.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ETag {
+    weak: bool,
+    tag: Box<str>,
+}
+
+impl ETag {
+    /// Creates a strong tag, `tag` is without quotes.
+    pub fn strong(tag: impl Into<Box<str>>) -> Self {
+        Self {
+            weak: false,
+            tag: tag.into(),
+        }
+    }
+
+    /// Creates a weak tag, `tag` is without quotes.
+    pub fn weak(tag: impl Into<Box<str>>) -> Self {
+        Self {
+            weak: true,
+            tag: tag.into(),
+        }
+    }
+
+    pub fn is_weak(&self) -> bool {
+        self.weak
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Both tags are strong and equal.
+    pub fn strong_eq(&self, other: &ETag) -> bool {
+        !self.weak && !other.weak && self.tag == other.tag
+    }
+
+    /// The tags are equal, weakness is ignored.
+    pub fn weak_eq(&self, other: &ETag) -> bool {
+        self.tag == other.tag
+    }
+}
+
+impl FromStr for ETag {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (weak, quoted) = match s.strip_prefix("W/") {
+            Some(quoted) => (true, quoted),
+            None => (false, s),
+        };
+        let tag = quoted
+            .strip_prefix('"')
+            .and_then(|t| t.strip_suffix('"'))
+            .filter(|t| !t.contains('"'))
+            .ok_or(invalid::<Self>("expected a quoted tag"))?;
+
+        Ok(ETag {
+            weak,
+            tag: tag.into(),
+        })
+    }
+}
+
+impl Display for ETag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.weak {
+            write!(f, "W/")?;
+        }
+        write!(f, "\"{}\"", self.tag)
+    }
+}
+
+impl TypedHeader for ETag {
+    const NAME: &'static str = "ETag";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        value.parse()
+    }
+
+    fn encode(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// `Date: Sun, 06 Nov 1994 08:49:37 GMT`, when the packet was sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Date(pub SystemTime);
+
+impl Date {
+    pub fn now() -> Self {
+        Date(SystemTime::now())
+    }
+}
+
+impl TypedHeader for Date {
+    const NAME: &'static str = "Date";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        date::parse(value)
+            .map(Date)
+            .ok_or(invalid::<Self>("expected an IMF-fixdate"))
+    }
+
+    fn encode(&self) -> String {
+        date::format(self.0)
+    }
+}
+
+/// `Connection: keep-alive` or `Connection: close`, whether the sender keeps the connection open
+/// after the exchange.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connection {
+    KeepAlive,
+    Close,
+}
+
+impl TypedHeader for Connection {
+    const NAME: &'static str = "Connection";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        let mut connection = None;
+        for option in value.split(',').map(str::trim) {
+            if !is_token(option) {
+                return Err(invalid::<Self>("expected a list of options"));
+            }
+            // close wins over keep-alive, other options are ignored
+            if option.eq_ignore_ascii_case("close") {
+                return Ok(Connection::Close);
+            } else if option.eq_ignore_ascii_case("keep-alive") {
+                connection = Some(Connection::KeepAlive);
+            }
+        }
+        connection.ok_or(invalid::<Self>("expected keep-alive or close"))
+    }
+
+    fn encode(&self) -> String {
+        match self {
+            Connection::KeepAlive => "keep-alive".to_string(),
+            Connection::Close => "close".to_string(),
+        }
+    }
+}
+
+/// `Keep-Alive: timeout=5, max=100`, how long an idle connection stays open and how many more
+/// requests it serves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct KeepAlive {
+    pub timeout: Option<Duration>,
+    pub max: Option<u32>,
+}
+
+impl TypedHeader for KeepAlive {
+    const NAME: &'static str = "Keep-Alive";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        let mut keep_alive = KeepAlive::default();
+        for param in value.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (name, arg) = param
+                .split_once('=')
+                .ok_or(invalid::<Self>("expected name=value parameters"))?;
+            let number = || {
+                arg.trim()
+                    .parse::<u32>()
+                    .map_err(|_| invalid::<Self>("expected a number"))
+            };
+
+            match name.trim().to_ascii_lowercase().as_str() {
+                "timeout" => keep_alive.timeout = Some(Duration::from_secs(number()?.into())),
+                "max" => keep_alive.max = Some(number()?),
+                _ => {}
+            }
+        }
+        Ok(keep_alive)
+    }
+
+    fn encode(&self) -> String {
+        let timeout = self.timeout.map(|t| format!("timeout={}", t.as_secs()));
+        let max = self.max.map(|m| format!("max={}", m));
+        timeout
+            .into_iter()
+            .chain(max)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Headers;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn test_content_type() {
+        let ct = ContentType::decode("Text/HTML; Charset=\"utf-8\"").unwrap();
+
+        // Tests
+        assert!(ct.is("text/html"));
+        assert_eq!(Some("utf-8"), ct.charset());
+        assert_eq!("text/html; charset=utf-8", ct.encode());
+        assert!(ContentType::decode("text").is_err());
+        assert!(ContentType::decode("text/html; charset").is_err());
+    }
+
+    #[test]
+    fn test_content_disposition() {
+        let header =
+            ContentDisposition::decode("Form-Data; name=\"file\"; filename=\"a b.txt\"").unwrap();
+
+        // Tests
+        assert_eq!("form-data", header.disposition());
+        assert_eq!(Some("file"), header.name());
+        assert_eq!(Some("a b.txt"), header.filename());
+        assert_eq!(
+            "form-data; name=file; filename=\"a b.txt\"",
+            header.encode()
+        );
+        assert!(ContentDisposition::decode("").is_err());
+    }
+
+    #[test]
+    fn test_quoted_params_round_trip() {
+        let disposition =
+            ContentDisposition::form_data("f").with_param("filename", "a;b\"c\\d, e.txt");
+        let content_type = ContentType::new("text/plain").with_param("x", "1; y=\"2\"");
+        let cache_control = CacheControl::decode("no-cache=\"a, b\", max-age=5").unwrap();
+
+        // Tests
+        assert_eq!(
+            "form-data; name=f; filename=\"a;b\\\"c\\\\d, e.txt\"",
+            disposition.encode()
+        );
+        assert_eq!(
+            Ok(disposition.clone()),
+            ContentDisposition::decode(&disposition.encode())
+        );
+        assert_eq!(
+            Ok(content_type.clone()),
+            ContentType::decode(&content_type.encode())
+        );
+        assert_eq!(Some(Duration::from_secs(5)), cache_control.max_age);
+        assert_eq!(
+            Ok(2),
+            Accept::decode("text/html; x=\"a, b\", */*;q=0.1").map(|a| a.0.len())
+        );
+    }
+
+    #[test]
+    fn test_content_length() {
+        // Tests
+        assert_eq!(Ok(ContentLength(42)), ContentLength::decode("42"));
+        assert_eq!(
+            Err(Error::InvalidHeader {
+                name: "Content-Length",
+                reason: "expected a number"
+            }),
+            ContentLength::decode("+42")
+        );
+        assert!(ContentLength::decode("1, 2").is_err());
+    }
+
+    #[test]
+    fn test_host() {
+        // Tests
+        assert_eq!(
+            Ok(Host::new("example", Some(8081))),
+            Host::decode("example:8081")
+        );
+        assert_eq!(Ok(Host::new("::1", None)), Host::decode("[::1]"));
+        assert_eq!("[::1]:80", Host::new("::1", Some(80)).encode());
+        assert!(Host::decode("example:port").is_err());
+        assert!(Host::decode("").is_err());
+    }
+
+    #[test]
+    fn test_accept() {
+        let accept = Accept::decode("text/*;q=0.5, text/html, */*;q=0.1").unwrap();
+
+        // Tests
+        assert_eq!(1000, accept.quality("text/html"));
+        assert_eq!(500, accept.quality("text/css"));
+        assert_eq!(100, accept.quality("image/png"));
+        assert_eq!(
+            Some("text/html"),
+            accept.negotiate(&["application/json", "text/html"])
+        );
+        assert_eq!("text/*;q=0.5, text/html, */*;q=0.1", accept.encode());
+        assert!(Accept::decode("text/html;q=2").is_err());
+    }
+
+    #[test]
+    fn test_cache_control() {
+        let cc = CacheControl::decode("max-age=60, must-revalidate, foo=\"bar\"").unwrap();
+
+        // Tests
+        assert_eq!(Some(Duration::from_secs(60)), cc.max_age);
+        assert!(cc.must_revalidate);
+        assert_eq!(vec![("foo".into(), Some("bar".into()))], cc.extensions);
+        assert_eq!("must-revalidate, max-age=60, foo=bar", cc.encode());
+        assert!(CacheControl::decode("max-age=soon").is_err());
+    }
+
+    #[test]
+    fn test_etag() {
+        let weak = ETag::decode("W/\"abc\"").unwrap();
+        let strong = ETag::decode("\"abc\"").unwrap();
+
+        // Tests
+        assert!(weak.is_weak());
+        assert!(weak.weak_eq(&strong));
+        assert!(!weak.strong_eq(&strong));
+        assert_eq!("W/\"abc\"", weak.encode());
+        assert!(ETag::decode("abc").is_err());
+    }
+
+    #[test]
+    fn test_date() {
+        let date = Date(UNIX_EPOCH + Duration::from_secs(784111777));
+
+        // Tests
+        assert_eq!(Ok(date), Date::decode(&date.encode()));
+        assert!(Date::decode("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_connection() {
+        // Tests
+        assert_eq!(
+            Ok(Connection::Close),
+            Connection::decode("Keep-Alive, close")
+        );
+        assert_eq!(
+            Ok(Connection::KeepAlive),
+            Connection::decode("keep-alive, Upgrade")
+        );
+        assert_eq!("close", Connection::Close.encode());
+        assert!(Connection::decode("upgrade").is_err());
+        assert!(Connection::decode("keep alive").is_err());
+    }
+
+    #[test]
+    fn test_keep_alive() {
+        let keep_alive = KeepAlive {
+            timeout: Some(Duration::from_secs(5)),
+            max: Some(100),
+        };
+
+        // Tests
+        assert_eq!(
+            Ok(keep_alive),
+            KeepAlive::decode("timeout=5, max=100, extra=1")
+        );
+        assert_eq!("timeout=5, max=100", keep_alive.encode());
+        assert!(KeepAlive::decode("timeout").is_err());
+        assert!(KeepAlive::decode("max=-1").is_err());
+    }
+
+    #[test]
+    fn test_typed_get_and_insert() {
+        let mut headers = Headers::new();
+        headers.typed_insert(ContentLength(5));
+        headers.append("accept", "text/html");
+        headers.append("Accept", "application/json");
+
+        // Tests
+        assert_eq!(Ok(Some(ContentLength(5))), headers.typed_get());
+        assert_eq!(Some("5"), headers.get("content-length"));
+        assert_eq!(2, headers.typed_get::<Accept>().unwrap().unwrap().0.len());
+        assert_eq!(Ok(None), headers.typed_get::<Host>());
+    }
+}
@@ -0,0 +1,361 @@
+//! Conversions to and from the types of the `http` crate, enabled by the `http` feature.
+//!
+//! Aethon packets carry their own protocol version, so HTTP versions aren't converted: requests
+//! and responses coming from `http` use [`Version::V1`] and the ones going to it the default
+//! HTTP version. Extensions of `http` packets are dropped.
+//!
+//! [`Version::V1`]: crate::Version::V1
+
+use super::{
+    headers::Headers, method::Method, request::Request, response::Response, status::Status, Error,
+};
+use std::fmt;
+
+/// Error returned when a value has no equivalent on the other side.
+#[derive(Debug)]
+pub enum HttpError {
+    /// The method has no equivalent on the other side, with its name. Aethon methods are
+    /// uppercase letters, digits, `-` and `_`.
+    Method(Box<str>),
+    /// The status code has no equivalent on the other side, Aethon codes are between 100 and
+    /// 599 and `http` ones above 99.
+    Status(u16),
+    /// A header's name or value can't be converted, with the header's name.
+    Header(Box<str>),
+    /// The path of the request isn't a valid URI.
+    Path(Box<str>),
+    /// The packet has trailers, which `http` packets can't carry.
+    Trailers,
+    /// The converted values don't make a valid Aethon packet.
+    Aethon(Error),
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Method(method) => write!(f, "The {} method has no equivalent", method),
+            Self::Status(status) => write!(f, "The {} status has no equivalent", status),
+            Self::Header(name) => write!(f, "The {} header can't be converted", name),
+            Self::Path(path) => write!(f, "The path {} isn't a valid URI", path),
+            Self::Trailers => write!(f, "Trailers can't be converted"),
+            Self::Aethon(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for HttpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Aethon(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for HttpError {
+    fn from(e: Error) -> Self {
+        Self::Aethon(e)
+    }
+}
+
+/// Extension methods built directly can hold any string, those that aren't HTTP tokens fail.
+impl TryFrom<Method> for http::Method {
+    type Error = HttpError;
+    fn try_from(method: Method) -> Result<Self, Self::Error> {
+        Ok(match method {
+            Method::GET => http::Method::GET,
+            Method::POST => http::Method::POST,
+            Method::DELETE => http::Method::DELETE,
+            Method::PUT => http::Method::PUT,
+            Method::PATCH => http::Method::PATCH,
+            Method::HEAD => http::Method::HEAD,
+            Method::OPTIONS => http::Method::OPTIONS,
+            Method::Extension(method) => http::Method::from_bytes(method.as_bytes())
+                .map_err(|_| HttpError::Method(method))?,
+        })
+    }
+}
+
+impl TryFrom<http::Method> for Method {
+    type Error = HttpError;
+    fn try_from(method: http::Method) -> Result<Self, Self::Error> {
+        method
+            .as_str()
+            .parse()
+            .map_err(|_| HttpError::Method(method.as_str().into()))
+    }
+}
+
+/// Custom statuses built directly can hold any code, those below 100 fail.
+impl TryFrom<Status> for http::StatusCode {
+    type Error = HttpError;
+    fn try_from(status: Status) -> Result<Self, Self::Error> {
+        http::StatusCode::from_u16(status.code()).map_err(|_| HttpError::Status(status.code()))
+    }
+}
+
+impl TryFrom<http::StatusCode> for Status {
+    type Error = HttpError;
+    fn try_from(status: http::StatusCode) -> Result<Self, Self::Error> {
+        Status::try_from(status.as_u16()).map_err(|_| HttpError::Status(status.as_u16()))
+    }
+}
+
+impl TryFrom<&Headers> for http::HeaderMap {
+    type Error = HttpError;
+    fn try_from(headers: &Headers) -> Result<Self, Self::Error> {
+        let mut map = http::HeaderMap::with_capacity(headers.len());
+        for (name, value) in headers.iter() {
+            let converted = http::HeaderName::from_bytes(name.as_bytes())
+                .ok()
+                .zip(http::HeaderValue::from_bytes(value.as_bytes()).ok());
+            let (name, value) = converted.ok_or_else(|| HttpError::Header(name.into()))?;
+            map.append(name, value);
+        }
+        Ok(map)
+    }
+}
+
+impl TryFrom<&http::HeaderMap> for Headers {
+    type Error = HttpError;
+    fn try_from(map: &http::HeaderMap) -> Result<Self, Self::Error> {
+        let mut headers = Headers::new();
+        for (name, value) in map {
+            let value = std::str::from_utf8(value.as_bytes())
+                .map_err(|_| HttpError::Header(name.as_str().into()))?;
+            headers.append(name.as_str(), value);
+        }
+        Ok(headers)
+    }
+}
+
+impl TryFrom<Request> for http::Request<Vec<u8>> {
+    type Error = HttpError;
+    fn try_from(req: Request) -> Result<Self, Self::Error> {
+        if !req.trailers().is_empty() {
+            return Err(HttpError::Trailers);
+        }
+        let uri =
+            http::Uri::try_from(req.path()).map_err(|_| HttpError::Path(req.path().into()))?;
+        let headers = http::HeaderMap::try_from(req.headers())?;
+
+        let mut http_req = http::Request::new(Vec::new());
+        *http_req.method_mut() = req.method().clone().try_into()?;
+        *http_req.uri_mut() = uri;
+        *http_req.headers_mut() = headers;
+        *http_req.body_mut() = req.into_body();
+        Ok(http_req)
+    }
+}
+
+/// The path is the path and query of the URI, its authority becomes the `Host` header if there
+/// isn't one.
+impl<B: Into<Vec<u8>>> TryFrom<http::Request<B>> for Request {
+    type Error = HttpError;
+    fn try_from(req: http::Request<B>) -> Result<Self, Self::Error> {
+        let (parts, body) = req.into_parts();
+        let mut headers = Headers::try_from(&parts.headers)?;
+        if let Some(authority) = parts.uri.authority() {
+            if !headers.contains(http::header::HOST.as_str()) {
+                headers.append("Host", authority.as_str());
+            }
+        }
+        let path = parts.uri.path_and_query().map_or("/", |p| p.as_str());
+
+        Ok(Request::builder()
+            .method(parts.method.try_into()?)
+            .path(path)
+            .headers(headers)
+            .body(body)
+            .build()?)
+    }
+}
+
+impl TryFrom<Response> for http::Response<Vec<u8>> {
+    type Error = HttpError;
+    fn try_from(res: Response) -> Result<Self, Self::Error> {
+        if !res.trailers().is_empty() {
+            return Err(HttpError::Trailers);
+        }
+        let headers = http::HeaderMap::try_from(res.headers())?;
+
+        let mut http_res = http::Response::new(Vec::new());
+        *http_res.status_mut() = res.status().try_into()?;
+        *http_res.headers_mut() = headers;
+        *http_res.body_mut() = res.into_body();
+        Ok(http_res)
+    }
+}
+
+impl<B: Into<Vec<u8>>> TryFrom<http::Response<B>> for Response {
+    type Error = HttpError;
+    fn try_from(res: http::Response<B>) -> Result<Self, Self::Error> {
+        let (parts, body) = res.into_parts();
+
+        Ok(Response::builder()
+            .status(parts.status.try_into()?)
+            .headers(Headers::try_from(&parts.headers)?)
+            .body(body)
+            .build()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_method_and_status() {
+        // Tests
+        assert_eq!(
+            http::Method::PATCH,
+            http::Method::try_from(Method::PATCH).unwrap()
+        );
+        assert_eq!(
+            "M-SEARCH",
+            http::Method::try_from(Method::Extension("M-SEARCH".into()))
+                .unwrap()
+                .as_str()
+        );
+        assert!(matches!(
+            http::Method::try_from(Method::Extension("A B".into())),
+            Err(HttpError::Method(method)) if &*method == "A B"
+        ));
+        assert_eq!(
+            Method::Extension("CONNECT".into()),
+            Method::try_from(http::Method::CONNECT).unwrap()
+        );
+        let lowercase = http::Method::from_bytes(b"get").unwrap();
+        assert!(matches!(
+            Method::try_from(lowercase),
+            Err(HttpError::Method(method)) if &*method == "get"
+        ));
+        assert_eq!(
+            http::StatusCode::IM_A_TEAPOT,
+            http::StatusCode::try_from(Status::ImATeapot).unwrap()
+        );
+        assert!(matches!(
+            http::StatusCode::try_from(Status::Custom(42)),
+            Err(HttpError::Status(42))
+        ));
+        assert_eq!(
+            Status::Custom(299),
+            Status::try_from(http::StatusCode::from_u16(299).unwrap()).unwrap()
+        );
+        assert!(matches!(
+            Status::try_from(http::StatusCode::from_u16(799).unwrap()),
+            Err(HttpError::Status(799))
+        ));
+    }
+
+    #[test]
+    fn test_headers() {
+        let headers: Headers = [("Accept", "text/html"), ("Accept", "*/*"), ("X-Id", "é")]
+            .into_iter()
+            .collect();
+        let map = http::HeaderMap::try_from(&headers).unwrap();
+
+        // Tests
+        assert_eq!(2, map.get_all("accept").iter().count());
+        assert_eq!("é".as_bytes(), map["x-id"].as_bytes());
+        assert_eq!(
+            vec![("accept", "text/html"), ("accept", "*/*"), ("x-id", "é")],
+            Headers::try_from(&map).unwrap().iter().collect::<Vec<_>>()
+        );
+        let mut invalid = Headers::new();
+        invalid.append("Bad Name", "1");
+        assert!(matches!(
+            http::HeaderMap::try_from(&invalid),
+            Err(HttpError::Header(name)) if &*name == "Bad Name"
+        ));
+        let mut binary = http::HeaderMap::new();
+        binary.insert("x-bin", http::HeaderValue::from_bytes(b"\xff").unwrap());
+        assert!(Headers::try_from(&binary).is_err());
+    }
+
+    #[test]
+    fn test_request() {
+        let req = Request::builder()
+            .method(Method::POST)
+            .path("/upload?x=1")
+            .header("Host", "example:8081")
+            .body("Hello")
+            .build()
+            .unwrap();
+        let http_req = http::Request::try_from(req.clone()).unwrap();
+
+        // Tests
+        assert_eq!(http::Method::POST, http_req.method());
+        assert_eq!("/upload?x=1", http_req.uri());
+        assert_eq!("example:8081", http_req.headers()["host"]);
+        assert_eq!(b"Hello", http_req.body().as_slice());
+        let converted = Request::try_from(http_req).unwrap();
+        assert_eq!(
+            (req.method(), req.path()),
+            (converted.method(), converted.path())
+        );
+        assert_eq!(Some("example:8081"), converted.headers().get("Host"));
+        assert_eq!(req.body(), converted.body());
+        let from_uri = http::Request::get("aethon://example/a?b").body("").unwrap();
+        let converted = Request::try_from(from_uri).unwrap();
+        assert_eq!("/a?b", converted.path());
+        assert_eq!(Some("example"), converted.headers().get("Host"));
+        let chunked = Request::builder()
+            .chunked()
+            .trailer("Checksum", "1")
+            .build()
+            .unwrap();
+        assert!(matches!(
+            http::Request::try_from(chunked),
+            Err(HttpError::Trailers)
+        ));
+        let extension = Request::new(1, Method::Extension("A B".into()), "/", Headers::new(), "");
+        assert!(matches!(
+            http::Request::try_from(extension),
+            Err(HttpError::Method(_))
+        ));
+        let spaced = Request::builder().path("/a b").build().unwrap();
+        assert!(matches!(
+            http::Request::try_from(spaced),
+            Err(HttpError::Path(_))
+        ));
+        let mismatched = http::Request::post("/")
+            .header("Content-Length", "3")
+            .body("Hello")
+            .unwrap();
+        assert!(matches!(
+            Request::try_from(mismatched),
+            Err(HttpError::Aethon(Error::BuildError(_)))
+        ));
+    }
+
+    #[test]
+    fn test_response() {
+        let res = Response::builder()
+            .status(Status::Created)
+            .header("Location", "/items/1")
+            .body("{}")
+            .build()
+            .unwrap();
+        let http_res = http::Response::try_from(res.clone()).unwrap();
+
+        // Tests
+        assert_eq!(http::StatusCode::CREATED, http_res.status());
+        assert_eq!("/items/1", http_res.headers()["location"]);
+        assert_eq!(b"{}", http_res.body().as_slice());
+        let converted = Response::try_from(http_res).unwrap();
+        assert_eq!(res.status(), converted.status());
+        assert_eq!(Some("/items/1"), converted.headers().get("Location"));
+        assert_eq!(res.body(), converted.body());
+        let unknown = http::Response::builder().status(999).body("").unwrap();
+        assert!(matches!(
+            Response::try_from(unknown),
+            Err(HttpError::Status(999))
+        ));
+        let custom = Response::new(1, Status::Custom(42), Headers::default(), "");
+        assert!(matches!(
+            http::Response::try_from(custom),
+            Err(HttpError::Status(42))
+        ));
+    }
+}
@@ -0,0 +1,160 @@
+//! JSON bodies serialized with serde, enabled by the `json` feature.
+
+use super::{
+    headers::{ContentType, Headers, TypedHeader},
+    request::{Request, RequestBuilder},
+    response::{Response, ResponseBuilder},
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt;
+
+/// Error returned when a body can't be read or written as JSON.
+#[derive(Debug)]
+pub enum JsonError {
+    /// The `Content-Type` isn't JSON, with its value if there is one.
+    WrongContentType(Option<Box<str>>),
+    /// The body doesn't match the type, or the value can't be serialized.
+    Serde(serde_json::Error),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WrongContentType(Some(content_type)) => {
+                write!(f, "Expected a JSON body, got {}", content_type)
+            }
+            Self::WrongContentType(None) => write!(f, "Expected a JSON body, got no Content-Type"),
+            Self::Serde(e) => write!(f, "Invalid JSON: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Serde(e) => Some(e),
+            Self::WrongContentType(_) => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for JsonError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Serde(e)
+    }
+}
+
+/// Deserializes `body` if `headers` say it's `application/json`, or a `+json` type like
+/// `application/problem+json`.
+fn decode<T: DeserializeOwned>(headers: &Headers, body: &[u8]) -> Result<T, JsonError> {
+    let is_json = match headers.typed_get::<ContentType>() {
+        Ok(Some(content_type)) => {
+            let media_type = content_type.media_type().to_ascii_lowercase();
+            media_type == "application/json" || media_type.ends_with("+json")
+        }
+        _ => false,
+    };
+    if !is_json {
+        let content_type = headers.get(ContentType::NAME).map(Box::from);
+        return Err(JsonError::WrongContentType(content_type));
+    }
+
+    Ok(serde_json::from_slice(body)?)
+}
+
+impl Request {
+    /// Deserializes the JSON body.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, JsonError> {
+        decode(self.headers(), self.body())
+    }
+}
+
+impl Response {
+    /// Deserializes the JSON body.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, JsonError> {
+        decode(self.headers(), self.body())
+    }
+}
+
+impl RequestBuilder {
+    /// Sets `value` serialized as JSON as the body, with its `Content-Type`.
+    pub fn json<T: Serialize + ?Sized>(self, value: &T) -> Result<Self, JsonError> {
+        let body = serde_json::to_vec(value)?;
+        Ok(self.typed_header(ContentType::json()).body(body))
+    }
+}
+
+impl ResponseBuilder {
+    /// Sets `value` serialized as JSON as the body, with its `Content-Type`.
+    pub fn json<T: Serialize + ?Sized>(self, value: &T) -> Result<Self, JsonError> {
+        let body = serde_json::to_vec(value)?;
+        Ok(self.typed_header(ContentType::json()).body(body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::collections::{BTreeMap, HashMap};
+
+    #[test]
+    fn test_round_trip() {
+        let value = json!({ "name": "apollo", "ports": [8080, 8081] });
+        let req = Request::builder().json(&value).unwrap().build().unwrap();
+        let res = Response::builder().json(&[1, 2]).unwrap().build().unwrap();
+
+        // Tests
+        assert_eq!(Some("application/json"), req.headers().get("Content-Type"));
+        assert_eq!(value, req.json::<Value>().unwrap());
+        assert_eq!(vec![1, 2], res.json::<Vec<u8>>().unwrap());
+        let problem = Response::builder()
+            .header("Content-Type", "application/problem+json; charset=utf-8")
+            .body(r#"{"status": 404}"#)
+            .build()
+            .unwrap();
+        assert_eq!(
+            BTreeMap::from([("status".to_string(), 404)]),
+            problem.json::<BTreeMap<String, u16>>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let text = Response::builder().body("[1]").build().unwrap();
+        let html = Response::builder()
+            .typed_header(ContentType::html())
+            .body("[1]")
+            .build()
+            .unwrap();
+        let invalid = Response::builder()
+            .typed_header(ContentType::json())
+            .body("[1,")
+            .build()
+            .unwrap();
+
+        // Tests
+        assert!(matches!(
+            text.json::<Value>(),
+            Err(JsonError::WrongContentType(None))
+        ));
+        assert!(matches!(
+            html.json::<Value>(),
+            Err(JsonError::WrongContentType(Some(ct))) if &*ct == "text/html; charset=utf-8"
+        ));
+        assert!(matches!(invalid.json::<Value>(), Err(JsonError::Serde(_))));
+        assert!(matches!(
+            Response::builder().json(&HashMap::from([((1, 2), 3)])),
+            Err(JsonError::Serde(_))
+        ));
+        assert!(matches!(
+            Request::builder()
+                .json(&json!(["a"]))
+                .unwrap()
+                .build()
+                .unwrap()
+                .json::<Vec<u8>>(),
+            Err(JsonError::Serde(_))
+        ));
+    }
+}
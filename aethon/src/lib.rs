@@ -1,12 +1,56 @@
+#[cfg(feature = "codec")]
+mod codec;
+#[cfg(feature = "codec")]
+pub use codec::{AethonCodec, Body, ClientCodec, CodecError, Frame, ServerCodec, StreamCodec};
+mod chunked;
+pub use chunked::{Chunk, ChunkedDecoder};
+#[cfg(feature = "net")]
+mod client;
+mod compression;
+#[cfg(feature = "net")]
+pub use client::{Cache, Client, ClientBuilder, ClientError, Resolver};
+#[cfg(feature = "net")]
+mod connection;
+mod cookie_jar;
+#[cfg(feature = "net")]
+pub use connection::{serve, ClientConnection, ServeOptions};
+pub use cookie_jar::CookieJar;
 mod error;
-pub use error::Error;
+mod form;
+pub use error::{Error, ParseError, ParseErrorKind};
+pub use form::{Form, Multipart, MultipartDecoder, MultipartEvent, Part};
 mod headers;
-pub use headers::Headers;
+pub use headers::{
+    Accept, AcceptEncoding, Authorization, ByteRange, CacheControl, Challenge, Coding, Connection,
+    ContentDisposition, ContentEncoding, ContentLength, ContentRange, ContentType, Cookie, Cookies,
+    Date, ETag, Expires, Headers, HeadersRef, Host, IfMatch, IfModifiedSince, IfNoneMatch, IfRange,
+    IfUnmodifiedSince, KeepAlive, LastModified, Location, MediaRange, Range, SetCookie,
+    TypedHeader, WwwAuthenticate,
+};
+#[cfg(feature = "http")]
+mod interop;
+#[cfg(feature = "http")]
+pub use interop::HttpError;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "json")]
+pub use json::JsonError;
 mod method;
 pub use method::Method;
+mod parser;
+pub use parser::ParseOptions;
+mod partial;
 mod request;
-pub use request::Request;
+pub use request::{Request, RequestBuilder, RequestRef};
 mod response;
-pub use response::Response;
+pub use response::{Response, ResponseBuilder, ResponseRef};
 mod status;
 pub use status::Status;
+#[cfg(feature = "tls")]
+mod tls;
+#[cfg(feature = "tls")]
+pub use tls::{TlsAcceptor, TlsAcceptorBuilder, TlsError, TlsRoots};
+mod url;
+pub use url::Url;
+mod version;
+pub use version::{Version, Versions};
@@ -3,7 +3,7 @@ use core::fmt;
 use std::str::FromStr;
 
 #[allow(clippy::upper_case_acronyms)]
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Method {
     /// The `GET` method requests some content.
     GET,
@@ -11,6 +11,58 @@ pub enum Method {
     POST,
     /// The `DELETE` method requests deletion of an item.
     DELETE,
+    /// The `PUT` method replaces an item with the body.
+    PUT,
+    /// The `PATCH` method applies a partial update to an item.
+    PATCH,
+    /// The `HEAD` method is `GET` without the response body.
+    HEAD,
+    /// The `OPTIONS` method asks what the server supports.
+    OPTIONS,
+    /// Any other method, made of uppercase letters, digits, `-` and `_`.
+    Extension(Box<str>),
+}
+
+impl Method {
+    /// Returns the method as it's written in a request.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::GET => "GET",
+            Self::POST => "POST",
+            Self::DELETE => "DELETE",
+            Self::PUT => "PUT",
+            Self::PATCH => "PATCH",
+            Self::HEAD => "HEAD",
+            Self::OPTIONS => "OPTIONS",
+            Self::Extension(m) => m,
+        }
+    }
+
+    /// Safe methods don't change anything on the server.
+    pub fn is_safe(&self) -> bool {
+        matches!(self, Self::GET | Self::HEAD | Self::OPTIONS)
+    }
+
+    /// Sending an idempotent request many times has the same effect as sending it once,
+    /// so it can be retried.
+    pub fn is_idempotent(&self) -> bool {
+        self.is_safe() || matches!(self, Self::PUT | Self::DELETE)
+    }
+
+    /// Whether a response to this method can carry a body.
+    pub fn allows_response_body(&self) -> bool {
+        *self != Self::HEAD
+    }
+
+    /// Whether `s` can be an extension method, the names of built-in methods can't since they'd
+    /// be parsed back as those.
+    pub(crate) fn is_valid_extension(s: &str) -> bool {
+        const BUILT_IN: [&str; 7] = ["GET", "POST", "DELETE", "PUT", "PATCH", "HEAD", "OPTIONS"];
+        !s.is_empty()
+            && !BUILT_IN.contains(&s)
+            && s.bytes()
+                .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'-' || b == b'_')
+    }
 }
 
 /// Used for parsing.
@@ -21,6 +73,11 @@ impl FromStr for Method {
             "GET" => Ok(Self::GET),
             "POST" => Ok(Self::POST),
             "DELETE" => Ok(Self::DELETE),
+            "PUT" => Ok(Self::PUT),
+            "PATCH" => Ok(Self::PATCH),
+            "HEAD" => Ok(Self::HEAD),
+            "OPTIONS" => Ok(Self::OPTIONS),
+            s if Self::is_valid_extension(s) => Ok(Self::Extension(s.into())),
             _ => Err(self::Error::WrongMethod),
         }
     }
@@ -28,11 +85,7 @@ impl FromStr for Method {
 
 impl fmt::Display for Method {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::GET => write!(f, "GET"),
-            Self::POST => write!(f, "POST"),
-            Self::DELETE => write!(f, "DELETE"),
-        }
+        write!(f, "{}", self.as_str())
     }
 }
 
@@ -67,4 +120,42 @@ mod tests {
         // Tests
         assert_eq!(res, expected)
     }
+
+    #[test]
+    fn test_new_methods_round_trip() {
+        let s = ["PUT", "PATCH", "HEAD", "OPTIONS", "PURGE", "M-SEARCH"];
+        let res: Vec<String> = s
+            .iter()
+            .map(|x| x.parse::<Method>().unwrap().to_string())
+            .collect();
+
+        // Tests
+        assert_eq!(s.to_vec(), res);
+        assert_eq!(Ok(Method::Extension("PURGE".into())), "PURGE".parse());
+    }
+
+    #[test]
+    fn test_invalid_extension_methods() {
+        let s = ["", "get", "GE T", "GET\n", "ÜBER"];
+
+        // Tests
+        for m in s {
+            assert_eq!(Err(Error::WrongMethod), m.parse::<Method>());
+        }
+    }
+
+    #[test]
+    fn test_method_properties() {
+        // Tests
+        assert!(Method::GET.is_safe());
+        assert!(Method::HEAD.is_safe());
+        assert!(Method::OPTIONS.is_safe());
+        assert!(!Method::PUT.is_safe());
+        assert!(Method::PUT.is_idempotent());
+        assert!(Method::DELETE.is_idempotent());
+        assert!(!Method::POST.is_idempotent());
+        assert!(!Method::PATCH.is_idempotent());
+        assert!(!Method::Extension("PURGE".into()).is_idempotent());
+        assert!(!Method::HEAD.allows_response_body());
+    }
 }
@@ -0,0 +1,303 @@
+use super::{
+    chunked,
+    error::{ParseError, ParseErrorKind},
+    headers::HeadersRef,
+    ChunkedDecoder, Error,
+};
+use std::borrow::Cow;
+
+/// How strictly packets are parsed and how large they may be.
+///
+/// Lenient parsing, the default, accepts common deviations from the spec: `\r\n` line endings,
+/// whitespace at the end of lines and around header names, runs of spaces in the start line and
+/// reason phrases after a status. Strict parsing rejects all of them.
+///
+/// Limits protect against hostile peers, packets exceeding them are rejected as soon as it's
+/// noticed, without waiting for the rest of them. By default lines are limited to 8 KiB, paths
+/// to 4 KiB, packets to 100 headers and bodies aren't limited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    strict: bool,
+    pub(crate) max_line_len: usize,
+    pub(crate) max_headers: usize,
+    pub(crate) max_path_len: usize,
+    pub(crate) max_body_len: usize,
+}
+
+impl ParseOptions {
+    /// Rejects anything off-spec.
+    pub const fn strict() -> Self {
+        Self {
+            strict: true,
+            ..Self::lenient()
+        }
+    }
+
+    /// Accepts common deviations from the spec.
+    pub const fn lenient() -> Self {
+        Self {
+            strict: false,
+            max_line_len: 8 * 1024,
+            max_headers: 100,
+            max_path_len: 4 * 1024,
+            max_body_len: usize::MAX,
+        }
+    }
+
+    /// Limits the start line and every header line, without their line endings.
+    pub const fn with_max_line_len(mut self, len: usize) -> Self {
+        self.max_line_len = len;
+        self
+    }
+
+    /// Limits the number of headers, and of trailers.
+    pub const fn with_max_headers(mut self, count: usize) -> Self {
+        self.max_headers = count;
+        self
+    }
+
+    /// Limits the path of requests.
+    pub const fn with_max_path_len(mut self, len: usize) -> Self {
+        self.max_path_len = len;
+        self
+    }
+
+    /// Limits the body, once decoded when it's chunked.
+    pub const fn with_max_body_len(mut self, len: usize) -> Self {
+        self.max_body_len = len;
+        self
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Removes the `\r` ending `line` when lenient, strict parsing rejects any `\r`.
+    pub(crate) fn strip_cr<'a>(&self, line: &'a str) -> Result<&'a str, ParseError> {
+        let line = match self.strict {
+            true => line,
+            false => line.strip_suffix('\r').unwrap_or(line),
+        };
+        match line.find('\r') {
+            Some(i) => Err(ParseError::at(
+                ParseErrorKind::CarriageReturn,
+                line.as_bytes(),
+                i,
+            )),
+            None => Ok(line),
+        }
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self::lenient()
+    }
+}
+
+/// Checks the lines of a header block against the limits while it arrives.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct HeadLimits {
+    /// Offset of the first line that isn't complete yet.
+    line_start: usize,
+    headers: usize,
+}
+
+impl HeadLimits {
+    /// Checks the lines of `block` after the ones seen by previous calls, errors are positioned
+    /// in `block`. The block must only grow between calls.
+    pub fn check(&mut self, block: &[u8], options: &ParseOptions) -> Result<(), ParseError> {
+        while let Some(i) = block[self.line_start..].iter().position(|&b| b == b'\n') {
+            if i > options.max_line_len {
+                let kind = ParseErrorKind::HeaderTooLarge;
+                return Err(ParseError::at(kind, block, self.line_start));
+            }
+            if i > 0 {
+                self.headers += 1;
+            }
+            if self.headers > options.max_headers {
+                let kind = ParseErrorKind::TooManyHeaders;
+                return Err(ParseError::at(kind, block, self.line_start));
+            }
+            self.line_start += i + 1;
+        }
+
+        match block.len() - self.line_start > options.max_line_len {
+            true => Err(ParseError::at(
+                ParseErrorKind::HeaderTooLarge,
+                block,
+                self.line_start,
+            )),
+            false => Ok(()),
+        }
+    }
+}
+
+/// A packet split into its parts, before any of them is interpreted.
+pub(crate) struct RawPacket<'a> {
+    buf: &'a [u8],
+    pub start_line: &'a str,
+    pub headers: &'a str,
+    headers_offset: usize,
+    pub rest: &'a [u8],
+    rest_offset: usize,
+}
+
+impl<'a> RawPacket<'a> {
+    /// Parses the header block.
+    pub fn parse_headers(&self, options: &ParseOptions) -> Result<HeadersRef<'a>, Error> {
+        HeadersRef::parse(self.headers, options).map_err(|e| self.headers_error(e))
+    }
+
+    /// Returns how the body is framed by `headers`, parsed from this packet.
+    pub fn framing(
+        &self, headers: &HeadersRef<'a>, options: &ParseOptions,
+    ) -> Result<Framing, Error> {
+        headers.framing(options).map_err(|e| self.headers_error(e))
+    }
+
+    /// Cuts the body out of the rest of the packet.
+    pub fn body(&self, framing: Framing, options: &ParseOptions) -> Result<Body<'a>, Error> {
+        body(self.rest, framing, options).map_err(|e| e.shift(self.buf, self.rest_offset).into())
+    }
+
+    fn headers_error(&self, e: ParseError) -> Error {
+        e.shift(self.buf, self.headers_offset).into()
+    }
+}
+
+/// Splits `buf` into the start line, the header block and the rest of the packet.
+///
+/// The start line ends with the first `\n`, the header block ends with the first empty line after
+/// it. Missing terminators are tolerated, the packet is then simply cut short.
+pub(crate) fn split<'a>(buf: &'a [u8], options: &ParseOptions) -> Result<RawPacket<'a>, Error> {
+    let (start_line, headers_offset) = match buf.iter().position(|&b| b == b'\n') {
+        Some(i) => (&buf[..i], i + 1),
+        None => (buf, buf.len()),
+    };
+    let after = &buf[headers_offset..];
+    let (headers, rest_offset) = match find_head_end(after, options) {
+        Some((i, len)) => (&after[..i], headers_offset + i + len),
+        None => (after, buf.len()),
+    };
+
+    let utf8 = |bytes: &'a [u8], base: usize| {
+        std::str::from_utf8(bytes)
+            .map_err(|e| ParseError::at(ParseErrorKind::InvalidUtf8, buf, base + e.valid_up_to()))
+    };
+    if start_line.len() > options.max_line_len {
+        return Err(ParseError::at(ParseErrorKind::HeaderTooLarge, buf, 0).into());
+    }
+    let start_line = utf8(start_line, 0)?;
+    Ok(RawPacket {
+        buf,
+        start_line: options.strip_cr(start_line)?,
+        headers: utf8(headers, headers_offset)?,
+        headers_offset,
+        rest: &buf[rest_offset..],
+        rest_offset,
+    })
+}
+
+/// Returns the index of the empty line ending a head in `buf` and the length of its terminator.
+///
+/// It's the first `\n\n`, or `\n\r\n` when lenient.
+pub(crate) fn find_head_end(buf: &[u8], options: &ParseOptions) -> Option<(usize, usize)> {
+    let mut start = 0;
+    while let Some(i) = buf[start..].iter().position(|&b| b == b'\n') {
+        let i = start + i;
+        match &buf[i + 1..] {
+            [b'\n', ..] => return Some((i, 2)),
+            [b'\r', b'\n', ..] if !options.strict => return Some((i, 3)),
+            _ => start = i + 1,
+        }
+    }
+    None
+}
+
+/// Returns the offset of `part` in `whole`, `part` has to be a slice of `whole`.
+pub(crate) fn offset_in(whole: &str, part: &str) -> usize {
+    part.as_ptr() as usize - whole.as_ptr() as usize
+}
+
+/// How the end of a body is found.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Framing {
+    /// `Content-Length` bytes.
+    Length(usize),
+    /// `Transfer-Encoding: chunked`.
+    Chunked,
+    /// Neither header was sent, the body is the rest of the packet.
+    Rest,
+}
+
+/// A body cut out of a packet.
+pub(crate) struct Body<'a> {
+    pub data: Cow<'a, [u8]>,
+    pub trailers: HeadersRef<'a>,
+}
+
+/// Cuts the body out of `rest` according to `framing`.
+fn body<'a>(
+    rest: &'a [u8], framing: Framing, options: &ParseOptions,
+) -> Result<Body<'a>, ParseError> {
+    let incomplete = || ParseError::at(ParseErrorKind::IncompleteBody, rest, rest.len());
+    let data = match framing {
+        Framing::Length(n) if n > rest.len() => return Err(incomplete()),
+        Framing::Length(n) => &rest[..n],
+        Framing::Rest if rest.len() > options.max_body_len => {
+            let kind = ParseErrorKind::BodyTooLarge;
+            return Err(ParseError::at(kind, rest, options.max_body_len));
+        }
+        Framing::Rest => rest,
+        Framing::Chunked => {
+            let decoder = ChunkedDecoder::with_options(*options);
+            let decoded = chunked::decode(rest, decoder)?.ok_or_else(incomplete)?;
+            return Ok(Body {
+                data: Cow::Owned(decoded.body),
+                trailers: decoded.trailers,
+            });
+        }
+    };
+
+    Ok(Body {
+        data: Cow::Borrowed(data),
+        trailers: HeadersRef::default(),
+    })
+}
+
+/// Splits a start line into `N` fields separated by spaces, the last one takes the rest of it.
+///
+/// Missing fields are empty, at the end of the line. Lenient parsing trims the line and accepts
+/// runs of whitespace between fields.
+pub(crate) fn start_line_fields<'a, const N: usize>(
+    line: &'a str, options: &ParseOptions,
+) -> Result<[&'a str; N], ParseError> {
+    let mut rest = match options.strict {
+        true => line,
+        false => line.trim(),
+    };
+    if rest.len() != rest.trim_end().len() {
+        let offset = rest.trim_end().len();
+        return Err(ParseError::at(
+            ParseErrorKind::Whitespace,
+            line.as_bytes(),
+            offset,
+        ));
+    }
+
+    let mut fields = [&line[line.len()..]; N];
+    for field in &mut fields[..N - 1] {
+        let split = match options.strict {
+            true => rest.split_once(' '),
+            false => rest.split_once(char::is_whitespace),
+        };
+        (*field, rest) = match split {
+            Some((field, rest)) if options.strict => (field, rest),
+            Some((field, rest)) => (field, rest.trim_start()),
+            None => (rest, &rest[rest.len()..]),
+        };
+    }
+    fields[N - 1] = rest;
+    Ok(fields)
+}
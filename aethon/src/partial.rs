@@ -0,0 +1,241 @@
+//! Answers to `Range` requests, `206 Partial Content` with one or many parts.
+
+use super::{
+    form,
+    headers::{
+        ContentRange, ContentType, ETag, Headers, IfRange, LastModified, Range, TypedHeader,
+    },
+    method::Method,
+    request::Request,
+    response::Response,
+    status::Status,
+};
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    ops,
+    path::Path,
+};
+
+/// Value of `Accept-Ranges` for responses that can be ranged.
+const ACCEPT_RANGES: (&str, &str) = ("Accept-Ranges", "bytes");
+
+/// What a request gets of a `len` long body.
+enum Plan {
+    Full,
+    Parts(Vec<ops::Range<u64>>),
+    Unsatisfiable,
+}
+
+impl Plan {
+    /// Only `GET` is ranged, malformed `Range` and `If-Range` headers are ignored.
+    fn new(req: &Request, headers: &Headers, len: u64) -> Self {
+        if *req.method() != Method::GET {
+            return Plan::Full;
+        }
+        let Ok(Some(range)) = req.headers().typed_get::<Range>() else {
+            return Plan::Full;
+        };
+        if req.headers().contains(IfRange::NAME) {
+            let etag = headers.typed_get::<ETag>().ok().flatten();
+            let last_modified = headers.typed_get::<LastModified>().ok().flatten();
+            match req.headers().typed_get::<IfRange>() {
+                Ok(Some(if_range))
+                    if if_range.matches(etag.as_ref(), last_modified.map(|lm| lm.0)) => {}
+                _ => return Plan::Full,
+            }
+        }
+
+        match range.resolve(len) {
+            ranges if ranges.is_empty() => Plan::Unsatisfiable,
+            ranges => Plan::Parts(ranges),
+        }
+    }
+}
+
+impl Response {
+    /// Answers `req` with the parts of this response it asks for in its `Range` header.
+    ///
+    /// A `200` becomes a `206 Partial Content` with a `Content-Range` for one range, or a
+    /// `multipart/byteranges` body for many, and a `416 Range Not Satisfiable` if no range
+    /// overlaps the body. `If-Range` is checked against the `ETag` and `Last-Modified` headers.
+    /// Other statuses and chunked responses are returned as they are.
+    pub fn ranged(mut self, req: &Request) -> Response {
+        if self.status() != Status::OK || self.headers().is_chunked() {
+            return self;
+        }
+        self.headers_mut().insert(ACCEPT_RANGES.0, ACCEPT_RANGES.1);
+
+        let len = self.body().len() as u64;
+        match Plan::new(req, self.headers(), len) {
+            Plan::Full => self,
+            plan => {
+                let body = self.body();
+                let read = |range: ops::Range<u64>| {
+                    Ok(body[range.start as usize..range.end as usize].to_vec())
+                };
+                // Slicing the body can't fail
+                let (status, headers, body) =
+                    partial(plan, self.headers().clone(), len, read).unwrap();
+                Response::new(self.version(), status, headers, body)
+            }
+        }
+    }
+
+    /// Answers `req` with the file at `path`, only the ranges it asks for are read.
+    ///
+    /// The response has the file's `Last-Modified` date and is ranged like
+    /// [`Response::ranged`].
+    pub fn from_file(
+        req: &Request, path: impl AsRef<Path>, content_type: ContentType,
+    ) -> io::Result<Response> {
+        let mut file = File::open(path)?;
+        let metadata = file.metadata()?;
+        let len = metadata.len();
+
+        let mut headers = Headers::new();
+        headers.typed_insert(content_type);
+        if let Ok(modified) = metadata.modified() {
+            headers.typed_insert(LastModified(modified));
+        }
+        headers.insert(ACCEPT_RANGES.0, ACCEPT_RANGES.1);
+
+        let (status, headers, body) = match Plan::new(req, &headers, len) {
+            Plan::Full => {
+                let mut body = Vec::with_capacity(len as usize);
+                file.read_to_end(&mut body)?;
+                (Status::OK, headers, body)
+            }
+            plan => {
+                let read = |range: ops::Range<u64>| {
+                    let mut part = vec![0; (range.end - range.start) as usize];
+                    file.seek(SeekFrom::Start(range.start))?;
+                    file.read_exact(&mut part)?;
+                    Ok(part)
+                };
+                partial(plan, headers, len, read)?
+            }
+        };
+        Ok(Response::new(req.version(), status, headers, body))
+    }
+}
+
+/// Builds the status, headers and body of a `206` or a `416`, `read` returns the bytes of a range.
+fn partial(
+    plan: Plan, mut headers: Headers, len: u64,
+    mut read: impl FnMut(ops::Range<u64>) -> io::Result<Vec<u8>>,
+) -> io::Result<(Status, Headers, Vec<u8>)> {
+    headers.remove(Headers::CONTENT_LENGTH);
+
+    let ranges = match plan {
+        Plan::Parts(ranges) => ranges,
+        _ => {
+            headers.remove(ContentType::NAME);
+            headers.typed_insert(ContentRange::unsatisfied(len));
+            return Ok((Status::RangeNotSatisfiable, headers, Vec::new()));
+        }
+    };
+
+    if let [range] = ranges.as_slice() {
+        headers.typed_insert(ContentRange::new(range.clone(), len));
+        return Ok((Status::PartialContent, headers, read(range.clone())?));
+    }
+
+    // Each part repeats the content type of the whole body
+    let content_type = headers.remove(ContentType::NAME);
+    let boundary = form::boundary();
+    let mut body = Vec::new();
+    for range in ranges {
+        body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+        if let Some(content_type) = &content_type {
+            body.extend_from_slice(
+                format!("{}: {}\r\n", ContentType::NAME, content_type).as_bytes(),
+            );
+        }
+        let content_range = ContentRange::new(range.clone(), len);
+        body.extend_from_slice(
+            format!("{}: {}\r\n\r\n", ContentRange::NAME, content_range.encode()).as_bytes(),
+        );
+        body.extend_from_slice(&read(range)?);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+
+    headers.typed_insert(ContentType::new("multipart/byteranges").with_param("boundary", boundary));
+    Ok((Status::PartialContent, headers, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get(range: &str) -> Request {
+        Request::builder().header("Range", range).build().unwrap()
+    }
+
+    #[test]
+    fn test_ranged() {
+        let res = Response::builder()
+            .typed_header(ContentType::text())
+            .typed_header(ETag::strong("v1"))
+            .body("0123456789")
+            .build()
+            .unwrap();
+        let multi = res.clone().ranged(&get("bytes=-2, 0-1, 1-3"));
+        let content_type: ContentType = multi.headers().typed_get().unwrap().unwrap();
+        let boundary = content_type.param("boundary").unwrap();
+        let stale = Request::builder()
+            .header("Range", "bytes=0-1")
+            .header("If-Range", "\"v0\"")
+            .build()
+            .unwrap();
+
+        // Tests
+        let single = res.clone().ranged(&get("bytes=2-4"));
+        assert_eq!(Status::PartialContent, single.status());
+        assert_eq!(Some("bytes 2-4/10"), single.headers().get("Content-Range"));
+        assert_eq!(Some("bytes"), single.headers().get("Accept-Ranges"));
+        assert_eq!(b"234", single.body());
+
+        assert_eq!(Status::PartialContent, multi.status());
+        assert_eq!("multipart/byteranges", content_type.media_type());
+        assert_eq!(
+            format!(
+                "--{b}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Range: bytes 0-3/10\r\n\r\n\
+                 0123\r\n--{b}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Range: bytes \
+                 8-9/10\r\n\r\n89\r\n--{b}--\r\n",
+                b = boundary
+            ),
+            multi.text().unwrap()
+        );
+
+        let unsatisfiable = res.clone().ranged(&get("bytes=10-"));
+        assert_eq!(Status::RangeNotSatisfiable, unsatisfiable.status());
+        assert_eq!(
+            Some("bytes */10"),
+            unsatisfiable.headers().get("Content-Range")
+        );
+        assert!(unsatisfiable.body().is_empty());
+
+        assert_eq!(Status::OK, res.clone().ranged(&stale).status());
+        assert_eq!(Status::OK, res.clone().ranged(&get("lines=1-2")).status());
+        assert_eq!(b"0123456789", res.ranged(&get("bytes=0-")).body());
+    }
+
+    #[test]
+    fn test_from_file() {
+        let path = std::env::temp_dir().join(format!("aethon-range-{}", std::process::id()));
+        std::fs::write(&path, "Hello, world!").unwrap();
+        let part = Response::from_file(&get("bytes=7-11"), &path, ContentType::text()).unwrap();
+        let full = Response::from_file(&get("bytes=x"), &path, ContentType::text()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // Tests
+        assert_eq!(Status::PartialContent, part.status());
+        assert_eq!(b"world", part.body());
+        assert_eq!(Some("bytes 7-11/13"), part.headers().get("Content-Range"));
+        assert!(part.headers().contains("Last-Modified"));
+        assert_eq!(Status::OK, full.status());
+        assert_eq!(b"Hello, world!", full.body());
+    }
+}
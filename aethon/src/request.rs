@@ -1,67 +1,225 @@
-use super::{headers::Headers, method::Method, Error};
+use super::{
+    chunked,
+    error::{ParseError, ParseErrorKind},
+    headers::{
+        Connection, ETag, Headers, HeadersRef, Host, IfMatch, IfModifiedSince, IfNoneMatch,
+        IfUnmodifiedSince, TypedHeader,
+    },
+    method::Method,
+    parser::{self, ParseOptions},
+    status::Status,
+    url::Url,
+    version::Version,
+    Error,
+};
 use std::{
-    fmt::Display,
-    iter::Peekable,
-    str::{Chars, FromStr},
+    borrow::Cow,
+    fmt::{self, Display},
+    str::{FromStr, Utf8Error},
+    time::SystemTime,
 };
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Request {
-    version: u8,
+    version: Version,
     method: Method,
     path: String,
     headers: Headers,
-    body: String,
+    body: Vec<u8>,
+    trailers: Headers,
 }
 
 impl Request {
     pub fn new(
-        version: u8, method: Method, path: impl Into<String>, headers: Headers,
-        body: impl Into<String>,
+        version: impl Into<Version>, method: Method, path: impl Into<String>, headers: Headers,
+        body: impl Into<Vec<u8>>,
     ) -> Self {
         Self {
-            version,
+            version: version.into(),
             method,
             path: path.into(),
             headers,
             body: body.into(),
+            trailers: Headers::default(),
         }
     }
 
-    fn parse(s: &str) -> Result<Self, Error> {
-        let mut buffer = s.chars().peekable();
+    /// Starts building a `GET /` request.
+    pub fn builder() -> RequestBuilder {
+        RequestBuilder::default()
+    }
 
-        let version: u8 = Self::consume_string(&mut buffer, ' ')
-            .parse()
-            .map_err(|_| Error::ParseError("Version ins't an u8"))?;
-        let method: Method = Self::consume_string(&mut buffer, ' ')
-            .parse()
-            .map_err(|_| Error::ParseError("Invalid method"))?;
-        let path: String = Self::consume_string(&mut buffer, '\n');
-        let headers = Headers::parser_headers(&mut buffer)?;
-        let body = buffer.collect();
+    pub fn version(&self) -> Version {
+        self.version
+    }
 
-        Ok(Request {
-            version,
-            method,
-            path,
-            headers,
-            body,
-        })
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the `aethon://` URL the request is meant for, made of its `Host` header and path.
+    pub fn url(&self) -> Result<Url, Error> {
+        self.url_with_scheme("aethon")
+    }
+
+    /// Returns the URL the request is meant for when sent with `scheme`.
+    pub(crate) fn url_with_scheme(&self, scheme: &str) -> Result<Url, Error> {
+        let host = self
+            .headers
+            .typed_get::<Host>()?
+            .ok_or(Error::InvalidUrl("The request has no Host header"))?;
+        if !self.path.starts_with('/') {
+            return Err(Error::InvalidUrl("The path isn't absolute"));
+        }
+        Url::parse(&format!("{}://{}{}", scheme, host.encode(), self.path))
+    }
+
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    pub fn headers_mut(&mut self) -> &mut Headers {
+        &mut self.headers
+    }
+
+    /// Returns the raw body.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    pub fn body_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.body
+    }
+
+    /// Returns the trailers sent after a chunked body.
+    pub fn trailers(&self) -> &Headers {
+        &self.trailers
+    }
+
+    /// Trailers are only sent with `Transfer-Encoding: chunked`.
+    pub fn trailers_mut(&mut self) -> &mut Headers {
+        &mut self.trailers
+    }
+
+    /// Returns the body as text, fails if it isn't valid UTF-8.
+    pub fn text(&self) -> Result<&str, Utf8Error> {
+        std::str::from_utf8(&self.body)
+    }
+
+    /// Returns the body as text, invalid UTF-8 sequences are replaced with `U+FFFD`.
+    pub fn text_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.body)
+    }
+
+    /// Consumes the request, returning its body.
+    pub fn into_body(self) -> Vec<u8> {
+        self.body
     }
 
-    fn consume_string(buffer: &mut Peekable<Chars>, to: char) -> String {
-        let mut s = String::new();
+    /// Whether the client keeps the connection open afterwards, it does unless it sent
+    /// `Connection: close`.
+    pub fn keep_alive(&self) -> bool {
+        matches!(
+            self.headers.typed_get::<Connection>(),
+            Ok(None | Some(Connection::KeepAlive))
+        )
+    }
 
-        for ch in buffer {
-            if ch == to {
-                break;
+    /// Evaluates the conditional headers against the current version of the content.
+    ///
+    /// Returns the status to answer with instead of handling the request, `304 Not Modified`
+    /// when a `GET` or `HEAD` client already has the current version or `412 Precondition
+    /// Failed`, or `None` to handle it. Malformed conditions are ignored.
+    pub fn preconditions(
+        &self, etag: Option<&ETag>, last_modified: Option<SystemTime>,
+    ) -> Option<Status> {
+        // Times only have a precision of one second on the wire
+        let modified_after = |since: SystemTime| {
+            last_modified.is_some_and(|lm| lm.duration_since(since).is_ok_and(|d| d.as_secs() > 0))
+        };
+
+        match self.headers.typed_get::<IfMatch>() {
+            Ok(Some(if_match)) if !if_match.matches(etag) => {
+                return Some(Status::PreconditionFailed)
+            }
+            Ok(Some(_)) => {}
+            _ => {
+                // Ignored when the content has no modification date
+                if let Ok(Some(IfUnmodifiedSince(since))) = self.headers.typed_get() {
+                    if modified_after(since) {
+                        return Some(Status::PreconditionFailed);
+                    }
+                }
             }
+        }
+
+        let get = matches!(self.method, Method::GET | Method::HEAD);
+        if self.headers.contains(IfNoneMatch::NAME) {
+            return match self.headers.typed_get::<IfNoneMatch>() {
+                Ok(Some(inm)) if inm.matches(etag) && get => Some(Status::NotModified),
+                Ok(Some(inm)) if inm.matches(etag) => Some(Status::PreconditionFailed),
+                _ => None,
+            };
+        }
+        match self.headers.typed_get::<IfModifiedSince>() {
+            Ok(Some(IfModifiedSince(since)))
+                if get && last_modified.is_some() && !modified_after(since) =>
+            {
+                Some(Status::NotModified)
+            }
+            _ => None,
+        }
+    }
 
-            s.push(ch);
+    /// Serializes the request into its wire format.
+    ///
+    /// With `Transfer-Encoding: chunked` the body is written as a single chunk.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = self.head_bytes();
+        match self.headers.is_chunked() {
+            true => chunked::encode(&self.body, &self.trailers, &mut buffer),
+            false => buffer.extend_from_slice(&self.body),
         }
+        buffer
+    }
+
+    /// Serializes the start line and the headers.
+    pub(crate) fn head_bytes(&self) -> Vec<u8> {
+        let mut head = String::new();
+        // Writing into a String can't fail
+        let _ = self.write_head(&mut head);
+        head.into_bytes()
+    }
+
+    pub(crate) fn parse(buf: &[u8]) -> Result<Self, Error> {
+        Self::parse_with(buf, &ParseOptions::default())
+    }
+
+    /// Parses a request as strictly as `options` say.
+    pub fn parse_with(buf: &[u8], options: &ParseOptions) -> Result<Self, Error> {
+        RequestRef::parse_with(buf, options).map(|req| req.to_owned())
+    }
 
-        s
+    /// Parses the head of a packet, the body is left empty.
+    #[cfg(feature = "codec")]
+    pub(crate) fn parse_head(head: &[u8], options: &ParseOptions) -> Result<Self, Error> {
+        let packet = parser::split(head, options)?;
+        RequestRef::from_head(&packet, options).map(|req| req.to_owned())
+    }
+
+    fn write_head(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        // Version
+        write!(f, "{} ", self.version)?;
+        // Method
+        write!(f, "{} ", self.method)?;
+        // Path
+        writeln!(f, "{}", self.path)?;
+        // Headers
+        self.headers.write_block(f, self.body.len())
     }
 }
 
@@ -69,33 +227,256 @@ impl Request {
 impl FromStr for Request {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Request::parse(s)
+        Request::parse(s.as_bytes())
     }
 }
 
 impl TryFrom<&[u8]> for Request {
     type Error = Error;
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        let buffer = String::from_utf8_lossy(value);
-        Request::parse(&buffer)
+        Request::parse(value)
     }
 }
 
+/// The packet is written lossily, use [`Request::to_bytes`] for binary bodies.
 impl Display for Request {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        // Version
-        // (1-9) + 48 = ASCII representation
-        write!(f, "{} ", (self.version + 48) as char)?;
-        // Method
-        write!(f, "{} ", self.method)?;
-        // Path
-        writeln!(f, "{}", self.path)?;
-        // Headers
-        writeln!(f, "{}", self.headers)?;
-        // Body
-        write!(f, "{}", self.body)?;
+        write!(f, "{}", String::from_utf8_lossy(&self.to_bytes()))
+    }
+}
+
+/// Builds a [`Request`], checking that it can be written and parsed back.
+///
+/// ```
+/// use aethon::{Method, Request};
+///
+/// let req = Request::builder()
+///     .method(Method::POST)
+///     .path("/upload")
+///     .header("Content-Type", "text/plain")
+///     .body("Hello World")
+///     .build()
+///     .unwrap();
+/// assert_eq!("/upload", req.path());
+/// ```
+#[derive(Debug)]
+pub struct RequestBuilder {
+    version: Version,
+    method: Method,
+    path: String,
+    headers: Headers,
+    body: Vec<u8>,
+    trailers: Headers,
+}
+
+impl RequestBuilder {
+    pub fn version(mut self, version: impl Into<Version>) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    pub fn method(mut self, method: Method) -> Self {
+        self.method = method;
+        self
+    }
+
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Sets the path and the `Host` header from `url`, its fragment isn't sent.
+    pub fn url(mut self, url: &Url) -> Self {
+        self.path = url.path_and_query();
+        self.headers.typed_insert(url.host_header());
+        self
+    }
+
+    /// Adds a header, repeated calls with the same name add more values.
+    pub fn header(mut self, key: impl Into<Box<str>>, value: impl Into<Box<str>>) -> Self {
+        self.headers.append(key, value);
+        self
+    }
+
+    /// Sets a typed header, replacing its previous values.
+    pub fn typed_header<H: TypedHeader>(mut self, header: H) -> Self {
+        self.headers.typed_insert(header);
+        self
+    }
+
+    /// Replaces all headers.
+    pub fn headers(mut self, headers: Headers) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Sends the body with chunked transfer encoding.
+    pub fn chunked(mut self) -> Self {
+        self.headers
+            .insert(Headers::TRANSFER_ENCODING, chunked::CHUNKED);
+        self
+    }
+
+    /// Adds a trailer, they need [`RequestBuilder::chunked`].
+    pub fn trailer(mut self, key: impl Into<Box<str>>, value: impl Into<Box<str>>) -> Self {
+        self.trailers.append(key, value);
+        self
+    }
+
+    pub fn build(self) -> Result<Request, Error> {
+        if self.version.get() == 0 {
+            return Err(Error::WrongVersion);
+        }
+        if let Method::Extension(method) = &self.method {
+            if !Method::is_valid_extension(method) {
+                return Err(Error::WrongMethod);
+            }
+        }
+        if self.path.is_empty() {
+            return Err(Error::BuildError("Path can't be empty"));
+        }
+        if self.path.contains(char::is_control) {
+            return Err(Error::BuildError("Path can't contain control characters"));
+        }
+        self.headers.validate(self.body.len())?;
+        if !self.trailers.is_empty() && !self.headers.is_chunked() {
+            return Err(Error::BuildError("Trailers need chunked transfer encoding"));
+        }
+        self.trailers.validate(0)?;
+
+        Ok(Request {
+            version: self.version,
+            method: self.method,
+            path: self.path,
+            headers: self.headers,
+            body: self.body,
+            trailers: self.trailers,
+        })
+    }
+}
+
+impl Default for RequestBuilder {
+    fn default() -> Self {
+        Self {
+            version: Version::V1,
+            method: Method::GET,
+            path: "/".to_string(),
+            headers: Headers::default(),
+            body: Vec::new(),
+            trailers: Headers::default(),
+        }
+    }
+}
+
+/// Borrowed view of a request, parsed without copying out of the input buffer.
+///
+/// Use [`RequestRef::to_owned`] to turn it into a [`Request`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestRef<'a> {
+    version: Version,
+    method: Method,
+    path: &'a str,
+    headers: HeadersRef<'a>,
+    body: Cow<'a, [u8]>,
+    trailers: HeadersRef<'a>,
+}
+
+impl<'a> RequestRef<'a> {
+    pub fn parse(buf: &'a [u8]) -> Result<Self, Error> {
+        Self::parse_with(buf, &ParseOptions::default())
+    }
+
+    /// Parses a request as strictly as `options` say.
+    pub fn parse_with(buf: &'a [u8], options: &ParseOptions) -> Result<Self, Error> {
+        let packet = parser::split(buf, options)?;
+        let mut req = Self::from_head(&packet, options)?;
+        let body = packet.body(packet.framing(&req.headers, options)?, options)?;
+        req.body = body.data;
+        req.trailers = body.trailers;
+        Ok(req)
+    }
+
+    /// Parses the start line and the headers, the body is left empty.
+    pub(crate) fn from_head(
+        packet: &parser::RawPacket<'a>, options: &ParseOptions,
+    ) -> Result<Self, Error> {
+        let line = packet.start_line;
+        let error = |kind, field: &str| {
+            ParseError::at(kind, line.as_bytes(), parser::offset_in(line, field))
+        };
+        let [version, method, path] = parser::start_line_fields(line, options)?;
+
+        let version: Version = version
+            .parse()
+            .map_err(|_| error(ParseErrorKind::InvalidVersion, version))?;
+        let method: Method = method
+            .parse()
+            .map_err(|_| error(ParseErrorKind::InvalidMethod, method))?;
+        if options.is_strict() && (path.is_empty() || path.contains(char::is_whitespace)) {
+            return Err(error(ParseErrorKind::InvalidPath, path).into());
+        }
+        if path.len() > options.max_path_len {
+            return Err(error(ParseErrorKind::PathTooLong, path).into());
+        }
+        let headers = packet.parse_headers(options)?;
+
+        Ok(RequestRef {
+            version,
+            method,
+            path,
+            headers,
+            body: Cow::Borrowed(&[]),
+            trailers: HeadersRef::default(),
+        })
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
 
-        Ok(())
+    pub fn path(&self) -> &'a str {
+        self.path
+    }
+
+    pub fn headers(&self) -> HeadersRef<'a> {
+        self.headers
+    }
+
+    /// Returns the body, it's only copied if it was chunked.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    pub fn trailers(&self) -> HeadersRef<'a> {
+        self.trailers
+    }
+
+    /// Copies the request out of the input buffer.
+    pub fn to_owned(&self) -> Request {
+        Request {
+            version: self.version,
+            method: self.method.clone(),
+            path: self.path.to_string(),
+            headers: self.headers.into(),
+            body: self.body.to_vec(),
+            trailers: self.trailers.into(),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for RequestRef<'a> {
+    type Error = Error;
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        RequestRef::parse(value)
     }
 }
 
@@ -165,4 +546,377 @@ mod tests {
         // Tests
         assert_eq!(expected, s);
     }
+
+    #[test]
+    fn test_to_string_without_headers() {
+        let req = Request::new(1, Method::GET, "/", Headers::default(), "");
+        let s = req.to_string();
+
+        // Tests
+        assert_eq!("1 GET /\n\n\n", s);
+        assert_eq!(req, s.parse().unwrap());
+    }
+
+    #[test]
+    fn test_binary_body_round_trip() {
+        let body: Vec<u8> = (0..=255).collect();
+        let req = Request::new(1, Method::POST, "/upload", Headers::default(), body.clone());
+        let parsed = Request::try_from(req.to_bytes().as_slice()).unwrap();
+
+        // Tests
+        assert_eq!(body, parsed.body());
+        assert_eq!(body, parsed.into_body());
+    }
+
+    #[test]
+    fn test_content_length_is_case_insensitive() {
+        let req: Request = "1 POST /\ncontent-length: 2\n\nHi there".parse().unwrap();
+
+        // Tests
+        assert_eq!(b"Hi", req.body());
+    }
+
+    #[test]
+    fn test_fail_to_parse_truncated_body() {
+        let req: Result<Request, Error> = "1 POST /\nContent-Length: 20\n\nHi".parse();
+        let invalid: Result<Request, Error> = "1 POST /\nContent-Length: two\n\nHi".parse();
+
+        // Tests
+        assert!(req.is_err());
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_framing_headers() {
+        let kind = |s: &str| match Request::parse_with(s.as_bytes(), &ParseOptions::strict()) {
+            Err(Error::ParseError(e)) => e.kind(),
+            other => panic!("{:?}", other),
+        };
+        let repeated: Request = "1 POST /\nContent-Length: 2\nContent-Length: 2\n\nHi"
+            .parse()
+            .unwrap();
+
+        // Tests
+        assert_eq!(b"Hi", repeated.body());
+        assert_eq!(
+            ParseErrorKind::InvalidContentLength,
+            kind("1 POST /\nContent-Length: +2\n\nHi")
+        );
+        assert_eq!(
+            ParseErrorKind::InvalidContentLength,
+            kind("1 POST /\nContent-Length: -0\n\n")
+        );
+        assert_eq!(
+            ParseErrorKind::ConflictingFraming,
+            kind("1 POST /\nContent-Length: 2\nContent-Length: 4\n\nHi!!")
+        );
+        assert_eq!(
+            ParseErrorKind::ConflictingFraming,
+            kind("1 POST /\nTransfer-Encoding: chunked\nTransfer-Encoding: chunked\n\n0\n\n")
+        );
+    }
+
+    #[test]
+    fn test_parse_error_positions() {
+        let kind_and_position = |s: &str| match Request::parse(s.as_bytes()) {
+            Err(Error::ParseError(e)) => (e.kind(), e.line(), e.offset()),
+            other => panic!("{:?}", other),
+        };
+
+        // Tests
+        assert_eq!(
+            (ParseErrorKind::InvalidMethod, 1, 2),
+            kind_and_position("1 get /\n\n\n")
+        );
+        assert_eq!(
+            (ParseErrorKind::MissingColon, 3, 25),
+            kind_and_position("1 GET /\na: hello\nno colon\n\n")
+        );
+        assert_eq!(
+            (ParseErrorKind::InvalidHeaderName, 2, 8),
+            kind_and_position("1 GET /\n: empty name\n\n")
+        );
+        assert_eq!(
+            (ParseErrorKind::InvalidContentLength, 2, 24),
+            kind_and_position("1 GET /\nContent-Length: two\n\nHi")
+        );
+        assert_eq!(
+            (ParseErrorKind::IncompleteBody, 4, 29),
+            kind_and_position("1 GET /\nContent-Length: 5\n\nHi")
+        );
+    }
+
+    #[test]
+    fn test_strict_and_lenient() {
+        let crlf = b"1  GET /path \r\nHost : example\r\nAccept: text/html  \r\n\r\nHi";
+        let strict = ParseOptions::strict();
+        let kind = |s: &[u8]| match Request::parse_with(s, &strict) {
+            Err(Error::ParseError(e)) => e.kind(),
+            other => panic!("{:?}", other),
+        };
+        let req = Request::parse_with(crlf, &ParseOptions::lenient()).unwrap();
+
+        // Tests
+        assert_eq!("/path", req.path());
+        assert_eq!(Some("example"), req.headers().get("host"));
+        assert_eq!(Some("text/html"), req.headers().get("accept"));
+        assert_eq!(b"Hi", req.body());
+        assert_eq!(ParseErrorKind::CarriageReturn, kind(crlf));
+        assert_eq!(ParseErrorKind::Whitespace, kind(b"1 GET / \n\n\n"));
+        assert_eq!(ParseErrorKind::InvalidMethod, kind(b"1  GET /\n\n\n"));
+        assert_eq!(ParseErrorKind::InvalidPath, kind(b"1 GET a b\n\n\n"));
+        assert_eq!(ParseErrorKind::Whitespace, kind(b"1 GET /\nHost : a\n\n"));
+        assert_eq!(ParseErrorKind::Whitespace, kind(b"1 GET /\nHost: a \n\n"));
+        assert!(Request::parse_with(b"1 GET /\nHost: a\n\n", &strict).is_ok());
+    }
+
+    #[test]
+    fn test_limits() {
+        let options = ParseOptions::default()
+            .with_max_path_len(4)
+            .with_max_headers(1)
+            .with_max_body_len(2);
+        let kind = |s: &str| match Request::parse_with(s.as_bytes(), &options) {
+            Err(Error::ParseError(e)) => e.kind(),
+            other => panic!("{:?}", other),
+        };
+
+        // Tests
+        assert_eq!(ParseErrorKind::PathTooLong, kind("1 GET /path\n\n\n"));
+        assert_eq!(
+            ParseErrorKind::TooManyHeaders,
+            kind("1 GET /\na: 1\nb: 2\n\n")
+        );
+        assert_eq!(ParseErrorKind::BodyTooLarge, kind("1 GET /\n\n\nabc"));
+        assert_eq!(
+            ParseErrorKind::BodyTooLarge,
+            kind("1 GET /\nTransfer-Encoding: chunked\n\n2\nab\n1\nc\n0\n\n")
+        );
+        assert!(Request::parse_with(b"1 GET /abc\na: 1\n\nab", &options).is_ok());
+    }
+
+    #[test]
+    fn test_hostile_framing() {
+        let packets = [
+            "1 POST /\nContent-Length: 4\ncontent-length: 0\n\nHi!!",
+            "1 POST /\nContent-Length: 0\nContent-Length: 0, 4\n\nHi!!",
+            "1 POST /\nTransfer-Encoding: chunked\ntransfer-encoding: gzip\n\n0\n\n",
+            "1 POST /\nTransfer-Encoding: chunked\nContent-Length: 2\n\n0\n\n",
+        ];
+
+        // Tests
+        for options in [ParseOptions::strict(), ParseOptions::lenient()] {
+            for packet in packets {
+                match Request::parse_with(packet.as_bytes(), &options) {
+                    Err(Error::ParseError(e)) => {
+                        assert_eq!(ParseErrorKind::ConflictingFraming, e.kind())
+                    }
+                    other => panic!("{:?}", other),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_borrowed_request() {
+        let buffer = b"1 POST /upload\na: hello\nContent-Length: 5\n\nHello World";
+        let req = RequestRef::parse(buffer).unwrap();
+
+        // Tests
+        assert_eq!(Version::V1, req.version());
+        assert_eq!(&Method::POST, req.method());
+        assert_eq!("/upload", req.path());
+        assert_eq!(Some("hello"), req.headers().get("A"));
+        assert_eq!(2, req.headers().len());
+        assert_eq!(b"Hello", req.body());
+        assert_eq!(Request::try_from(&buffer[..]).unwrap(), req.to_owned());
+    }
+
+    #[test]
+    fn test_builder() {
+        let req = Request::builder()
+            .method(Method::PUT)
+            .path("/items/1")
+            .header("a", "hello")
+            .header("a", "world")
+            .body("Hi")
+            .build()
+            .unwrap();
+
+        // Tests
+        assert_eq!(Version::V1, req.version());
+        assert_eq!(&Method::PUT, req.method());
+        assert_eq!("/items/1", req.path());
+        assert_eq!(
+            vec!["hello", "world"],
+            req.headers().get_all("A").collect::<Vec<_>>()
+        );
+        assert_eq!(
+            req.to_bytes(),
+            Request::try_from(req.to_bytes().as_slice())
+                .unwrap()
+                .to_bytes()
+        );
+    }
+
+    #[test]
+    fn test_builder_validation() {
+        let build = |builder: RequestBuilder| builder.build().unwrap_err();
+
+        // Tests
+        assert_eq!(
+            Error::BuildError("Path can't contain control characters"),
+            build(Request::builder().path("/a\n1 GET /b"))
+        );
+        assert_eq!(
+            Error::BuildError("Path can't be empty"),
+            build(Request::builder().path(""))
+        );
+        assert_eq!(
+            Error::BuildError("Header values can't be empty"),
+            build(Request::builder().header("a", " "))
+        );
+        assert_eq!(
+            Error::BuildError("Header names must be non-empty tokens"),
+            build(Request::builder().header("a: b", "c"))
+        );
+        assert_eq!(
+            Error::BuildError("Header values can't contain newlines"),
+            build(Request::builder().header("a", "b\n\nbody"))
+        );
+        assert_eq!(
+            Error::BuildError("Content-Length doesn't match the body"),
+            build(Request::builder().header("Content-Length", "3").body("Hi"))
+        );
+        assert_eq!(
+            Error::BuildError("Content-Length doesn't match the body"),
+            build(Request::builder().header("Content-Length", "+2").body("Hi"))
+        );
+        assert_eq!(
+            Error::WrongMethod,
+            build(Request::builder().method(Method::Extension("get".into())))
+        );
+        assert_eq!(
+            Error::WrongMethod,
+            build(Request::builder().method(Method::Extension("GET".into())))
+        );
+        assert_eq!(Error::WrongVersion, build(Request::builder().version(0)));
+    }
+
+    #[test]
+    fn test_mutable_accessors() {
+        let mut req = Request::builder().build().unwrap();
+        req.headers_mut().insert("a", "b");
+        req.body_mut().extend_from_slice(b"Hi");
+        let parsed = Request::try_from(req.to_bytes().as_slice()).unwrap();
+
+        // Tests
+        assert_eq!(Some("b"), parsed.headers().get("a"));
+        assert_eq!(b"Hi", parsed.body());
+    }
+
+    #[test]
+    fn test_chunked_request() {
+        let req = Request::builder()
+            .method(Method::POST)
+            .chunked()
+            .body("Hello World")
+            .trailer("Checksum", "1234")
+            .build()
+            .unwrap();
+        let bytes = req.to_bytes();
+        let parsed = RequestRef::parse(&bytes).unwrap();
+
+        // Tests
+        assert_eq!(
+            &b"1 POST /\nTransfer-Encoding: chunked\n\nb\nHello World\n0\nChecksum: 1234\n\n"[..],
+            &bytes[..]
+        );
+        assert_eq!(b"Hello World", parsed.body());
+        assert_eq!(Some("1234"), parsed.trailers().get("checksum"));
+        assert_eq!(req, parsed.to_owned());
+    }
+
+    #[test]
+    fn test_chunked_validation() {
+        let build = |builder: RequestBuilder| builder.build().unwrap_err();
+
+        // Tests
+        assert_eq!(
+            Error::BuildError("Trailers need chunked transfer encoding"),
+            build(Request::builder().trailer("a", "b"))
+        );
+        assert_eq!(
+            Error::BuildError("Content-Length can't be used with chunked encoding"),
+            build(Request::builder().chunked().header("Content-Length", "0"))
+        );
+        assert!("1 GET /\nTransfer-Encoding: chunked\n\n5\nHel"
+            .parse::<Request>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_url() {
+        let url: Url = "aethon://example:9000/search?q=a+b#results"
+            .parse()
+            .unwrap();
+        let req = Request::builder().url(&url).build().unwrap();
+
+        // Tests
+        assert_eq!("/search?q=a+b", req.path());
+        assert_eq!(Some("example:9000"), req.headers().get("host"));
+        assert_eq!(Url::parse("aethon://example:9000/search?q=a+b"), req.url());
+        assert!(Request::builder().build().unwrap().url().is_err());
+    }
+
+    #[test]
+    fn test_preconditions() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let etag = ETag::strong("v2");
+        let modified = UNIX_EPOCH + Duration::from_secs(1445412480);
+        let check = |method: Method, key: &str, value: &str| {
+            Request::builder()
+                .method(method)
+                .header(key, value)
+                .build()
+                .unwrap()
+                .preconditions(Some(&etag), Some(modified))
+        };
+        let date = "Wed, 21 Oct 2015 07:28:00 GMT";
+        let earlier = "Wed, 21 Oct 2015 07:27:59 GMT";
+
+        // Tests
+        assert_eq!(
+            Some(Status::NotModified),
+            check(Method::GET, "If-None-Match", "\"v1\", W/\"v2\"")
+        );
+        assert_eq!(None, check(Method::GET, "If-None-Match", "\"v1\""));
+        assert_eq!(
+            Some(Status::PreconditionFailed),
+            check(Method::PUT, "If-None-Match", "*")
+        );
+        assert_eq!(
+            Some(Status::NotModified),
+            check(Method::GET, "If-Modified-Since", date)
+        );
+        assert_eq!(None, check(Method::GET, "If-Modified-Since", earlier));
+        assert_eq!(None, check(Method::POST, "If-Modified-Since", date));
+        assert_eq!(None, check(Method::PUT, "If-Match", "\"v2\""));
+        assert_eq!(
+            Some(Status::PreconditionFailed),
+            check(Method::PUT, "If-Match", "W/\"v2\"")
+        );
+        assert_eq!(
+            Some(Status::PreconditionFailed),
+            check(Method::PUT, "If-Unmodified-Since", earlier)
+        );
+        let undated = Request::builder()
+            .method(Method::PUT)
+            .header("If-Unmodified-Since", earlier)
+            .build()
+            .unwrap();
+        assert_eq!(None, undated.preconditions(Some(&etag), None));
+        assert_eq!(None, check(Method::GET, "If-Modified-Since", "yesterday"));
+    }
 }
@@ -1,60 +1,153 @@
-use super::{headers::Headers, status::Status, Error};
+use super::{
+    chunked,
+    error::{ParseError, ParseErrorKind},
+    headers::{Connection, Cookie, Headers, HeadersRef, SetCookie, TypedHeader},
+    parser::{self, ParseOptions},
+    status::Status,
+    version::Version,
+    Error,
+};
 use std::{
-    fmt::Display,
-    iter::Peekable,
-    str::{Chars, FromStr},
+    borrow::Cow,
+    fmt::{self, Display},
+    str::{FromStr, Utf8Error},
 };
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Response {
-    version: u8,
+    version: Version,
     status: Status,
     headers: Headers,
-    body: String,
+    body: Vec<u8>,
+    trailers: Headers,
 }
 
 impl Response {
-    pub fn new(version: u8, status: Status, headers: Headers, body: impl Into<String>) -> Self {
+    pub fn new(
+        version: impl Into<Version>, status: Status, headers: Headers, body: impl Into<Vec<u8>>,
+    ) -> Self {
         Self {
-            version,
-            status,
+            version: version.into(),
+            // Custom codes of known statuses become their named variants
+            status: Status::try_from(status.code()).unwrap_or(status),
             headers,
             body: body.into(),
+            trailers: Headers::default(),
         }
     }
 
-    fn parse(s: &str) -> Result<Self, Error> {
-        let mut buffer = s.chars().peekable();
+    /// Starts building a `200` response.
+    pub fn builder() -> ResponseBuilder {
+        ResponseBuilder::default()
+    }
 
-        let version: u8 = Self::consume_string(&mut buffer, ' ')
-            .parse()
-            .map_err(|_| Error::ParseError("Version isn't an u8"))?;
-        let status: Status = Self::consume_string(&mut buffer, '\n')
-            .parse()
-            .map_err(|_| Error::ParseError("Invalid status"))?;
-        let headers: Headers = Headers::parser_headers(&mut buffer)?;
-        let body: String = buffer.collect();
+    pub fn version(&self) -> Version {
+        self.version
+    }
 
-        Ok(Response {
-            version,
-            status,
-            headers,
-            body,
-        })
+    pub fn status(&self) -> Status {
+        self.status
     }
 
-    fn consume_string(buffer: &mut Peekable<Chars>, to: char) -> String {
-        let mut s = String::new();
+    /// Sets the status, custom codes of known statuses become their named variants.
+    pub fn set_status(&mut self, status: Status) {
+        self.status = Status::try_from(status.code()).unwrap_or(status);
+    }
 
-        for ch in buffer {
-            if ch == to {
-                break;
-            }
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
 
-            s.push(ch);
+    pub fn headers_mut(&mut self) -> &mut Headers {
+        &mut self.headers
+    }
+
+    /// Returns the raw body.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    pub fn body_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.body
+    }
+
+    /// Returns the trailers sent after a chunked body.
+    pub fn trailers(&self) -> &Headers {
+        &self.trailers
+    }
+
+    /// Trailers are only sent with `Transfer-Encoding: chunked`.
+    pub fn trailers_mut(&mut self) -> &mut Headers {
+        &mut self.trailers
+    }
+
+    /// Returns the body as text, fails if it isn't valid UTF-8.
+    pub fn text(&self) -> Result<&str, Utf8Error> {
+        std::str::from_utf8(&self.body)
+    }
+
+    /// Returns the body as text, invalid UTF-8 sequences are replaced with `U+FFFD`.
+    pub fn text_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.body)
+    }
+
+    /// Consumes the response, returning its body.
+    pub fn into_body(self) -> Vec<u8> {
+        self.body
+    }
+
+    /// Whether the server keeps the connection open afterwards, it does unless it sent
+    /// `Connection: close`.
+    pub fn keep_alive(&self) -> bool {
+        matches!(
+            self.headers.typed_get::<Connection>(),
+            Ok(None | Some(Connection::KeepAlive))
+        )
+    }
+
+    /// Serializes the response into its wire format.
+    ///
+    /// With `Transfer-Encoding: chunked` the body is written as a single chunk.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = self.head_bytes();
+        match self.headers.is_chunked() {
+            true => chunked::encode(&self.body, &self.trailers, &mut buffer),
+            false => buffer.extend_from_slice(&self.body),
         }
+        buffer
+    }
+
+    /// Serializes the start line and the headers.
+    pub(crate) fn head_bytes(&self) -> Vec<u8> {
+        let mut head = String::new();
+        // Writing into a String can't fail
+        let _ = self.write_head(&mut head);
+        head.into_bytes()
+    }
+
+    pub(crate) fn parse(buf: &[u8]) -> Result<Self, Error> {
+        Self::parse_with(buf, &ParseOptions::default())
+    }
+
+    /// Parses a response as strictly as `options` say.
+    pub fn parse_with(buf: &[u8], options: &ParseOptions) -> Result<Self, Error> {
+        ResponseRef::parse_with(buf, options).map(|res| res.to_owned())
+    }
+
+    /// Parses the head of a packet, the body is left empty.
+    #[cfg(feature = "codec")]
+    pub(crate) fn parse_head(head: &[u8], options: &ParseOptions) -> Result<Self, Error> {
+        let packet = parser::split(head, options)?;
+        ResponseRef::from_head(&packet, options).map(|res| res.to_owned())
+    }
 
-        s
+    fn write_head(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        // Version
+        write!(f, "{} ", self.version)?;
+        // Status
+        writeln!(f, "{}", self.status)?;
+        // Headers
+        self.headers.write_block(f, self.body.len())
     }
 }
 
@@ -62,31 +155,230 @@ impl Response {
 impl FromStr for Response {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Self::parse(s)
+        Self::parse(s.as_bytes())
     }
 }
 
 impl TryFrom<&[u8]> for Response {
     type Error = Error;
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        let buffer = String::from_utf8_lossy(value);
-        Response::parse(&buffer)
+        Response::parse(value)
     }
 }
 
+/// The packet is written lossily, use [`Response::to_bytes`] for binary bodies.
 impl Display for Response {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        // Version
-        // (1-9) + 48 = ASCII representation
-        write!(f, "{} ", (self.version + 48) as char)?;
-        // Status
-        writeln!(f, "{}", self.status)?;
-        // Headers
-        writeln!(f, "{}", self.headers)?;
-        // Body
-        write!(f, "{}", self.body)?;
+        write!(f, "{}", String::from_utf8_lossy(&self.to_bytes()))
+    }
+}
+
+/// Builds a [`Response`], checking that it can be written and parsed back.
+///
+/// ```
+/// use aethon::{Response, Status};
+///
+/// let res = Response::builder()
+///     .status(Status::NotFound)
+///     .body("Not here")
+///     .build()
+///     .unwrap();
+/// assert_eq!(Status::NotFound, res.status());
+/// ```
+#[derive(Debug)]
+pub struct ResponseBuilder {
+    version: Version,
+    status: Status,
+    headers: Headers,
+    body: Vec<u8>,
+    trailers: Headers,
+}
 
-        Ok(())
+impl ResponseBuilder {
+    pub fn version(mut self, version: impl Into<Version>) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    pub fn status(mut self, status: Status) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Adds a header, repeated calls with the same name add more values.
+    pub fn header(mut self, key: impl Into<Box<str>>, value: impl Into<Box<str>>) -> Self {
+        self.headers.append(key, value);
+        self
+    }
+
+    /// Sets a typed header, replacing its previous values.
+    pub fn typed_header<H: TypedHeader>(mut self, header: H) -> Self {
+        self.headers.typed_insert(header);
+        self
+    }
+
+    /// Adds a `Set-Cookie` header, keeping the cookies set before.
+    pub fn cookie(mut self, cookie: Cookie) -> Self {
+        self.headers.append(SetCookie::NAME, cookie.to_string());
+        self
+    }
+
+    /// Replaces all headers.
+    pub fn headers(mut self, headers: Headers) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Sends the body with chunked transfer encoding.
+    pub fn chunked(mut self) -> Self {
+        self.headers
+            .insert(Headers::TRANSFER_ENCODING, chunked::CHUNKED);
+        self
+    }
+
+    /// Adds a trailer, they need [`ResponseBuilder::chunked`].
+    pub fn trailer(mut self, key: impl Into<Box<str>>, value: impl Into<Box<str>>) -> Self {
+        self.trailers.append(key, value);
+        self
+    }
+
+    pub fn build(self) -> Result<Response, Error> {
+        if self.version.get() == 0 {
+            return Err(Error::WrongVersion);
+        }
+        // Custom codes of known statuses become their named variants
+        let status = Status::try_from(self.status.code())?;
+        self.headers.validate(self.body.len())?;
+        if !self.trailers.is_empty() && !self.headers.is_chunked() {
+            return Err(Error::BuildError("Trailers need chunked transfer encoding"));
+        }
+        self.trailers.validate(0)?;
+
+        Ok(Response {
+            version: self.version,
+            status,
+            headers: self.headers,
+            body: self.body,
+            trailers: self.trailers,
+        })
+    }
+}
+
+impl Default for ResponseBuilder {
+    fn default() -> Self {
+        Self {
+            version: Version::V1,
+            status: Status::OK,
+            headers: Headers::default(),
+            body: Vec::new(),
+            trailers: Headers::default(),
+        }
+    }
+}
+
+/// Borrowed view of a response, parsed without copying out of the input buffer.
+///
+/// Use [`ResponseRef::to_owned`] to turn it into a [`Response`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseRef<'a> {
+    version: Version,
+    status: Status,
+    headers: HeadersRef<'a>,
+    body: Cow<'a, [u8]>,
+    trailers: HeadersRef<'a>,
+}
+
+impl<'a> ResponseRef<'a> {
+    pub fn parse(buf: &'a [u8]) -> Result<Self, Error> {
+        Self::parse_with(buf, &ParseOptions::default())
+    }
+
+    /// Parses a response as strictly as `options` say.
+    pub fn parse_with(buf: &'a [u8], options: &ParseOptions) -> Result<Self, Error> {
+        let packet = parser::split(buf, options)?;
+        let mut res = Self::from_head(&packet, options)?;
+        let body = packet.body(packet.framing(&res.headers, options)?, options)?;
+        res.body = body.data;
+        res.trailers = body.trailers;
+        Ok(res)
+    }
+
+    /// Parses the start line and the headers, the body is left empty.
+    pub(crate) fn from_head(
+        packet: &parser::RawPacket<'a>, options: &ParseOptions,
+    ) -> Result<Self, Error> {
+        let line = packet.start_line;
+        let error = |kind, field: &str| {
+            ParseError::at(kind, line.as_bytes(), parser::offset_in(line, field))
+        };
+        // A reason phrase after the status is only accepted when lenient
+        let [version, status] = match options.is_strict() {
+            true => parser::start_line_fields(line, options)?,
+            false => {
+                let [version, status, _reason] = parser::start_line_fields(line, options)?;
+                [version, status]
+            }
+        };
+
+        let version: Version = version
+            .parse()
+            .map_err(|_| error(ParseErrorKind::InvalidVersion, version))?;
+        let status: Status = status
+            .parse()
+            .map_err(|_| error(ParseErrorKind::InvalidStatus, status))?;
+        let headers = packet.parse_headers(options)?;
+
+        Ok(ResponseRef {
+            version,
+            status,
+            headers,
+            body: Cow::Borrowed(&[]),
+            trailers: HeadersRef::default(),
+        })
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    pub fn headers(&self) -> HeadersRef<'a> {
+        self.headers
+    }
+
+    /// Returns the body, it's only copied if it was chunked.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    pub fn trailers(&self) -> HeadersRef<'a> {
+        self.trailers
+    }
+
+    /// Copies the response out of the input buffer.
+    pub fn to_owned(&self) -> Response {
+        Response {
+            version: self.version,
+            status: self.status,
+            headers: self.headers.into(),
+            body: self.body.to_vec(),
+            trailers: self.trailers.into(),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ResponseRef<'a> {
+    type Error = Error;
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        ResponseRef::parse(value)
     }
 }
 
@@ -142,9 +434,99 @@ mod tests {
             ])),
             "Hello World",
         );
-        let expected = "1 200\nh: Hello\nw: World\n\nHello World";
+        let expected = "1 200\nh: Hello\nw: World\nContent-Length: 11\n\nHello World";
 
         // Tests
         assert_eq!(expected, res.to_string());
     }
+
+    #[test]
+    fn test_binary_body_round_trip() {
+        let body = vec![0x89, b'P', b'N', b'G', 0x00, 0xff, b'\n', b'\n'];
+        let res = Response::new(1, Status::OK, Headers::default(), body.clone());
+        let parsed = Response::try_from(res.to_bytes().as_slice()).unwrap();
+
+        // Tests
+        assert_eq!(body, parsed.body());
+        assert!(parsed.text().is_err());
+    }
+
+    #[test]
+    fn test_content_length_ends_the_body() {
+        let res: Response = "1 200\nContent-Length: 5\n\nHello World".parse().unwrap();
+
+        // Tests
+        assert_eq!(b"Hello", res.body());
+        assert_eq!(Ok("Hello"), res.text());
+    }
+
+    #[test]
+    fn test_reason_phrase() {
+        let s = b"1 404 Not Found\r\nContent-Length: 2\r\n\r\nHi";
+        let res = Response::parse_with(s, &ParseOptions::lenient()).unwrap();
+
+        // Tests
+        assert_eq!(Status::NotFound, res.status());
+        assert_eq!(b"Hi", res.body());
+        assert!(matches!(
+            Response::parse_with(b"1 404 Not Found\n\n", &ParseOptions::strict()),
+            Err(Error::ParseError(e)) if e.kind() == ParseErrorKind::InvalidStatus && e.offset() == 2
+        ));
+    }
+
+    #[test]
+    fn test_parse_borrowed_response() {
+        let buffer = b"1 404\nh: Hello\nh: World\n\nNot here";
+        let res = ResponseRef::parse(buffer).unwrap();
+
+        // Tests
+        assert_eq!(Status::NotFound, res.status());
+        assert_eq!(
+            vec![("h", "Hello"), ("h", "World")],
+            res.headers().iter().collect::<Vec<_>>()
+        );
+        assert_eq!(b"Not here", res.body());
+        assert_eq!(Response::try_from(&buffer[..]).unwrap(), res.to_owned());
+    }
+
+    #[test]
+    fn test_builder() {
+        let res = Response::builder()
+            .status(Status::Created)
+            .header("Location", "/items/1")
+            .body("Created")
+            .build()
+            .unwrap();
+
+        // Tests
+        assert_eq!(Version::V1, res.version());
+        assert_eq!(Status::Created, res.status());
+        assert_eq!(Some("/items/1"), res.headers().get("location"));
+        assert_eq!(
+            res.to_bytes(),
+            Response::try_from(res.to_bytes().as_slice())
+                .unwrap()
+                .to_bytes()
+        );
+    }
+
+    #[test]
+    fn test_builder_validation() {
+        // Tests
+        assert_eq!(
+            Err(Error::BuildError("Header values can't be empty")),
+            Response::builder().header("a", "").build()
+        );
+        assert_eq!(
+            Err(Error::WrongStatus),
+            Response::builder().status(Status::Custom(999)).build()
+        );
+        let built = Response::builder()
+            .status(Status::Custom(404))
+            .build()
+            .unwrap();
+        assert!(matches!(built.status(), Status::NotFound));
+        let no_content = Response::new(1, Status::Custom(204), Headers::default(), "");
+        assert!(matches!(no_content.status(), Status::NoContent));
+    }
 }
@@ -1,70 +1,170 @@
 use super::Error;
-use std::{fmt::Display, str::FromStr};
+use std::{
+    fmt::Display,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
 
-#[derive(Debug, PartialEq)]
-pub enum Status {
+/// Declares the known statuses with their codes and canonical reason phrases.
+macro_rules! statuses {
+    ($(
+        $(#[$doc:meta])*
+        $variant:ident = $code:literal, $reason:literal;
+    )+) => {
+        /// Statuses are compared by code, so `Custom(404)` equals `NotFound`.
+        #[derive(Debug, Clone, Copy)]
+        pub enum Status {
+            $(
+                $(#[$doc])*
+                $variant,
+            )+
+            /// Any other code between 100 and 599, so newer statuses can still be parsed.
+            Custom(u16),
+        }
+
+        impl Status {
+            /// Returns the numeric code.
+            pub fn code(&self) -> u16 {
+                match self {
+                    $(Self::$variant => $code,)+
+                    Self::Custom(code) => *code,
+                }
+            }
+
+            /// Returns the canonical reason phrase, `None` for custom codes.
+            pub fn canonical_reason(&self) -> Option<&'static str> {
+                match self {
+                    $(Self::$variant => Some($reason),)+
+                    Self::Custom(_) => None,
+                }
+            }
+        }
+
+        impl TryFrom<u16> for Status {
+            type Error = Error;
+            fn try_from(value: u16) -> Result<Self, Self::Error> {
+                match value {
+                    $($code => Ok(Self::$variant),)+
+                    100..=599 => Ok(Self::Custom(value)),
+                    _ => Err(Self::Error::WrongStatus),
+                }
+            }
+        }
+    };
+}
+
+statuses! {
+    // 1** Informational
+    Continue = 100, "Continue";
+    SwitchingProtocols = 101, "Switching Protocols";
     // 2** Success
-    OK,      // 200
-    Created, // 201
+    OK = 200, "OK";
+    Created = 201, "Created";
+    Accepted = 202, "Accepted";
+    NoContent = 204, "No Content";
+    PartialContent = 206, "Partial Content";
+    // 3** Redirection
+    MultipleChoices = 300, "Multiple Choices";
+    MovedPermanently = 301, "Moved Permanently";
+    Found = 302, "Found";
+    SeeOther = 303, "See Other";
+    NotModified = 304, "Not Modified";
+    TemporaryRedirect = 307, "Temporary Redirect";
+    PermanentRedirect = 308, "Permanent Redirect";
     // 4** Client error
-    BadRequest,       // 400
-    Unauthorized,     // 401
-    NotFound,         // 404
-    MethodNotAllowed, // 405
-    ImATeapot,        // 418 The server refuses the attempt to brew coffee with a teapot.
+    BadRequest = 400, "Bad Request";
+    Unauthorized = 401, "Unauthorized";
+    Forbidden = 403, "Forbidden";
+    NotFound = 404, "Not Found";
+    MethodNotAllowed = 405, "Method Not Allowed";
+    NotAcceptable = 406, "Not Acceptable";
+    RequestTimeout = 408, "Request Timeout";
+    Conflict = 409, "Conflict";
+    Gone = 410, "Gone";
+    LengthRequired = 411, "Length Required";
+    PreconditionFailed = 412, "Precondition Failed";
+    PayloadTooLarge = 413, "Payload Too Large";
+    UriTooLong = 414, "URI Too Long";
+    UnsupportedMediaType = 415, "Unsupported Media Type";
+    RangeNotSatisfiable = 416, "Range Not Satisfiable";
+    /// The server refuses the attempt to brew coffee with a teapot.
+    ImATeapot = 418, "I'm a teapot";
+    UnprocessableContent = 422, "Unprocessable Content";
+    TooManyRequests = 429, "Too Many Requests";
+    RequestHeaderFieldsTooLarge = 431, "Request Header Fields Too Large";
     // 5** Server error
-    InternalServerError, // 500
+    InternalServerError = 500, "Internal Server Error";
+    NotImplemented = 501, "Not Implemented";
+    BadGateway = 502, "Bad Gateway";
+    ServiceUnavailable = 503, "Service Unavailable";
+    GatewayTimeout = 504, "Gateway Timeout";
+    VersionNotSupported = 505, "Version Not Supported";
+}
+
+impl Status {
+    /// 1** codes.
+    pub fn is_informational(&self) -> bool {
+        (100..200).contains(&self.code())
+    }
+
+    /// 2** codes.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.code())
+    }
+
+    /// 3** codes.
+    pub fn is_redirection(&self) -> bool {
+        (300..400).contains(&self.code())
+    }
+
+    /// 4** codes.
+    pub fn is_client_error(&self) -> bool {
+        (400..500).contains(&self.code())
+    }
+
+    /// 5** codes.
+    pub fn is_server_error(&self) -> bool {
+        (500..600).contains(&self.code())
+    }
+
+    /// Whether a response with this status can carry a body, 1** codes, `204` and `304` can't.
+    pub fn allows_body(&self) -> bool {
+        !matches!(self.code(), 100..=199 | 204 | 304)
+    }
+}
+
+impl PartialEq for Status {
+    fn eq(&self, other: &Self) -> bool {
+        self.code() == other.code()
+    }
+}
+
+impl Eq for Status {}
+
+impl Hash for Status {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.code().hash(state);
+    }
 }
 
 impl FromStr for Status {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.trim() {
-            // 2**
-            "200" => Ok(Self::OK),
-            "201" => Ok(Self::Created),
-            // 4**
-            "400" => Ok(Self::BadRequest),
-            "401" => Ok(Self::Unauthorized),
-            "404" => Ok(Self::NotFound),
-            "405" => Ok(Self::MethodNotAllowed),
-            "418" => Ok(Self::ImATeapot),
-            // 5**
-            "500" => Ok(Self::InternalServerError),
-            _ => Err(Self::Err::WrongStatus),
+        let s = s.trim();
+        // u16::from_str accepts a leading +
+        if s.len() != 3 || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(Self::Err::WrongStatus);
         }
+
+        s.parse::<u16>()
+            .map_err(|_| Self::Err::WrongStatus)
+            .and_then(Self::try_from)
     }
 }
 
 impl Display for Status {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::OK => write!(f, "200"),
-            Self::Created => write!(f, "201"),
-            Self::BadRequest => write!(f, "400"),
-            Self::Unauthorized => write!(f, "401"),
-            Self::NotFound => write!(f, "404"),
-            Self::MethodNotAllowed => write!(f, "405"),
-            Self::ImATeapot => write!(f, "418"),
-            Self::InternalServerError => write!(f, "500"),
-        }
-    }
-}
-
-impl TryFrom<u16> for Status {
-    type Error = Error;
-    fn try_from(value: u16) -> Result<Self, Self::Error> {
-        match value {
-            200 => Ok(Status::OK),
-            201 => Ok(Status::Created),
-            400 => Ok(Status::BadRequest),
-            401 => Ok(Status::Unauthorized),
-            404 => Ok(Status::NotFound),
-            405 => Ok(Status::MethodNotAllowed),
-            418 => Ok(Status::ImATeapot),
-            500 => Ok(Status::InternalServerError),
-            _ => Err(Self::Error::WrongStatus),
-        }
+        write!(f, "{}", self.code())
     }
 }
 
@@ -107,12 +207,48 @@ mod tests {
 
     #[test]
     fn test_invalid_status_codes() {
-        assert_eq!(Status::from_str("199"), Err(Error::WrongStatus));
-        assert_eq!(Status::from_str("300"), Err(Error::WrongStatus));
+        assert_eq!(Status::from_str("099"), Err(Error::WrongStatus));
         assert_eq!(Status::from_str("600"), Err(Error::WrongStatus));
+        assert_eq!(Status::from_str("+200"), Err(Error::WrongStatus));
+        assert_eq!(Status::from_str("2000"), Err(Error::WrongStatus));
+        assert_eq!(Status::try_from(99), Err(Error::WrongStatus));
         assert_eq!(Status::try_from(999), Err(Error::WrongStatus));
     }
 
+    #[test]
+    fn test_unknown_status_codes_pass_through() {
+        assert_eq!(Status::from_str("199"), Ok(Status::Custom(199)));
+        assert_eq!(Status::try_from(599), Ok(Status::Custom(599)));
+        assert_eq!(Status::Custom(451).to_string(), "451");
+        assert_eq!(Status::Custom(451).canonical_reason(), None);
+        assert!(Status::Custom(451).is_client_error());
+        assert_eq!(Status::NotFound, Status::Custom(404));
+        assert!(!Status::Custom(204).allows_body());
+    }
+
+    #[test]
+    fn test_new_status_codes() {
+        assert_eq!(Status::from_str("300"), Ok(Status::MultipleChoices));
+        assert_eq!(Status::from_str("403"), Ok(Status::Forbidden));
+        assert_eq!(Status::try_from(503), Ok(Status::ServiceUnavailable));
+        assert_eq!(Status::NotModified.to_string(), "304");
+        assert_eq!(Status::TooManyRequests.code(), 429);
+        assert_eq!(
+            Status::PayloadTooLarge.canonical_reason(),
+            Some("Payload Too Large")
+        );
+    }
+
+    #[test]
+    fn test_status_classes() {
+        assert!(Status::Continue.is_informational());
+        assert!(Status::NoContent.is_success());
+        assert!(Status::SeeOther.is_redirection());
+        assert!(Status::Conflict.is_client_error());
+        assert!(Status::GatewayTimeout.is_server_error());
+        assert!(!Status::OK.is_client_error());
+    }
+
     #[test]
     fn test_whitespace_handling() {
         assert_eq!(Status::from_str(" 200 "), Ok(Status::OK));
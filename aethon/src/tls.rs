@@ -0,0 +1,379 @@
+//! TLS for `aethons://` with rustls, certificates and keys are loaded from PEM.
+
+use std::{collections::HashMap, fmt, fs, io, path::Path, sync::Arc};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::{
+    client,
+    rustls::{
+        self,
+        crypto::{ring, CryptoProvider},
+        pki_types::{CertificateDer, PrivateKeyDer, ServerName},
+        server::{ClientHello, ResolvesServerCert},
+        sign::CertifiedKey,
+        ClientConfig, RootCertStore, ServerConfig,
+    },
+    server,
+};
+
+/// Accepts TLS connections, e.g. before handing them to [`serve`](crate::serve).
+///
+/// ```no_run
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// use aethon::{serve, Response, ServeOptions, TlsAcceptor};
+/// use tokio::net::TcpListener;
+///
+/// let acceptor = TlsAcceptor::from_pem_files("cert.pem", "key.pem")?;
+/// let listener = TcpListener::bind("127.0.0.1:8443").await?;
+/// let (socket, _) = listener.accept().await?;
+/// let stream = acceptor.accept(socket).await?;
+/// serve(stream, ServeOptions::default(), |_| async {
+///     Response::builder().build().unwrap()
+/// })
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct TlsAcceptor {
+    inner: tokio_rustls::TlsAcceptor,
+}
+
+impl TlsAcceptor {
+    /// Serves the certificate chain in `cert` with the private key in `key` to every client.
+    pub fn from_pem_files(cert: impl AsRef<Path>, key: impl AsRef<Path>) -> Result<Self, TlsError> {
+        Self::builder()
+            .certificate(fs::read(cert)?, fs::read(key)?)
+            .build()
+    }
+
+    pub fn builder() -> TlsAcceptorBuilder {
+        TlsAcceptorBuilder::default()
+    }
+
+    /// Performs the server side of the handshake.
+    pub async fn accept<IO>(&self, io: IO) -> io::Result<server::TlsStream<IO>>
+    where
+        IO: AsyncRead + AsyncWrite + Unpin,
+    {
+        self.inner.accept(io).await
+    }
+}
+
+impl fmt::Debug for TlsAcceptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsAcceptor").finish_non_exhaustive()
+    }
+}
+
+/// Builder of a [`TlsAcceptor`], the certificate is picked by the name the client sent with SNI.
+#[derive(Debug, Default)]
+pub struct TlsAcceptorBuilder {
+    default: Option<Pem>,
+    by_name: Vec<(String, Pem)>,
+}
+
+impl TlsAcceptorBuilder {
+    /// PEM certificate chain and private key served when no other certificate matches.
+    pub fn certificate(mut self, cert: impl Into<Vec<u8>>, key: impl Into<Vec<u8>>) -> Self {
+        self.default = Some(Pem::new(cert, key));
+        self
+    }
+
+    /// PEM certificate chain and private key served to clients asking for `name`.
+    pub fn sni_certificate(
+        mut self, name: &str, cert: impl Into<Vec<u8>>, key: impl Into<Vec<u8>>,
+    ) -> Self {
+        self.by_name
+            .push((name.to_ascii_lowercase(), Pem::new(cert, key)));
+        self
+    }
+
+    pub fn build(self) -> Result<TlsAcceptor, TlsError> {
+        let provider = provider();
+        let load = |pem: &Pem| -> Result<Arc<CertifiedKey>, TlsError> {
+            let key = provider
+                .key_provider
+                .load_private_key(parse_key(&pem.key)?)?;
+            Ok(Arc::new(CertifiedKey::new(parse_certs(&pem.cert)?, key)))
+        };
+
+        let certificates = Certificates {
+            default: self.default.as_ref().map(load).transpose()?,
+            by_name: self
+                .by_name
+                .iter()
+                .map(|(name, pem)| Ok((name.clone(), load(pem)?)))
+                .collect::<Result<_, TlsError>>()?,
+        };
+        if certificates.default.is_none() && certificates.by_name.is_empty() {
+            return Err(TlsError::NoCertificate);
+        }
+
+        let config = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_cert_resolver(Arc::new(certificates));
+        Ok(TlsAcceptor {
+            inner: Arc::new(config).into(),
+        })
+    }
+}
+
+struct Pem {
+    cert: Vec<u8>,
+    key: Vec<u8>,
+}
+
+impl Pem {
+    fn new(cert: impl Into<Vec<u8>>, key: impl Into<Vec<u8>>) -> Self {
+        Self {
+            cert: cert.into(),
+            key: key.into(),
+        }
+    }
+}
+
+impl fmt::Debug for Pem {
+    // Keeps the private key out of logs
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pem").finish_non_exhaustive()
+    }
+}
+
+/// Picks the certificate for the name the client asked for.
+#[derive(Debug)]
+struct Certificates {
+    default: Option<Arc<CertifiedKey>>,
+    by_name: HashMap<String, Arc<CertifiedKey>>,
+}
+
+impl ResolvesServerCert for Certificates {
+    fn resolve(&self, hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        hello
+            .server_name()
+            .and_then(|name| self.by_name.get(&name.to_ascii_lowercase()))
+            .or(self.default.as_ref())
+            .cloned()
+    }
+}
+
+/// Certificate authorities a client trusts, there are none by default.
+///
+/// Ariadnet hosts aren't on the public internet, their certificates are usually self-signed
+/// and trusted one by one.
+#[derive(Debug, Clone)]
+pub struct TlsRoots {
+    store: RootCertStore,
+}
+
+impl Default for TlsRoots {
+    fn default() -> Self {
+        Self {
+            store: RootCertStore::empty(),
+        }
+    }
+}
+
+impl TlsRoots {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trusts the PEM certificates in the file at `path`.
+    pub fn from_pem_file(path: impl AsRef<Path>) -> Result<Self, TlsError> {
+        let mut roots = Self::new();
+        roots.add_pem(&fs::read(path)?)?;
+        Ok(roots)
+    }
+
+    /// Trusts every certificate in `pem`.
+    pub fn add_pem(&mut self, pem: &[u8]) -> Result<(), TlsError> {
+        for cert in parse_certs(pem)? {
+            self.store.add(cert)?;
+        }
+        Ok(())
+    }
+}
+
+/// Connects to `aethons://` hosts.
+#[derive(Clone)]
+pub(crate) struct TlsConnector {
+    inner: tokio_rustls::TlsConnector,
+}
+
+impl TlsConnector {
+    pub fn new(roots: TlsRoots) -> Self {
+        let config = ClientConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()
+            .expect("ring supports the default protocol versions")
+            .with_root_certificates(roots.store)
+            .with_no_client_auth();
+        Self {
+            inner: Arc::new(config).into(),
+        }
+    }
+
+    /// Performs the client side of the handshake, `host` is sent with SNI and checked against
+    /// the server's certificate.
+    pub async fn connect<IO>(&self, host: &str, io: IO) -> Result<client::TlsStream<IO>, TlsError>
+    where
+        IO: AsyncRead + AsyncWrite + Unpin,
+    {
+        let name = ServerName::try_from(host.to_owned())
+            .map_err(|_| TlsError::InvalidServerName(host.into()))?;
+        Ok(self.inner.connect(name, io).await?)
+    }
+}
+
+impl fmt::Debug for TlsConnector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsConnector").finish_non_exhaustive()
+    }
+}
+
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(ring::default_provider())
+}
+
+fn parse_certs(pem: &[u8]) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let certs = rustls_pemfile::certs(&mut &pem[..]).collect::<Result<Vec<_>, _>>()?;
+    match certs.is_empty() {
+        true => Err(TlsError::NoCertificate),
+        false => Ok(certs),
+    }
+}
+
+fn parse_key(pem: &[u8]) -> Result<PrivateKeyDer<'static>, TlsError> {
+    rustls_pemfile::private_key(&mut &pem[..])?.ok_or(TlsError::NoPrivateKey)
+}
+
+/// Error loading certificates or setting up a TLS connection.
+#[derive(Debug)]
+pub enum TlsError {
+    /// The PEM has no certificate.
+    NoCertificate,
+    /// The PEM has no private key.
+    NoPrivateKey,
+    /// The host is neither a DNS name nor an IP address.
+    InvalidServerName(Box<str>),
+    /// Reading a PEM file failed, or the handshake did.
+    Io(io::Error),
+    Rustls(rustls::Error),
+}
+
+impl fmt::Display for TlsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoCertificate => write!(f, "No certificate found in the PEM"),
+            Self::NoPrivateKey => write!(f, "No private key found in the PEM"),
+            Self::InvalidServerName(host) => write!(f, "Invalid TLS server name: {}", host),
+            Self::Io(e) => write!(f, "I/O error: {}", e),
+            Self::Rustls(e) => write!(f, "TLS error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TlsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Rustls(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TlsError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<rustls::Error> for TlsError {
+    fn from(e: rustls::Error) -> Self {
+        Self::Rustls(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcgen::{generate_simple_self_signed, CertifiedKey as Generated};
+    use tokio::net::{TcpListener, TcpStream};
+
+    fn generate(name: &str) -> (String, String) {
+        let Generated { cert, key_pair } = generate_simple_self_signed([name.into()]).unwrap();
+        (cert.pem(), key_pair.serialize_pem())
+    }
+
+    #[test]
+    fn test_load_pem() {
+        let (cert, key) = generate("apollo");
+
+        // Tests
+        assert!(TlsAcceptor::builder()
+            .certificate(cert.clone(), key.clone())
+            .build()
+            .is_ok());
+        assert!(matches!(
+            TlsAcceptor::builder().build(),
+            Err(TlsError::NoCertificate)
+        ));
+        assert!(matches!(
+            TlsAcceptor::builder().certificate(key.clone(), key).build(),
+            Err(TlsError::NoCertificate)
+        ));
+        assert!(matches!(
+            TlsAcceptor::builder()
+                .certificate(cert.clone(), cert)
+                .build(),
+            Err(TlsError::NoPrivateKey)
+        ));
+        assert!(matches!(
+            TlsRoots::new().add_pem(b""),
+            Err(TlsError::NoCertificate)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sni() {
+        let (localhost_cert, localhost_key) = generate("localhost");
+        let (apollo_cert, apollo_key) = generate("apollo");
+        let acceptor = TlsAcceptor::builder()
+            .certificate(localhost_cert.clone(), localhost_key)
+            .sni_certificate("Apollo", apollo_cert.clone(), apollo_key)
+            .build()
+            .unwrap();
+        let mut roots = TlsRoots::new();
+        roots.add_pem(localhost_cert.as_bytes()).unwrap();
+        roots.add_pem(apollo_cert.as_bytes()).unwrap();
+        let connector = TlsConnector::new(roots);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move { acceptor.accept(socket).await });
+            }
+        });
+        let connect = |host: &'static str| {
+            let connector = connector.clone();
+            async move {
+                let socket = TcpStream::connect(addr).await.unwrap();
+                connector.connect(host, socket).await
+            }
+        };
+
+        // Tests
+        assert!(connect("apollo").await.is_ok());
+        assert!(connect("localhost").await.is_ok());
+        // Gets the default certificate, which isn't for hermes
+        assert!(matches!(connect("hermes").await, Err(TlsError::Io(_))));
+        assert!(matches!(
+            connect("not a host").await,
+            Err(TlsError::InvalidServerName(_))
+        ));
+    }
+}
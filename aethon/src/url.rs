@@ -0,0 +1,512 @@
+//! `aethon://host:port/path?query#fragment` URLs, `aethons://` for Aethon over TLS.
+
+use super::{headers::TypedHeader, Error, Host};
+use std::{
+    borrow::Cow,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+const SCHEME: &str = "aethon";
+const SECURE_SCHEME: &str = "aethons";
+
+/// An absolute `aethon://` or `aethons://` URL.
+///
+/// The path, query and fragment are kept percent-encoded in their canonical form: characters
+/// outside of their allowed sets are encoded, encoded unreserved characters are decoded, hex
+/// digits are uppercase and dot segments are removed. The host is lowercased and the default
+/// port is omitted.
+///
+/// ```
+/// use aethon::Url;
+///
+/// let base: Url = "aethon://Example:8081/docs/guide.html".parse().unwrap();
+/// let link = base.join("../images/logo%2epng?size=2#top").unwrap();
+/// assert_eq!("aethon://example/images/logo.png?size=2#top", link.to_string());
+/// assert_eq!(Ok(Some(2)), link.typed_query::<u32>("size"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Url {
+    /// `aethons://`
+    secure: bool,
+    host: Box<str>,
+    /// `None` for the default port.
+    port: Option<u16>,
+    path: String,
+    query: Option<String>,
+    fragment: Option<String>,
+}
+
+impl Url {
+    /// Port used when an `aethon://` URL has none.
+    pub const DEFAULT_PORT: u16 = 8081;
+    /// Port used when an `aethons://` URL has none.
+    pub const DEFAULT_SECURE_PORT: u16 = 8443;
+
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        let (secure, rest) = match s.split_once("://") {
+            Some((scheme, rest)) if scheme.eq_ignore_ascii_case(SCHEME) => (false, rest),
+            Some((scheme, rest)) if scheme.eq_ignore_ascii_case(SECURE_SCHEME) => (true, rest),
+            _ => {
+                return Err(Error::InvalidUrl(
+                    "URL must start with aethon:// or aethons://",
+                ))
+            }
+        };
+        let (rest, fragment) = split_off(rest, '#');
+        let (rest, query) = split_off(rest, '?');
+        let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+
+        if authority.contains('@') {
+            return Err(Error::InvalidUrl("URL can't contain credentials"));
+        }
+        let host = Host::decode(authority).map_err(|_| Error::InvalidUrl("Invalid URL host"))?;
+
+        let default_port = match secure {
+            true => Self::DEFAULT_SECURE_PORT,
+            false => Self::DEFAULT_PORT,
+        };
+        let mut url = Url {
+            secure,
+            host: host.host().to_ascii_lowercase().into(),
+            port: host.port().filter(|p| *p != default_port),
+            path: String::new(),
+            query: query.map(|q| normalize(q, is_query_char)),
+            fragment: fragment.map(|f| normalize(f, is_query_char)),
+        };
+        url.set_path(path);
+        Ok(url)
+    }
+
+    /// Returns `aethon` or `aethons`.
+    pub fn scheme(&self) -> &str {
+        match self.secure {
+            true => SECURE_SCHEME,
+            false => SCHEME,
+        }
+    }
+
+    /// Whether the URL is `aethons://`, sent over TLS.
+    pub fn is_secure(&self) -> bool {
+        self.secure
+    }
+
+    /// Returns the host name, IPv6 addresses are without brackets.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Returns the port, the scheme's default if the URL has none.
+    pub fn port(&self) -> u16 {
+        match self.secure {
+            true => self.port.unwrap_or(Self::DEFAULT_SECURE_PORT),
+            false => self.port.unwrap_or(Self::DEFAULT_PORT),
+        }
+    }
+
+    /// Returns the `Host` header for requests to this URL.
+    pub fn host_header(&self) -> Host {
+        Host::new(self.host.clone(), self.port)
+    }
+
+    /// Returns the percent-encoded path, it always starts with `/`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Sets the path, encoding characters that aren't allowed in it.
+    pub fn set_path(&mut self, path: &str) {
+        let path = normalize(path, is_path_char);
+        self.path = match path.starts_with('/') {
+            true => remove_dot_segments(&path),
+            false => remove_dot_segments(&format!("/{}", path)),
+        };
+    }
+
+    /// Iterates over the percent-decoded segments of the path.
+    pub fn path_segments(&self) -> impl Iterator<Item = Cow<'_, str>> {
+        self.path[1..].split('/').map(|s| decode(s, false))
+    }
+
+    /// Returns the percent-encoded query without the `?`.
+    pub fn query(&self) -> Option<&str> {
+        self.query.as_deref()
+    }
+
+    pub fn set_query(&mut self, query: Option<&str>) {
+        self.query = query.map(|q| normalize(q, is_query_char));
+    }
+
+    /// Iterates over the decoded `key=value` pairs of the query, `+` is decoded as a space.
+    pub fn query_pairs(&self) -> impl Iterator<Item = (Cow<'_, str>, Cow<'_, str>)> {
+        self.query
+            .as_deref()
+            .unwrap_or_default()
+            .split('&')
+            .filter(|p| !p.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (decode(key, true), decode(value, true))
+            })
+    }
+
+    /// Returns the first value of the query parameter `key`.
+    pub fn query_param(&self, key: &str) -> Option<Cow<'_, str>> {
+        self.query_pairs().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Parses the first value of the query parameter `key`.
+    pub fn typed_query<T: FromStr>(&self, key: &str) -> Result<Option<T>, Error> {
+        self.query_param(key)
+            .map(|v| {
+                v.parse()
+                    .map_err(|_| Error::InvalidUrl("Invalid query parameter"))
+            })
+            .transpose()
+    }
+
+    /// Appends `key=value` to the query, encoding both.
+    pub fn append_query_pair(&mut self, key: &str, value: &str) {
+        let query = self.query.get_or_insert_with(String::new);
+        if !query.is_empty() {
+            query.push('&');
+        }
+        query.push_str(&encode_component(key));
+        query.push('=');
+        query.push_str(&encode_component(value));
+    }
+
+    /// Returns the percent-encoded fragment without the `#`.
+    pub fn fragment(&self) -> Option<&str> {
+        self.fragment.as_deref()
+    }
+
+    pub fn set_fragment(&mut self, fragment: Option<&str>) {
+        self.fragment = fragment.map(|f| normalize(f, is_query_char));
+    }
+
+    /// Returns the path with the query, the target of a request.
+    pub fn path_and_query(&self) -> String {
+        match &self.query {
+            Some(query) => format!("{}?{}", self.path, query),
+            None => self.path.clone(),
+        }
+    }
+
+    /// Resolves a reference, e.g. a link, against this URL.
+    ///
+    /// The reference can be an absolute URL, a `//host/path` without the scheme, an absolute
+    /// path, a relative path, a query or a fragment.
+    pub fn join(&self, reference: &str) -> Result<Url, Error> {
+        let reference = reference.trim();
+        if has_scheme(reference) {
+            return Url::parse(reference);
+        }
+        if let Some(rest) = reference.strip_prefix("//") {
+            return Url::parse(&format!("{}://{}", self.scheme(), rest));
+        }
+
+        let (rest, fragment) = split_off(reference, '#');
+        let (path, query) = split_off(rest, '?');
+        let mut url = Url {
+            fragment: None,
+            ..self.clone()
+        };
+        url.set_fragment(fragment);
+
+        if path.is_empty() {
+            if query.is_some() {
+                url.set_query(query);
+            }
+            return Ok(url);
+        }
+
+        url.set_query(query);
+        match path.starts_with('/') {
+            true => url.set_path(path),
+            // Relative to the directory of the base path
+            false => {
+                let dir = &self.path[..=self.path.rfind('/').unwrap_or(0)];
+                url.set_path(&format!("{}{}", dir, path));
+            }
+        }
+        Ok(url)
+    }
+}
+
+impl FromStr for Url {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Url::parse(s)
+    }
+}
+
+impl TryFrom<&str> for Url {
+    type Error = Error;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Url::parse(value)
+    }
+}
+
+impl Display for Url {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}://{}{}",
+            self.scheme(),
+            self.host_header().encode(),
+            self.path
+        )?;
+        if let Some(query) = &self.query {
+            write!(f, "?{}", query)?;
+        }
+        if let Some(fragment) = &self.fragment {
+            write!(f, "#{}", fragment)?;
+        }
+        Ok(())
+    }
+}
+
+/// Splits `s` at the first `delimiter`, the second part is `None` without it.
+fn split_off(s: &str, delimiter: char) -> (&str, Option<&str>) {
+    match s.split_once(delimiter) {
+        Some((s, rest)) => (s, Some(rest)),
+        None => (s, None),
+    }
+}
+
+/// Whether a reference starts with a scheme, e.g. `aethon:`.
+fn has_scheme(reference: &str) -> bool {
+    let end = reference.find([':', '/', '?', '#']);
+    match end {
+        Some(i) if reference[i..].starts_with(':') => {
+            let scheme = &reference[..i];
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        }
+        _ => false,
+    }
+}
+
+fn is_unreserved(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"-._~".contains(&b)
+}
+
+fn is_path_char(b: u8) -> bool {
+    is_unreserved(b) || b"!$&'()*+,;=:@/".contains(&b)
+}
+
+fn is_query_char(b: u8) -> bool {
+    is_path_char(b) || b == b'?'
+}
+
+/// Percent-encodes bytes that aren't `allowed` and canonicalizes existing escapes.
+fn normalize(s: &str, allowed: fn(u8) -> bool) -> String {
+    let bytes = s.as_bytes();
+    let mut out = String::with_capacity(s.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+        match (b, hex_pair(&bytes[i + 1..])) {
+            (b'%', Some(decoded)) if is_unreserved(decoded) => {
+                out.push(decoded as char);
+                i += 3;
+                continue;
+            }
+            (b'%', Some(decoded)) => {
+                out.push_str(&format!("%{:02X}", decoded));
+                i += 3;
+                continue;
+            }
+            (b, _) if b != b'%' && allowed(b) => out.push(b as char),
+            (b, _) => out.push_str(&format!("%{:02X}", b)),
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Percent-encodes everything but unreserved characters, spaces become `+`.
+pub(crate) fn encode_component(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b' ' => out.push('+'),
+            b if is_unreserved(b) => out.push(b as char),
+            b => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+/// Decodes percent-encoded bytes, invalid UTF-8 is replaced.
+pub(crate) fn decode(s: &str, plus_as_space: bool) -> Cow<'_, str> {
+    if !(s.contains('%') || plus_as_space && s.contains('+')) {
+        return Cow::Borrowed(s);
+    }
+
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match (bytes[i], hex_pair(&bytes[i + 1..])) {
+            (b'%', Some(decoded)) => {
+                out.push(decoded);
+                i += 2;
+            }
+            (b'+', _) if plus_as_space => out.push(b' '),
+            (b, _) => out.push(b),
+        }
+        i += 1;
+    }
+    Cow::Owned(String::from_utf8_lossy(&out).into_owned())
+}
+
+/// Decodes two hex digits at the start of `bytes`.
+fn hex_pair(bytes: &[u8]) -> Option<u8> {
+    let hex = std::str::from_utf8(bytes.get(..2)?).ok()?;
+    match hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        true => u8::from_str_radix(hex, 16).ok(),
+        false => None,
+    }
+}
+
+/// Removes `.` and `..` segments from an absolute path.
+fn remove_dot_segments(path: &str) -> String {
+    let segments: Vec<&str> = path.split('/').skip(1).collect();
+    let mut out: Vec<&str> = Vec::with_capacity(segments.len());
+
+    for (i, segment) in segments.iter().enumerate() {
+        let last = i == segments.len() - 1;
+        match *segment {
+            "." => {}
+            ".." => {
+                out.pop();
+            }
+            segment => {
+                out.push(segment);
+                continue;
+            }
+        }
+        // A trailing dot segment leaves a directory
+        if last {
+            out.push("");
+        }
+    }
+
+    format!("/{}", out.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_url() {
+        let url = Url::parse("AETHON://Example:9000/a%20b/c?x=1&y=%7e#frag").unwrap();
+
+        // Tests
+        assert_eq!("example", url.host());
+        assert_eq!(9000, url.port());
+        assert_eq!("/a%20b/c", url.path());
+        assert_eq!(Some("x=1&y=~"), url.query());
+        assert_eq!(Some("frag"), url.fragment());
+        assert_eq!(vec!["a b", "c"], url.path_segments().collect::<Vec<_>>());
+        assert_eq!(
+            "aethon://example:9000/a%20b/c?x=1&y=~#frag",
+            url.to_string()
+        );
+    }
+
+    #[test]
+    fn test_canonical_form() {
+        let url: Url = "aethon://[::1]:8081/./a/../b c/%c3%a9".parse().unwrap();
+
+        // Tests
+        assert_eq!(8081, url.port());
+        assert_eq!("aethon://[::1]/b%20c/%C3%A9", url.to_string());
+        assert_eq!("é", url.path_segments().last().unwrap());
+        assert_eq!(
+            "aethon://example/",
+            Url::parse("aethon://example").unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_secure_scheme() {
+        let url = Url::parse("aethons://example:8443/a").unwrap();
+
+        // Tests
+        assert!(url.is_secure());
+        assert_eq!("aethons", url.scheme());
+        assert_eq!(8443, url.port());
+        assert_eq!("aethons://example/a", url.to_string());
+        assert_eq!(
+            "aethons://other/b",
+            url.join("//other/b").unwrap().to_string()
+        );
+        assert!(!url.join("aethon://example/").unwrap().is_secure());
+    }
+
+    #[test]
+    fn test_invalid_urls() {
+        // Tests
+        for url in [
+            "http://example/",
+            "aethon://",
+            "aethon://user@example/",
+            "aethon://example:port/",
+            "example/path",
+        ] {
+            assert!(Url::parse(url).is_err(), "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_query_params() {
+        let mut url: Url = "aethon://example/search?q=hello+world&page=2&empty"
+            .parse()
+            .unwrap();
+        url.append_query_pair("lang", "c & rust");
+
+        // Tests
+        assert_eq!(Some("hello world".into()), url.query_param("q"));
+        assert_eq!(Ok(Some(2)), url.typed_query::<u32>("page"));
+        assert_eq!(Some("".into()), url.query_param("empty"));
+        assert_eq!(Ok(None), url.typed_query::<u32>("missing"));
+        assert!(url.typed_query::<u32>("q").is_err());
+        assert_eq!(Some("c & rust".into()), url.query_param("lang"));
+        assert_eq!(
+            "/search?q=hello+world&page=2&empty&lang=c+%26+rust",
+            url.path_and_query()
+        );
+    }
+
+    #[test]
+    fn test_join() {
+        let base: Url = "aethon://a/b/c/d?q#f".parse().unwrap();
+        let join = |reference| base.join(reference).unwrap().to_string();
+
+        // Tests, the examples of RFC 3986
+        assert_eq!("aethon://g/", join("aethon://g"));
+        assert_eq!("aethon://a/b/c/g", join("g"));
+        assert_eq!("aethon://a/b/c/g", join("./g"));
+        assert_eq!("aethon://a/b/c/g/", join("g/"));
+        assert_eq!("aethon://a/g", join("/g"));
+        assert_eq!("aethon://g/", join("//g"));
+        assert_eq!("aethon://a/b/c/d?y", join("?y"));
+        assert_eq!("aethon://a/b/c/g?y", join("g?y"));
+        assert_eq!("aethon://a/b/c/d?q#s", join("#s"));
+        assert_eq!("aethon://a/b/c/g?y#s", join("g?y#s"));
+        assert_eq!("aethon://a/b/c/d?q", join(""));
+        assert_eq!("aethon://a/b/c/", join("."));
+        assert_eq!("aethon://a/b/", join(".."));
+        assert_eq!("aethon://a/b/g", join("../g"));
+        assert_eq!("aethon://a/", join("../.."));
+        assert_eq!("aethon://a/g", join("../../../g"));
+        assert!(base.join("http://example/").is_err());
+    }
+}
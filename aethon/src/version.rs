@@ -0,0 +1,195 @@
+use super::{
+    headers::{invalid, TypedHeader},
+    Error, Request, Response, Status,
+};
+use std::{fmt, str::FromStr};
+
+/// Version of the protocol, written in decimal at the start of every packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version(u8);
+
+impl Version {
+    pub const V1: Version = Version(1);
+
+    /// Returns the version's number.
+    pub fn get(&self) -> u8 {
+        self.0
+    }
+}
+
+/// Version `0` doesn't exist, packets using it fail to build and parse.
+impl From<u8> for Version {
+    fn from(version: u8) -> Self {
+        Version(version)
+    }
+}
+
+impl FromStr for Version {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // u8::from_str accepts a leading +
+        if !s.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(Error::WrongVersion);
+        }
+
+        match s.parse() {
+            Ok(0) | Err(_) => Err(Error::WrongVersion),
+            Ok(version) => Ok(Version(version)),
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A set of versions, e.g. the ones a server supports.
+///
+/// It's also the `Aethon-Versions` header, which peers send to advertise their versions:
+///
+/// 1. The client sends requests in a version it expects the server to support, usually `1`.
+/// 2. If the server supports it, it answers in the same version and advertises its own versions,
+///    the client can then switch to the highest common one with [`Versions::highest_common`].
+/// 3. Otherwise the server answers with `505 Version Not Supported`, see [`Versions::check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Versions(Vec<Version>);
+
+impl Versions {
+    pub fn new(versions: impl IntoIterator<Item = Version>) -> Self {
+        let mut versions: Vec<Version> = versions.into_iter().collect();
+        versions.sort();
+        versions.dedup();
+        Self(versions)
+    }
+
+    pub fn contains(&self, version: Version) -> bool {
+        self.0.binary_search(&version).is_ok()
+    }
+
+    pub fn highest(&self) -> Option<Version> {
+        self.0.last().copied()
+    }
+
+    /// Iterates over the versions from the lowest.
+    pub fn iter(&self) -> impl Iterator<Item = Version> + '_ {
+        self.0.iter().copied()
+    }
+
+    /// Returns the highest version in both sets.
+    pub fn highest_common(&self, other: &Versions) -> Option<Version> {
+        self.0.iter().rev().find(|v| other.contains(**v)).copied()
+    }
+
+    /// Checks the version of a request, returning the response to send if it isn't supported.
+    pub fn check(&self, req: &Request) -> Result<(), Response> {
+        match self.contains(req.version()) {
+            true => Ok(()),
+            false => Err(self.unsupported(req.version())),
+        }
+    }
+
+    /// Builds the `505 Version Not Supported` response to a packet in the version `requested`.
+    ///
+    /// It's written in the highest supported version older than `requested`, so the client can
+    /// most likely read it, and advertises the supported versions.
+    pub fn unsupported(&self, requested: Version) -> Response {
+        let version = self
+            .0
+            .iter()
+            .rev()
+            .find(|v| **v < requested)
+            .or(self.0.first())
+            .copied()
+            .unwrap_or(Version::V1);
+
+        let mut res = Response::new(version, Status::VersionNotSupported, Default::default(), "");
+        res.headers_mut().typed_insert(self.clone());
+        res
+    }
+}
+
+impl Default for Versions {
+    /// Only version `1`.
+    fn default() -> Self {
+        Self(vec![Version::V1])
+    }
+}
+
+impl TypedHeader for Versions {
+    const NAME: &'static str = "Aethon-Versions";
+
+    fn decode(value: &str) -> Result<Self, Error> {
+        value
+            .split(',')
+            .map(|v| {
+                v.trim()
+                    .parse()
+                    .map_err(|_| invalid::<Self>("expected a list of versions"))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Versions::new)
+    }
+
+    fn encode(&self) -> String {
+        self.0
+            .iter()
+            .map(Version::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Headers, Method};
+
+    #[test]
+    fn test_multi_digit_versions() {
+        let req = Request::new(12, Method::GET, "/", Headers::default(), "");
+        let s = req.to_string();
+
+        // Tests
+        assert_eq!("12 GET /\n\n\n", s);
+        assert_eq!(Version::from(12), s.parse::<Request>().unwrap().version());
+        assert_eq!(Ok(Version::from(255)), "255".parse());
+    }
+
+    #[test]
+    fn test_invalid_versions() {
+        // Tests
+        for v in ["0", "256", "+1", "", "v1", "1.0"] {
+            assert_eq!(Err(Error::WrongVersion), v.parse::<Version>());
+        }
+        assert!("0 GET /\n\n\n".parse::<Request>().is_err());
+    }
+
+    #[test]
+    fn test_versions_header() {
+        let versions = Versions::decode("2, 1, 2").unwrap();
+
+        // Tests
+        assert_eq!(Versions::new([Version::V1, Version::from(2)]), versions);
+        assert_eq!("1, 2", versions.encode());
+        assert!(Versions::decode("1, two").is_err());
+    }
+
+    #[test]
+    fn test_negotiation() {
+        let server = Versions::new([Version::V1, Version::from(2)]);
+        let client = Versions::new([Version::V1, Version::from(2), Version::from(3)]);
+        let old = Request::builder().version(Version::V1).build().unwrap();
+        let new = Request::builder().version(3).build().unwrap();
+
+        // Tests
+        assert_eq!(Some(Version::from(2)), client.highest_common(&server));
+        assert_eq!(Ok(()), server.check(&old));
+
+        let res = server.check(&new).unwrap_err();
+        assert_eq!(Status::VersionNotSupported, res.status());
+        assert_eq!(Version::from(2), res.version());
+        assert_eq!(Ok(Some(server)), res.headers().typed_get::<Versions>());
+    }
+}
@@ -1,6 +1,13 @@
-use aethon::{Method, Request};
+use aethon::{serve, Request, Response, ServeOptions, TlsAcceptor};
+use std::{
+    env,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
 use tokio::{
-    io::{self, AsyncReadExt},
+    io::{self, AsyncRead, AsyncWrite},
     net::TcpListener,
 };
 
@@ -10,14 +17,67 @@ async fn main() -> io::Result<()> {
         .await
         .expect("Failed to bind the listener");
 
-    let mut i = 0u32;
+    let i = Arc::new(AtomicU32::new(0));
+
+    // aethons:// is served too when a certificate is given
+    if let (Some(cert), Some(key)) = (
+        env::var_os("APOLLO_TLS_CERT"),
+        env::var_os("APOLLO_TLS_KEY"),
+    ) {
+        let acceptor =
+            TlsAcceptor::from_pem_files(cert, key).expect("Failed to load the TLS certificate");
+        let tls_listener = TcpListener::bind("127.0.0.1:8443")
+            .await
+            .expect("Failed to bind the TLS listener");
+        let i = Arc::clone(&i);
+
+        tokio::spawn(async move {
+            loop {
+                let socket = match tls_listener.accept().await {
+                    Ok((socket, _)) => socket,
+                    Err(e) => {
+                        eprintln!("Failed to accept a connection: {e}");
+                        continue;
+                    }
+                };
+                let acceptor = acceptor.clone();
+                let i = Arc::clone(&i);
+
+                tokio::spawn(async move {
+                    match acceptor.accept(socket).await {
+                        Ok(stream) => handle(stream, i).await,
+                        Err(e) => eprintln!("TLS handshake failed: {e}"),
+                    }
+                });
+            }
+        });
+    }
 
     loop {
-        let (mut socket, _) = listener.accept().await?;
-        let mut buffer = [0u8; 1204];
-        let n = socket.read(&mut buffer).await?;
-        Request::try_from(&buffer[..n]).unwrap();
-        i += 1;
-        println!("{i}");
+        let (socket, _) = listener.accept().await?;
+        tokio::spawn(handle(socket, Arc::clone(&i)));
+    }
+}
+
+async fn handle<IO>(io: IO, i: Arc<AtomicU32>)
+where
+    IO: AsyncRead + AsyncWrite + Unpin,
+{
+    let handler = |request: Request| {
+        println!(
+            "{} {} {}",
+            i.fetch_add(1, Ordering::Relaxed) + 1,
+            request.method(),
+            request.path()
+        );
+        async {
+            Response::builder()
+                .build()
+                .expect("Failed to build the response")
+        }
+    };
+
+    if let Err(e) = serve(io, ServeOptions::default(), handler).await {
+        eprintln!("Failed to read the request: {e}");
     }
 }
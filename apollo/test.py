@@ -47,7 +47,7 @@ def send_message(thread_id):
         client_socket.connect(("localhost", 8081))
 
         # Prepare the message
-        message = "1 GET /\n\n\nHello World"
+        message = "1 GET /\nContent-Length: 11\n\nHello World"
 
         # Send the message
         bytes_sent = client_socket.send(message.encode())